@@ -0,0 +1,56 @@
+//! Parses a generated high-degree graph, where every segment is linked to a handful of
+//! hubs, to keep an eye on the per-link cost of looking up the segments of a bridge.
+//!
+//! Run with `cargo bench --bench bridge_lookups`. To compare against another revision,
+//! run it there with `-- --save-baseline before` first, then here with `-- --baseline before`.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use parfait_gfa::gfa::{GfaParser, ParseOptions};
+
+const SEGMENTS: usize = 50_000;
+const HUBS: usize = 20;
+
+fn write_hub_graph(path: &Path) -> std::io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+
+    writeln!(file, "H\tVN:Z:1.0")?;
+    for i in 0..SEGMENTS {
+        writeln!(file, "S\ts{i}\tACGTACGT")?;
+    }
+    for i in HUBS..SEGMENTS {
+        for hub in 0..HUBS {
+            writeln!(file, "L\ts{hub}\t+\ts{i}\t+\t4M")?;
+        }
+    }
+
+    file.flush()
+}
+
+fn parse_hub_graph(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("parfait_gfa_bridge_lookups.gfa");
+    write_hub_graph(&path).expect("benchmark input should be writable");
+
+    let options = ParseOptions::default();
+
+    let mut group = c.benchmark_group("bridges");
+    group.sample_size(10);
+    group.bench_function("1M links onto 20 hubs", |b| {
+        b.iter(|| {
+            let mut gfa = GfaParser::new();
+            let _ = gfa.parse(&path, &options);
+            gfa
+        })
+    });
+    group.finish();
+
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, parse_hub_graph);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+//! Compares serial and parallel parsing of a generated graph with 1M segments,
+//! on every core and on a fixed number of threads.
+//!
+//! Run with `cargo bench --bench parallel_parse`.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use parfait_gfa::gfa::{GfaParser, ParseOptions};
+
+const SEGMENTS: usize = 1_000_000;
+
+fn main() -> std::io::Result<()> {
+    let path = std::env::temp_dir().join("parfait_gfa_parallel_parse.gfa");
+
+    let mut file = BufWriter::new(File::create(&path)?);
+    writeln!(file, "H\tVN:Z:1.0")?;
+    for i in 0..SEGMENTS {
+        writeln!(file, "S\ts{i}\tACGTACGTACGTACGTACGTACGTACGTACGT\tRC:i:{i}")?;
+    }
+    for i in 1..SEGMENTS {
+        writeln!(file, "L\ts{}\t+\ts{i}\t+\t4M", i - 1)?;
+    }
+    file.flush()?;
+
+    for threads in [1, 0, 2, 4] {
+        let options = ParseOptions {
+            threads,
+            ..ParseOptions::default()
+        };
+
+        let mut gfa = GfaParser::new();
+        let start = Instant::now();
+        let _ = gfa.parse(&path, &options);
+
+        println!(
+            "threads: {threads}  segments: {}  time: {:?}",
+            gfa.segments().count(),
+            start.elapsed()
+        );
+    }
+
+    std::fs::remove_file(&path)
+}
@@ -0,0 +1,84 @@
+//! Parses `test/gfa_working.gfa` replicated to roughly 500k lines, to keep an eye on
+//! the per-line allocations of the parse loop.
+//!
+//! Run with `cargo bench --bench parse_allocations`.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use parfait_gfa::gfa::{GfaParser, ParseOptions};
+
+const TARGET_LINES: usize = 500_000;
+
+// every copy gets its own names, so the replicated graph has no collisions
+fn rename_copy(line: &str, copy: usize) -> String {
+    let mut parts: Vec<String> = line.split('\t').map(str::to_string).collect();
+    let suffix = |name: &str| format!("{name}_{copy}");
+
+    match parts[0].as_str() {
+        "S" => parts[1] = suffix(&parts[1]),
+        "L" => {
+            parts[1] = suffix(&parts[1]);
+            parts[3] = suffix(&parts[3]);
+        }
+        "P" => {
+            parts[1] = suffix(&parts[1]);
+            parts[2] = parts[2]
+                .split(',')
+                .map(|step| {
+                    let (name, orientation) = step.split_at(step.len() - 1);
+                    format!("{}{orientation}", suffix(name))
+                })
+                .collect::<Vec<_>>()
+                .join(",");
+        }
+        _ => {}
+    }
+
+    parts.join("\t")
+}
+
+fn write_replicated(path: &Path) -> std::io::Result<()> {
+    let source = std::fs::read_to_string("test/gfa_working.gfa")?;
+    let (headers, body): (Vec<&str>, Vec<&str>) =
+        source.lines().partition(|line| line.starts_with('H'));
+
+    let mut file = BufWriter::new(File::create(path)?);
+    for header in &headers {
+        writeln!(file, "{header}")?;
+    }
+    for copy in 0..TARGET_LINES / body.len() {
+        for line in &body {
+            writeln!(file, "{}", rename_copy(line, copy))?;
+        }
+    }
+
+    file.flush()
+}
+
+fn parse_replicated(c: &mut Criterion) {
+    let path = std::env::temp_dir().join("parfait_gfa_parse_allocations.gfa");
+    write_replicated(&path).expect("benchmark input should be writable");
+
+    let options = ParseOptions::default();
+
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    group.bench_function("gfa_working x500k lines", |b| {
+        b.iter(|| {
+            let mut gfa = GfaParser::new();
+            let _ = gfa.parse(&path, &options);
+            gfa
+        })
+    });
+    group.finish();
+
+    let _ = std::fs::remove_file(&path);
+}
+
+criterion_group!(benches, parse_replicated);
+criterion_main!(benches);
@@ -1,21 +1,26 @@
 use owo_colors::{AnsiColors, OwoColorize};
-use std::fmt::Write;
+use std::{fmt::Write, io::IsTerminal};
 
 #[derive(Debug, Clone, Default)]
 pub struct ParseMessage {
     pub line: usize,
     pub code: ParseMessageCode,
     pub offender: String,
+    /// 1-based byte column of the problem within the line, when it is known.
+    pub column: Option<usize>,
+    /// Replaces the severity that comes with the code, for codes whose severity
+    /// depends on the parse options.
+    pub severity: Option<ParseMessageSeverity>,
 }
 
 /// Severity levels for parse errors.
-/// 
+///
 /// - Info: something to consider
 /// - Warn: something that's not ideal
 /// - Severe: something that could break other tools, but can still be parsed
 /// - Error: something that cannot be parsed, skip this line
 /// - Fatal: whole file is cooked
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ParseMessageSeverity {
     Info,
     Warn,
@@ -34,6 +39,16 @@ impl ParseMessageSeverity {
             ParseMessageSeverity::Fatal => "X",
         }
     }
+    pub fn name(&self) -> &str {
+        match self {
+            ParseMessageSeverity::Info => "info",
+            ParseMessageSeverity::Warn => "warn",
+            ParseMessageSeverity::Severe => "severe",
+            ParseMessageSeverity::Error => "error",
+            ParseMessageSeverity::Fatal => "fatal",
+        }
+    }
+
     pub fn to_char(&self) -> char {
         match self {
             ParseMessageSeverity::Info => 'i',
@@ -73,11 +88,14 @@ pub enum ParseMessageCode {
     RedundantSegmentLengthTagMismatch,
     InvalidSequence,
     IndeterminateSegmentLength,
+    IncompleteRGFATags,
     SegmentNotFound,
     InvalidOrientation,
     InvalidCIGAR,
+    OverlapExceedsSegment,
     InvalidJumpDistance,
     InvalidShortcut,
+    ShortcutJumpHasDistance,
     InvalidID,
     InvalidPosition,
     InvalidContainmentPositionRange,
@@ -85,6 +103,7 @@ pub enum ParseMessageCode {
     SelfContainment,
     IsolatedSegment,
     DeadEndTip,
+    GraphContainsCycle,
     SelfBridge,
     PathOverlapLengthMismatch,
     InvalidPath,
@@ -106,12 +125,21 @@ pub enum ParseMessageCode {
     InvalidIntervalPositionSentinel,
     MissingIntervalPositionSentinel,
     InvalidAlignment,
+    MissingTraceSpacing,
     RedundantEdgeIDTag,
     EdgeIDTagUsedInAnonEdge,
+    EdgeIntervalLengthMismatch,
     InvalidGapDistance,
     InvalidVariance,
     GroupMemberNotFound,
     InvalidGroup,
+    LossyConversion,
+    DuplicateLinkRemoved,
+    MergedSegmentMismatch,
+    NonNucleotideSequence,
+    TooManyErrors,
+    GraphHasCycle,
+    NotAGfaFile,
 }
 
 impl std::fmt::Display for ParseMessageCode {
@@ -159,44 +187,109 @@ impl ParseMessage {
             line,
             code,
             offender,
+            column: None,
+            severity: None,
+        }
+    }
+
+    /// Points the message at a 1-based byte column within the line.
+    pub fn with_column(mut self, column: usize) -> Self {
+        self.column = Some(column);
+        self
+    }
+
+    /// Reports the message with `severity` instead of the one that comes with its code.
+    pub fn with_severity(mut self, severity: ParseMessageSeverity) -> Self {
+        self.severity = Some(severity);
+        self
+    }
+
+    // "line 5" or "line 5, column 12"
+    fn location(&self) -> String {
+        match self.column {
+            Some(column) => format!("line {}, column {column}", self.line),
+            None => format!("line {}", self.line),
         }
     }
 
     fn formatted(&self) -> String {
+        self.layout(true)
+    }
+
+    /// Same layout as [`ParseMessage::print_formatted_error`], without any ANSI colour codes.
+    pub fn formatted_plain(&self) -> String {
+        self.layout(false)
+    }
+
+    fn layout(&self, coloured: bool) -> String {
         let (severity, message) = self.get_message();
 
-        let header = severity.header();
-        let code = severity.body(format!("[parfait-gfa] {:?}", self.code));
-        let context = severity.body(format!(
-            "while parsing {}{} on line {}",
+        let header = format!("[{}]", severity.as_str());
+        let code = format!("[parfait-gfa] {:?}", self.code);
+        let context = format!(
+            "while parsing {}{} on {}",
             self.offender.chars().take(256).collect::<String>(),
             if self.offender.len() > 256 { "..." } else { "" },
-            self.line
-        ));
-        let msg = severity.body(message);
+            self.location()
+        );
 
         let mut out = String::new();
 
-        writeln!(&mut out, "{} {}", header.bold(), code.bold()).unwrap();
-        writeln!(&mut out, "{msg}").unwrap();
-        writeln!(&mut out, "{}", context.italic()).unwrap();
+        if coloured {
+            let code = severity.body(code);
+            writeln!(&mut out, "{} {}", severity.header().bold(), code.bold()).unwrap();
+            writeln!(&mut out, "{}", severity.body(message)).unwrap();
+            writeln!(&mut out, "{}", severity.body(context).italic()).unwrap();
+        } else {
+            writeln!(&mut out, "{header} {code}").unwrap();
+            writeln!(&mut out, "{message}").unwrap();
+            writeln!(&mut out, "{context}").unwrap();
+        }
+
         writeln!(&mut out).unwrap();
         out
     }
 
+    /// Prints the message to stdout, coloured only if stdout is a terminal and
+    /// `NO_COLOR` is not set (see <https://no-color.org>).
     pub fn print_formatted_error(&self) {
-        let formatted_error: String = self.formatted();
+        let formatted_error: String = if use_colour() {
+            self.formatted()
+        } else {
+            self.formatted_plain()
+        };
         print!("{formatted_error}");
     }
 
+    /// Formats the message as a single-line JSON object, e.g.
+    /// `{"line":5,"code":"UnexpectedReservedTagType","severity":"warn","message":"...","offender":"foo"}`.
+    /// A `"column"` key follows `"line"` when the column is known.
+    pub fn to_json(&self) -> String {
+        let (severity, message) = self.get_message();
+
+        let column = self
+            .column
+            .map(|c| format!(",\"column\":{c}"))
+            .unwrap_or_default();
+
+        format!(
+            "{{\"line\":{}{column},\"code\":\"{:?}\",\"severity\":\"{}\",\"message\":{},\"offender\":{}}}",
+            self.line,
+            self.code,
+            severity.name(),
+            json_string(&message),
+            json_string(&self.offender),
+        )
+    }
+
     // TODO: rework entire error system
     // right now errors are missing...
     // - custom info at the time
     // - raw is unused
-    // - line number doesn't bubble up
+    // - columns are only known for a few codes
 
     pub fn get_message(&self) -> (ParseMessageSeverity, String) {
-        match self.code {
+        let (severity, message) = match self.code {
             ParseMessageCode::InvalidLine => (
                 ParseMessageSeverity::Error,
                 "failed to parse line".to_string(),
@@ -297,10 +390,30 @@ impl ParseMessage {
                 ParseMessageSeverity::Severe,
                 "sequence must match * or [!-~]+".to_string(),
             ),
+            ParseMessageCode::NonNucleotideSequence => (
+                ParseMessageSeverity::Severe,
+                "sequence contains a character that is not an IUPAC nucleotide code".to_string(),
+            ),
+            ParseMessageCode::TooManyErrors => (
+                ParseMessageSeverity::Fatal,
+                "too many errors; stopped parsing".to_string(),
+            ),
+            ParseMessageCode::GraphHasCycle => (
+                ParseMessageSeverity::Error,
+                "segments form a cycle, so they have no topological order".to_string(),
+            ),
+            ParseMessageCode::NotAGfaFile => (
+                ParseMessageSeverity::Fatal,
+                "file does not look like GFA (is it BAM, FASTA or FASTQ?); stopped parsing".to_string(),
+            ),
             ParseMessageCode::IndeterminateSegmentLength => (
                 ParseMessageSeverity::Severe,
                 "sequence was not provided (*) and no length tag (LN) was found; defaulting length to 1".to_string(),
             ),
+            ParseMessageCode::IncompleteRGFATags => (
+                ParseMessageSeverity::Warn,
+                "rGFA segments need all of SN, SO and SR; ignoring the partial reference coordinate".to_string(),
+            ),
             ParseMessageCode::SegmentNotFound => (
                 ParseMessageSeverity::Severe,
                 "referenced segment does not exist within the graph".to_string(),
@@ -313,10 +426,18 @@ impl ParseMessage {
                 ParseMessageSeverity::Severe,
                 "overlap CIGAR string must match /[0-9]+[MIDNSHPX=]/; defaulting to *".to_string(),
             ),
+            ParseMessageCode::OverlapExceedsSegment => (
+                ParseMessageSeverity::Severe,
+                "overlap is longer than one of the segments it joins".to_string(),
+            ),
             ParseMessageCode::InvalidJumpDistance => (
                 ParseMessageSeverity::Severe,
                 "jump distance must be a signed integer or omitted; defaulting to *".to_string(),
             ),
+            ParseMessageCode::EdgeIntervalLengthMismatch => (
+                ParseMessageSeverity::Warn,
+                "edge intervals have different lengths without an alignment, or lengths other than the ones their CIGAR covers".to_string(),
+            ),
             ParseMessageCode::InvalidGapDistance => (
                 ParseMessageSeverity::Severe,
                 "gap distance must be a signed integer; defaulting to 0".to_string(),
@@ -325,6 +446,10 @@ impl ParseMessage {
                 ParseMessageSeverity::Severe,
                 "jump shortcut must be either 0 or 1; defaulting to 0".to_string(),
             ),
+            ParseMessageCode::ShortcutJumpHasDistance => (
+                ParseMessageSeverity::Warn,
+                "shortcut jump (SC:i:1) has a distance; a shortcut's distance is derived from the path it skips, so it should be *".to_string(),
+            ),
             ParseMessageCode::InvalidPosition => (
                 ParseMessageSeverity::Severe,
                 "position must be a non-negative integer; defaulting to 0".to_string(),
@@ -343,7 +468,11 @@ impl ParseMessage {
             ),
             ParseMessageCode::DeadEndTip => (
                 ParseMessageSeverity::Info,
-                "segment is a dead-end tip (missing an outgoing or incoming bridge, or both)".to_string(),
+                "segment is a dead-end tip (one of its sides has no bridges)".to_string(),
+            ),
+            ParseMessageCode::GraphContainsCycle => (
+                ParseMessageSeverity::Info,
+                "segments form a cycle; the graph is not a DAG".to_string(),
             ),
             ParseMessageCode::SelfBridge => (
                 ParseMessageSeverity::Warn,
@@ -433,6 +562,10 @@ impl ParseMessage {
                 ParseMessageSeverity::Severe,
                 "alignment must be either CIGAR or Trace; defaulting to *".to_string(),
             ),
+            ParseMessageCode::MissingTraceSpacing => (
+                ParseMessageSeverity::Warn,
+                "trace alignment used without a trace spacing (TS) tag in the header".to_string(),
+            ),
             ParseMessageCode::RedundantEdgeIDTag => (
                 ParseMessageSeverity::Warn,
                 "redundant edge_id tag (ID) in v2 edge/gap. the id column was not omitted, so this tag is not needed; ignoring tag".to_string(),
@@ -449,11 +582,25 @@ impl ParseMessage {
                 ParseMessageSeverity::Severe,
                 "group member not found in namespace".to_string(),
             ),
+            ParseMessageCode::LossyConversion => (
+                ParseMessageSeverity::Info,
+                "record could not be fully represented in the target GFA version".to_string(),
+            ),
+            ParseMessageCode::DuplicateLinkRemoved => (
+                ParseMessageSeverity::Info,
+                "removed a link identical to an earlier one".to_string(),
+            ),
+            ParseMessageCode::MergedSegmentMismatch => (
+                ParseMessageSeverity::Warn,
+                "merged segments with the same name have different sequences; keeping the first".to_string(),
+            ),
             ParseMessageCode::InvalidGroup => (
                 ParseMessageSeverity::Severe,
                 "could not parse group; skipping group line".to_string(),
             ),
-        }
+        };
+
+        (self.severity.unwrap_or(severity), message)
     }
 
     pub fn severity(&self) -> ParseMessageSeverity {
@@ -462,6 +609,33 @@ impl ParseMessage {
     }
 }
 
+// NO_COLOR only counts when it is set to something
+fn use_colour() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && std::io::stdout().is_terminal()
+}
+
+// quotes and escapes a string for JSON output
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(&mut out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+
+    out.push('"');
+    out
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -488,6 +662,8 @@ mod tests {
             line: 5,
             code: ParseMessageCode::UnexpectedReservedTagType,
             offender: "foo".into(),
+            column: None,
+            severity: None,
         };
 
         let out = err.formatted();
@@ -495,4 +671,47 @@ mod tests {
         assert!(out.contains("this tag type is not expected in this context"));
         assert!(out.contains("?"));
     }
+
+    #[test]
+    fn plain_formatting_has_no_ansi_codes() {
+        let err = ParseMessage {
+            line: 5,
+            code: ParseMessageCode::UnexpectedReservedTagType,
+            offender: "foo".into(),
+            column: Some(3),
+            severity: None,
+        };
+
+        let out = err.formatted_plain();
+        assert!(!out.contains("\u{1b}["));
+        assert!(out.starts_with("[?] [parfait-gfa] UnexpectedReservedTagType\n"));
+        assert!(out.contains("while parsing foo on line 5, column 3"));
+    }
+
+    #[test]
+    fn json_contains_message_and_escapes_offender() {
+        let err = ParseMessage {
+            line: 5,
+            code: ParseMessageCode::UnexpectedReservedTagType,
+            offender: "a\t\"b\"".into(),
+            column: None,
+            severity: None,
+        };
+
+        assert_eq!(
+            err.to_json(),
+            "{\"line\":5,\"code\":\"UnexpectedReservedTagType\",\"severity\":\"warn\",\
+             \"message\":\"this tag type is not expected in this context\",\
+             \"offender\":\"a\\t\\\"b\\\"\"}"
+        );
+    }
+
+    #[test]
+    fn column_is_rendered_when_known() {
+        let err =
+            ParseMessage::new(5, ParseMessageCode::InvalidPathStep, "x".into()).with_column(12);
+
+        assert!(err.formatted().contains("on line 5, column 12"));
+        assert!(err.to_json().starts_with("{\"line\":5,\"column\":12,"));
+    }
 }
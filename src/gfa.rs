@@ -6,17 +6,47 @@ use std::{
 };
 
 use clap::ValueEnum;
+use rayon::prelude::*;
 
 use crate::{
     errors::{ParseMessage, ParseMessageCode, ParseMessageSeverity},
     impl_enum_find_accessors,
     line::{
-        containment::Containment, edge::Edge, fragment::Fragment, gap::Gap, header::Header,
-        jump::Jump, link::Link, ordered::OrderedGroup, path::Path, record::GfaRecord,
-        segment::Segment, unordered::UnorderedGroup, walk::Walk,
+        BridgeEnds, BridgeRef, BridgeType, check_bridge_ends,
+        containment::Containment,
+        edge::Edge,
+        fragment::Fragment,
+        gap::Gap,
+        header::Header,
+        jump::Jump,
+        link::Link,
+        ordered::OrderedGroup,
+        path::{Path, Step},
+        record::{GfaRecord, unknown_line},
+        resolve_bridge_ends,
+        segment::{Segment, SegmentSide},
+        symbol::{Symbol, SymbolTable},
+        unordered::UnorderedGroup,
+        utils::{check_interval, cigar_query_length, cigar_reference_length, is_valid_name},
+        walk::Walk,
+        wire_bridge,
     },
+    optional_field::{FLAG_TAG, OptionalFieldValue, TagMap},
 };
 
+mod builder;
+mod convert;
+mod edit;
+mod graph;
+mod merge;
+mod mmap;
+mod stats;
+mod stream;
+mod subgraph;
+
+pub use builder::GfaBuilder;
+pub use stats::GraphStats;
+
 #[derive(Debug, Default)]
 pub struct GfaParser {
     pub records: Vec<GfaRecord>,
@@ -24,11 +54,36 @@ pub struct GfaParser {
     pub tag_names: HashSet<String>,
     pub version: GFAVersion,
     pub trace: Option<String>,
+    /// Number of `#` comment lines read so far.
+    pub comment_count: usize,
+    /// Number of empty (or whitespace only) lines read so far.
+    pub blank_line_count: usize,
+    /// Comment lines and their line numbers, only collected with
+    /// [`ParseOptions::capture_comments`].
+    pub comments: Vec<(usize, String)>,
 
     namespace: HashMap<String, u32>,
     records_index: HashMap<usize, usize>,
     namespace_index: HashMap<String, usize>,
+    symbols: SymbolTable,
+    walk_ranges: HashMap<WalkKey, WalkRanges>,
     max_lines: usize,
+    // (line_no, line) queued by add_line_deferred
+    deferred: Vec<(usize, String)>,
+}
+
+// (sample_id, hap_index, seq_id) of a walk
+type WalkKey = (String, u32, String);
+// (seq_start, seq_end, line_no) of a walk
+type WalkRange = (u32, u32, usize);
+
+// ranges of the walks of one sequence, sorted by seq_start. max_end[i] is the largest
+// seq_end in ranges[..=i], so an overlap search can stop as soon as nothing before
+// the current range reaches far enough
+#[derive(Debug, Default)]
+struct WalkRanges {
+    ranges: Vec<WalkRange>,
+    max_end: Vec<u32>,
 }
 
 impl GfaParser {
@@ -67,32 +122,11 @@ impl GfaParser {
     ) -> Result<(), Vec<ParseMessage>> {
         let path_buf = path.into();
 
-        // dont run on a directory
-        if path_buf.is_dir() {
-            self.messages.push(ParseMessage::new(
-                0,
-                ParseMessageCode::DirectoryError,
-                path_buf.to_string_lossy().to_string(),
-            ));
-            return Err(self.messages.clone());
-        }
-
-        let file = match File::open(&path_buf) {
-            Ok(f) => BufReader::new(f),
-            Err(_) => {
-                self.messages.push(ParseMessage::new(
-                    0,
-                    ParseMessageCode::IOError,
-                    path_buf.to_string_lossy().to_string(),
-                ));
-                return Err(self.messages.clone());
-            }
-        };
+        let file = self.open_gfa_file(&path_buf)?;
 
         let mut raw_lines: Vec<(usize, String)> = Vec::new();
-        let mut line_no = 1;
 
-        for line in file.lines() {
+        for (line_no, line) in (1..).zip(file.lines()) {
             match line {
                 Ok(l) => raw_lines.push((line_no, l)),
                 Err(_) => self.messages.push(ParseMessage::new(
@@ -101,43 +135,63 @@ impl GfaParser {
                     "(unable to read line)".into(),
                 )),
             }
-            line_no += 1;
         }
 
+        self.parse_lines(&raw_lines, options);
+
+        if !self.stopped_early() {
+            self.check_header(&path_buf);
+            self.add_info_errors(options);
+        }
+
+        self.fatal_messages()
+    }
+
+    // runs the parse passes over lines that have already been read
+    fn parse_lines<S: AsRef<str> + Sync>(
+        &mut self,
+        raw_lines: &[(usize, S)],
+        options: &ParseOptions,
+    ) {
         self.max_lines = raw_lines.len();
 
+        if !self.check_looks_like_gfa(raw_lines, options) {
+            return;
+        }
+
         // TODO: is there a better way to preallocate?
         self.records.reserve(raw_lines.len());
         self.namespace_index = HashMap::with_capacity(raw_lines.len());
 
-        // pass 0: parse headers
-        // pass 1: parse segments
-        // pass 2: parse bridges (links/containments/jumps/gaps/edges/fragments)
-        // pass 3: parse trails (paths/walks/groups)
+        let mut cap = ErrorCap::new(self, options);
+
+        for (idx, line) in raw_lines {
+            self.note_unparsed_line(*idx, line.as_ref(), options);
+        }
+
+        if cap.exceeded(&mut self.messages) {
+            return;
+        }
+
+        // one split buffer for every line, rather than one allocation per line
+        let mut parts: Vec<&str> = Vec::with_capacity(16);
 
+        // records are parsed in passes so that everything they reference already exists
         for pass in 0..4 {
-            for &(idx, ref line) in &raw_lines {
-                if matches!(line.as_bytes(), [] | [b'#', ..]) {
-                    continue;
+            if pass == 1 && options.segment_threads() != 1 {
+                self.parse_segments_parallel(raw_lines, options);
+
+                if cap.exceeded(&mut self.messages) {
+                    return;
                 }
+                continue;
+            }
 
-                let tag = line.as_bytes()[0];
-                match pass {
-                    0 if tag != b'H' => continue,
-                    1 if tag != b'S' => continue,
-                    2 if (!(tag == b'L'
-                        || tag == b'J'
-                        || tag == b'C'
-                        || tag == b'F'
-                        || tag == b'E'
-                        || tag == b'G')) =>
-                    {
-                        continue;
-                    }
-                    3 if (!(tag == b'P' || tag == b'W' || tag == b'O' || tag == b'U')) => {
-                        continue;
-                    }
-                    _ => {}
+            for (idx, line) in raw_lines {
+                let line = line.as_ref();
+
+                if raw_line_pass(line) != Some(pass) {
+                    continue;
                 }
 
                 // TODO: add current_line_no to GfaParser state so that we don't have to pass it around
@@ -145,82 +199,193 @@ impl GfaParser {
                 // my implementation is bad and ugly but it will take forever to refactor properly
 
                 let (parsed_line, errs) =
-                    GfaRecord::parse_line((self, line.as_str(), idx, options));
+                    GfaRecord::parse_line_with_buffer((self, line, *idx, options), &mut parts);
 
                 self.push_record_and_update_index(parsed_line);
 
                 self.messages.extend(errs);
-            }
-        }
 
-        match self.header() {
-            Some(header) => {
-                if header.line_no != 1 {
-                    self.messages.push(ParseMessage::new(
-                        header.line_no,
-                        ParseMessageCode::HeaderNotOnFirstLine,
-                        header.raw.clone(),
-                    ));
+                if cap.exceeded(&mut self.messages) {
+                    return;
                 }
             }
-            None => {
-                self.messages.push(ParseMessage::new(
-                    0,
-                    ParseMessageCode::MissingHeader,
-                    path_buf.to_string_lossy().to_string(),
-                ));
-            }
         }
+    }
 
-        self.add_info_errors();
+    /// Parses the GFA file at the given path like [`GfaParser::parse`], and summarizes
+    /// the messages it produced in a [`ParseReport`].
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// let report = parser.parse_with_report("test/gfa_working.gfa", &ParseOptions::default());
+    ///
+    /// assert!(!report.is_fatal());
+    /// assert_eq!(report.messages.len(), report.total());
+    /// ```
+    pub fn parse_with_report(
+        &mut self,
+        path: impl Into<PathBuf>,
+        options: &ParseOptions,
+    ) -> ParseReport<'_> {
+        let first = self.messages.len();
+        let _ = self.parse(path, options);
 
-        if self
-            .messages
-            .iter()
-            .any(|e| e.severity() == ParseMessageSeverity::Fatal)
-        {
-            Err(self
-                .messages
-                .iter()
-                .filter(|e| e.severity() == ParseMessageSeverity::Fatal)
-                .cloned()
-                .collect())
-        } else {
-            Ok(())
-        }
+        ParseReport::from_messages(&self.messages[first..])
     }
 
-    /// Serialises the GFA records to a file.
-    pub fn write_to_file(&self, path: &str, version: GFAVersion) -> Result<(), std::io::Error> {
+    /// Serialises the GFA records to a file, see [`GfaParser::to_gfa_string`].
+    pub fn write_to_file(
+        &self,
+        path: &str,
+        version: GFAVersion,
+        options: &WriteOptions,
+    ) -> Result<(), std::io::Error> {
         let path = PathBuf::from(path);
         let mut file = File::create(path)?;
 
-        for pass in 0..4 {
-            for record in &self.records {
-                let record_pass = match record {
-                    GfaRecord::Header(_) => 0,
-                    GfaRecord::Segment(_) => 1,
-                    GfaRecord::Link(_) => 2,
-                    GfaRecord::Jump(_) => 2,
-                    GfaRecord::Containment(_) => 2,
-                    GfaRecord::Fragment(_) => 2,
-                    GfaRecord::Edge(_) => 2,
-                    GfaRecord::Gap(_) => 2,
-                    GfaRecord::Path(_) => 3,
-                    GfaRecord::Walk(_) => 3,
-                    GfaRecord::OrderedGroup(_) => 3,
-                    GfaRecord::UnorderedGroup(_) => 3,
+        file.write_all(self.to_gfa_string(version, options).as_bytes())
+    }
+
+    /// Serialises the GFA records to a string, one line per record.
+    ///
+    /// Records are written in the order they are parsed in (headers, segments, bridges,
+    /// then paths, walks and groups), so that every reference comes after what it references.
+    /// Records that can't be represented in `version` are left out. Walks are written as
+    /// paths in v1.0, and paths with a PanSN name and no overlaps as walks in v1.1 and v1.2.
+    /// Fragments are written as containments before v2, preceded by a segment without a
+    /// sequence for their external sequence if there is none yet.
+    ///
+    /// Within each of those groups records keep their insertion order, unless `options`
+    /// asks for segments or links to be sorted. With [`WriteOptions::preserve_file_order`]
+    /// there is a single group, in line number order, instead. The parser's own flags are
+    /// only written if `options` asks for them.
+    pub fn to_gfa_string(&self, version: GFAVersion, options: &WriteOptions) -> String {
+        let mut out = String::new();
+        let mut externals: HashSet<&str> = HashSet::new();
+
+        let groups: Vec<Vec<&GfaRecord>> = if options.preserve_file_order {
+            vec![self.records_in_file_order()]
+        } else {
+            (0..4)
+                .map(|pass| self.records.iter().filter(|r| r.pass() == pass).collect())
+                .collect()
+        };
+
+        for mut records in groups {
+            if options.sort_segments_by_name {
+                self.sort_in_place(&mut records, |r| r.as_segment().map(|s| s.name.as_str()));
+            }
+            if options.sort_links {
+                self.sort_in_place(&mut records, |r| {
+                    r.as_link().map(|l| {
+                        (
+                            self.resolve(l.from_segment),
+                            self.resolve(l.to_segment),
+                            l.from_orientation,
+                            l.to_orientation,
+                        )
+                    })
+                });
+            }
+
+            for record in records {
+                let line = match record {
+                    // walks only exist since v1.1
+                    GfaRecord::Walk(w) if version == GFAVersion::V1 => {
+                        w.to_path(self).to_raw_line(version.clone(), self)
+                    }
+                    // and are preferred for haplotypes from then on
+                    GfaRecord::Path(p)
+                        if matches!(version, GFAVersion::V1_1 | GFAVersion::V1_2) =>
+                    {
+                        match p.to_walk() {
+                            Some(w) => w.to_raw_line(version.clone(), self),
+                            None => p.to_raw_line(version.clone(), self),
+                        }
+                    }
+                    GfaRecord::Fragment(f) if version != GFAVersion::V2 => {
+                        let external = f.external_name.reference.as_str();
+                        if self.segment_record_index(external).is_none()
+                            && externals.insert(external)
+                        {
+                            out.push_str(&format!("S\t{external}\t*\n"));
+                        }
+                        record.to_raw_line(version.clone(), self)
+                    }
+                    _ => record.to_raw_line(version.clone(), self),
                 };
-                if record_pass != pass {
-                    continue;
-                }
-                let line = record.to_raw_line(version.clone(), self);
                 if line.is_empty() {
                     continue;
                 }
-                writeln!(file, "{line}")?;
+                out.push_str(&line);
+
+                let mut flags = record.tags().flags().collect::<Vec<_>>();
+                flags.sort_unstable();
+                if options.write_internal_flags && !flags.is_empty() {
+                    out.push_str(&format!("\t{FLAG_TAG}:Z:{}", flags.join(" ")));
+                }
+                out.push('\n');
+            }
+        }
+
+        out
+    }
+
+    // sorts the records that have a key among themselves, every other record keeps its slot
+    fn sort_in_place<'a, K: Ord>(
+        &self,
+        records: &mut [&'a GfaRecord],
+        key: impl Fn(&'a GfaRecord) -> Option<K>,
+    ) {
+        let slots: Vec<usize> = (0..records.len())
+            .filter(|i| key(records[*i]).is_some())
+            .collect();
+        let mut keyed: Vec<&GfaRecord> = slots.iter().map(|i| records[*i]).collect();
+        keyed.sort_by_key(|r| key(r));
+
+        for (slot, record) in slots.into_iter().zip(keyed) {
+            records[slot] = record;
+        }
+    }
+
+    /// Writes the sequence of every segment as a FASTA record.
+    ///
+    /// Ghost segments and segments without a stored sequence (`*`) are skipped.
+    /// rGFA coordinates (SN/SO/SR) are added to the FASTA header when present.
+    /// Sequences are wrapped to `line_width` columns when `Some`.
+    pub fn write_fasta<W: Write>(
+        &self,
+        writer: &mut W,
+        line_width: Option<usize>,
+    ) -> Result<(), std::io::Error> {
+        for segment in self.segments() {
+            if segment.is_ghost() || segment.sequence == "*" || segment.sequence.is_empty() {
+                continue;
+            }
+
+            match segment.rgfa_coordinate() {
+                Some((stable_name, offset, rank)) => writeln!(
+                    writer,
+                    ">{} SN:Z:{stable_name} SO:i:{offset} SR:i:{rank}",
+                    segment.name
+                )?,
+                None => writeln!(writer, ">{}", segment.name)?,
+            }
+
+            match line_width {
+                Some(width) if width > 0 => {
+                    for chunk in segment.sequence.as_bytes().chunks(width) {
+                        writer.write_all(chunk)?;
+                        writeln!(writer)?;
+                    }
+                }
+                _ => writeln!(writer, "{}", segment.sequence)?,
             }
         }
+
         Ok(())
     }
 
@@ -245,20 +410,106 @@ impl GfaParser {
         options: &ParseOptions,
     ) -> Result<usize, Vec<ParseMessage>> {
         let line_no: usize = self.get_available_line_no();
+        self.add_line_at(line, line_no, options)
+    }
+
+    fn add_line_at(
+        &mut self,
+        line: &str,
+        line_no: usize,
+        options: &ParseOptions,
+    ) -> Result<usize, Vec<ParseMessage>> {
+        let record = self.parse_line_at(line, line_no, options)?;
+        self.push_record_and_update_index(Some(record));
+
+        Ok(line_no)
+    }
 
+    // parses a line without adding it. errors that don't stop the line from being
+    // parsed go to `messages`
+    fn parse_line_at(
+        &mut self,
+        line: &str,
+        line_no: usize,
+        options: &ParseOptions,
+    ) -> Result<GfaRecord, Vec<ParseMessage>> {
         let (parsed_line, errs) = GfaRecord::parse_line((self, line, line_no, options));
 
-        if parsed_line.is_none() {
+        let Some(record) = parsed_line else {
             return Err(errs);
-        } else {
-            self.messages.extend(errs);
+        };
+
+        self.messages.extend(errs);
+        Ok(record)
+    }
+
+    /// Like [`GfaParser::add_line`], but bridges, paths, walks and groups are queued until
+    /// [`GfaParser::flush`] instead of being parsed straight away. Headers and segments are
+    /// still added immediately. Returns the line number the line will have.
+    ///
+    /// [`GfaParser::add_line`] expects every segment to be added before anything that
+    /// references it, and creates ghost segments otherwise (which then collide with the
+    /// real segments once they arrive). Use this when lines come in any other order,
+    /// e.g. all links before all segments, and call [`GfaParser::flush`] once every
+    /// segment has been added.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let options = ParseOptions::default();
+    /// let mut parser = GfaParser::new();
+    /// parser.add_line_deferred("L\ts1\t+\ts2\t+\t0M", &options).unwrap();
+    /// parser.add_line_deferred("S\ts1\tATCG", &options).unwrap();
+    /// parser.add_line_deferred("S\ts2\tATCG", &options).unwrap();
+    /// parser.flush(&options).unwrap();
+    ///
+    /// assert_eq!(parser.links().count(), 1);
+    /// assert_eq!(parser.ghost_segments().count(), 0);
+    /// ```
+    pub fn add_line_deferred(
+        &mut self,
+        line: &str,
+        options: &ParseOptions,
+    ) -> Result<usize, Vec<ParseMessage>> {
+        if !matches!(raw_line_pass(line), Some(2 | 3)) {
+            return self.add_line(line, options);
         }
 
-        self.push_record_and_update_index(parsed_line);
+        let line_no = self.get_available_line_no();
+        self.deferred.push((line_no, line.to_string()));
 
         Ok(line_no)
     }
 
+    /// Parses every line queued by [`GfaParser::add_line_deferred`], bridges first, then
+    /// paths, walks and groups, each in the order they were added. Lines that can't be
+    /// parsed are skipped, and their errors are returned together.
+    pub fn flush(&mut self, options: &ParseOptions) -> Result<(), Vec<ParseMessage>> {
+        let mut deferred = std::mem::take(&mut self.deferred);
+        deferred.sort_by_key(|(_, line)| raw_line_pass(line));
+
+        let mut failed = vec![];
+
+        for (line_no, line) in deferred {
+            let (parsed_line, errs) = GfaRecord::parse_line((self, &line, line_no, options));
+
+            if parsed_line.is_none() {
+                failed.extend(errs);
+                continue;
+            }
+
+            self.messages.extend(errs);
+            self.push_record_and_update_index(parsed_line);
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(failed)
+        }
+    }
+
     /// Adds a clone of the GFA record to [`GfaParser::records`].
     /// Returns the line number on Ok() or a `Vec` of errors if the record could not be parsed.
     ///
@@ -266,6 +517,9 @@ impl GfaParser {
     /// To modify the record after it has been added,
     /// use any of the "find_mut_" methods with the line number returned from this method to
     /// obtain a mutable reference to the new record.
+    ///
+    /// Bridges added before the segments they reference are not wired into their adjacency;
+    /// call [`GfaParser::rebuild_adjacency`] afterwards or use [`GfaParser::add_records`].
     /// </div>
     ///
     /// Example:
@@ -276,12 +530,7 @@ impl GfaParser {
     ///
     /// let mut parser = GfaParser::new();
     ///
-    /// let new_segment = Segment {
-    ///     name: "s1".into(),
-    ///     sequence: "ATCG".into(),
-    ///     length: Some(4),
-    ///     ..Segment::default()
-    /// };
+    /// let new_segment = Segment::builder("s1").sequence("ATCG").length(4).build();
     ///
     /// let result = parser.add_record(GfaRecord::Segment(new_segment), &ParseOptions::default());
     ///
@@ -295,13 +544,124 @@ impl GfaParser {
         draft: GfaRecord,
         options: &ParseOptions,
     ) -> Result<usize, Vec<ParseMessage>> {
-        let version = match self.version {
-            GFAVersion::Unknown => GFAVersion::V2, // v2 preserves the most info
-            _ => self.version.clone(),
-        };
+        let line_no = self.get_available_line_no();
+        self.add_record_at(draft, line_no, options)
+    }
+
+    // segments and bridges are added as-is, everything else goes through a raw line
+    fn add_record_at(
+        &mut self,
+        draft: GfaRecord,
+        line_no: usize,
+        options: &ParseOptions,
+    ) -> Result<usize, Vec<ParseMessage>> {
+        match draft {
+            GfaRecord::Segment(segment) => {
+                let segment = self.prepare_segment(segment, line_no, options)?;
+                self.push_record_and_update_index(Some(GfaRecord::Segment(segment)));
+            }
+            GfaRecord::Link(_)
+            | GfaRecord::Jump(_)
+            | GfaRecord::Containment(_)
+            | GfaRecord::Edge(_)
+            | GfaRecord::Gap(_) => {
+                let (bridge, ends) = self.prepare_bridge(draft, line_no, options)?;
+                wire_bridge(self, &ends, line_no);
+                self.push_record_and_update_index(Some(bridge));
+            }
+            _ => {
+                let record = self.parse_draft(&draft, line_no, options)?;
+                self.push_record_and_update_index(Some(record));
+            }
+        }
+
+        Ok(line_no)
+    }
 
-        let line = draft.to_raw_line(version, self);
-        self.add_line(&line, options)
+    /// Adds clones of many GFA records at once. Returns one result per draft, in the
+    /// same order as `drafts`, like [`GfaParser::add_record`] would.
+    ///
+    /// Line numbers are handed out in draft order, but the drafts are added the same
+    /// way as [`GfaParser::parse`] reads a file: headers first, then segments, bridges,
+    /// and finally paths, walks and groups. A bridge can therefore come before the
+    /// segments it references and still be wired into their adjacency.
+    ///
+    /// Instead of updating the indexes and the adjacency for every draft, the headers
+    /// and segments are pushed and [`GfaParser::reindex`] is called once, and the
+    /// bridges are pushed and [`GfaParser::rebuild_adjacency`] is called once.
+    pub fn add_records(
+        &mut self,
+        drafts: Vec<GfaRecord>,
+        options: &ParseOptions,
+    ) -> Vec<Result<usize, Vec<ParseMessage>>> {
+        let first_line_no = self.max_lines + 1;
+        self.max_lines += drafts.len();
+
+        let mut drafts: Vec<(usize, GfaRecord)> = drafts.into_iter().enumerate().collect();
+        drafts.sort_by_key(|(_, draft)| draft.pass());
+        let mut drafts = drafts.into_iter().peekable();
+
+        let mut results: Vec<Result<usize, Vec<ParseMessage>>> = vec![Ok(0); drafts.len()];
+
+        // headers and segments don't refer to anything, so they are only indexed once
+        // all of them are in
+        while let Some((i, draft)) = drafts.next_if(|(_, draft)| draft.pass() < 2) {
+            let line_no = first_line_no + i;
+            let record = match draft {
+                GfaRecord::Segment(segment) => self
+                    .prepare_segment(segment, line_no, options)
+                    .map(GfaRecord::Segment),
+                _ => self.parse_draft(&draft, line_no, options),
+            };
+
+            results[i] = record.map(|record| {
+                self.records.push(record);
+                line_no
+            });
+        }
+
+        self.reindex();
+
+        // bridges are checked against the segments, but only wired once all of them are in
+        while let Some((i, draft)) = drafts.next_if(|(_, draft)| draft.pass() == 2) {
+            let line_no = first_line_no + i;
+            let record = match draft {
+                GfaRecord::Fragment(_) => self.parse_draft(&draft, line_no, options),
+                _ => self
+                    .prepare_bridge(draft, line_no, options)
+                    .map(|(bridge, _)| bridge),
+            };
+
+            results[i] = record.map(|record| {
+                self.push_record_and_update_index(Some(record));
+                line_no
+            });
+        }
+
+        self.rebuild_adjacency();
+
+        // paths, walks and groups follow the adjacency, and nothing refers to them here
+        for (i, draft) in drafts {
+            let line_no = first_line_no + i;
+
+            results[i] = self.parse_draft(&draft, line_no, options).map(|record| {
+                self.push_record_and_update_index(Some(record));
+                line_no
+            });
+        }
+
+        results
+    }
+
+    // records other than segments and bridges are added through a raw line
+    fn parse_draft(
+        &mut self,
+        draft: &GfaRecord,
+        line_no: usize,
+        options: &ParseOptions,
+    ) -> Result<GfaRecord, Vec<ParseMessage>> {
+        let line = draft.to_raw_line(self.draft_version(), self);
+        self.parse_line_at(&line, line_no, options)
     }
 
     /// Returns the total length of all segments in the GFA file.
@@ -322,6 +682,7 @@ impl GfaParser {
     }
 
     /// Checks to see if a valid step exists between two segments.
+    #[allow(clippy::too_many_arguments)]
     pub fn is_step_valid(
         &mut self,
         line_no: usize,
@@ -377,56 +738,27 @@ impl GfaParser {
 
         // Iterate over outgoing links of the from segment
         for bridge_idx in from_segment_outgoing_bridges {
-            let bridge = &self.find_record_mut(bridge_idx);
-            if bridge.is_none() {
-                continue;
-            }
-
-            let bridge = bridge.as_ref().unwrap();
-
-            let bridge_to_segment = match bridge {
-                GfaRecord::Link(link) => link.to_segment.clone(),
-                GfaRecord::Jump(jump) => jump.to_segment.clone(),
-                GfaRecord::Edge(edge) => edge.to.reference.clone(),
-                GfaRecord::Gap(gap) => gap.to.reference.clone(),
-                _ => continue, // skip if not a bridge
-            };
-
-            // Check if the link goes to the correct segment
-            if bridge_to_segment != to_segment_name {
+            let Some(bridge) = self.find_record(bridge_idx) else {
                 continue;
-            }
-
-            let bridge_from_orientation = match bridge {
-                GfaRecord::Link(link) => link.from_orientation,
-                GfaRecord::Jump(jump) => jump.from_orientation,
-                GfaRecord::Edge(edge) => edge.from.direction,
-                GfaRecord::Gap(gap) => gap.from.direction,
-                _ => continue, // skip if not a bridge
             };
 
-            let bridge_to_orientation = match bridge {
-                GfaRecord::Link(link) => link.to_orientation,
-                GfaRecord::Jump(jump) => jump.to_orientation,
-                GfaRecord::Edge(edge) => edge.to.direction,
-                GfaRecord::Gap(gap) => gap.to.direction,
-                _ => continue, // skip if not a bridge
+            let Some(bridge_ref) = BridgeRef::from_record(bridge, self) else {
+                continue; // skip if not a bridge
             };
 
-            // Check if the orientations are correct
-            if bridge_from_orientation != from_orientation
-                || bridge_to_orientation != to_orientation
+            // Check if the bridge goes to the correct segment with the right orientations
+            if bridge_ref.to_segment != to_segment_name
+                || bridge_ref.from_orientation != from_orientation
+                || bridge_ref.to_orientation != to_orientation
             {
                 continue;
             }
 
             if report_overlaps
-                && !(matches!(bridge, GfaRecord::Jump(_)) || matches!(bridge, GfaRecord::Gap(_)))
+                && !matches!(bridge_ref.bridge_type, BridgeType::Jump | BridgeType::Gap)
             {
                 // if the link overlap is 0M, let it slide
-                if (matches!(bridge, GfaRecord::Link(_))
-                    && bridge.as_link().unwrap().overlap == "0M")
-                {
+                if bridge_ref.bridge_type == BridgeType::Link && bridge_ref.overlap == Some("0M") {
                     return true;
                 }
 
@@ -495,15 +827,26 @@ impl GfaParser {
         to_orientation: bool,
         overlap: String,
     ) -> &Link {
-        let mut new_link = Link::default();
-        new_link.line_no = self.get_available_line_no();
-        new_link.from_segment = from_segment;
-        new_link.from_orientation = from_orientation;
-        new_link.to_segment = to_segment;
-        new_link.to_orientation = to_orientation;
-        new_link.overlap = overlap;
+        let mut new_link = Link {
+            line_no: self.get_available_line_no(),
+            from_segment: self.intern(&from_segment),
+            from_orientation,
+            to_segment: self.intern(&to_segment),
+            to_orientation,
+            overlap,
+            ..Link::default()
+        };
         new_link.tags.add_flag("ghost");
 
+        // register it so that later steps can follow the link
+        if let Some(from) = self.find_segment_with_name(&from_segment) {
+            from.outgoing_links.push(new_link.line_no);
+        }
+
+        if let Some(to) = self.find_segment_with_name(&to_segment) {
+            to.incoming_links.push(new_link.line_no);
+        }
+
         self.records_index
             .insert(new_link.line_no, self.records.len());
 
@@ -543,509 +886,3042 @@ impl GfaParser {
         name
     }
 
-    /// Checks if a name is in the namespace.
-    pub fn is_name_in_namespace(&self, name: &str) -> bool {
-        self.namespace.contains_key(name)
-    }
-
-    /// Returns a count of dead-ends and all segments that are dead ends in the graph.
-    /// NB: Isolated segments have two dead ends, hence the count may not always
-    /// be equal to the length of the returned [`Vec<Segment>`]
-    pub fn find_dead_ends(&mut self) -> (u32, Vec<&mut Segment>) {
-        let mut dead_ends = 0;
+    /// Clears and recomputes the bridge and fragment vectors of every segment
+    /// (`outgoing_links`, `contained_by`, ...) by scanning [`GfaParser::records`].
+    ///
+    /// Bridges register themselves with their segments when they are added, so a bridge
+    /// added with [`GfaParser::add_line`] or [`GfaParser::add_record`] before its segments
+    /// (with [`MissingSegmentOptions::Ignore`]) is not wired up until this is called.
+    /// [`GfaParser::add_records`] calls it once, after all of its bridges are added.
+    pub fn rebuild_adjacency(&mut self) {
+        for segment in self.segments_mut() {
+            segment.outgoing_links.clear();
+            segment.incoming_links.clear();
+            segment.containments.clear();
+            segment.contained_by.clear();
+            segment.outgoing_jumps.clear();
+            segment.incoming_jumps.clear();
+            segment.outgoing_edges.clear();
+            segment.incoming_edges.clear();
+            segment.outgoing_gaps.clear();
+            segment.incoming_gaps.clear();
+            segment.fragments.clear();
+        }
 
-        let segments: Vec<&mut Segment> = self
-            .records
-            .iter_mut()
-            .filter_map(GfaRecord::as_mut_segment)
-            .filter(|s| {
-                let prev_dead_ends = dead_ends;
+        let bridges: Vec<(usize, BridgeType, String, String)> = self
+            .bridges()
+            .map(|b| {
+                (
+                    b.line_no,
+                    b.bridge_type,
+                    b.from_segment.to_string(),
+                    b.to_segment.to_string(),
+                )
+            })
+            .collect();
 
-                if s.get_outgoing_bridges().is_empty() {
-                    dead_ends += 1;
+        for (line_no, bridge_type, from, to) in bridges {
+            if let Some(from) = self.find_segment_with_name(&from) {
+                match bridge_type {
+                    BridgeType::Link => from.outgoing_links.push(line_no),
+                    BridgeType::Jump => from.outgoing_jumps.push(line_no),
+                    BridgeType::Containment => from.containments.push(line_no),
+                    BridgeType::Edge => from.outgoing_edges.push(line_no),
+                    BridgeType::Gap => from.outgoing_gaps.push(line_no),
                 }
+            }
 
-                if s.get_incoming_bridges().is_empty() {
-                    dead_ends += 1;
+            if let Some(to) = self.find_segment_with_name(&to) {
+                match bridge_type {
+                    BridgeType::Link => to.incoming_links.push(line_no),
+                    BridgeType::Jump => to.incoming_jumps.push(line_no),
+                    BridgeType::Containment => to.contained_by.push(line_no),
+                    BridgeType::Edge => to.incoming_edges.push(line_no),
+                    BridgeType::Gap => to.incoming_gaps.push(line_no),
                 }
+            }
+        }
 
-                prev_dead_ends != dead_ends
-            })
+        let fragments: Vec<(usize, String)> = self
+            .fragments()
+            .map(|f| (f.line_no, f.segment_name.clone()))
             .collect();
 
-        (dead_ends, segments)
+        for (line_no, segment_name) in fragments {
+            if let Some(segment) = self.find_segment_with_name(&segment_name) {
+                segment.fragments.push(line_no);
+            }
+        }
     }
 
-    /// Finds all segments with no bridge connections
-    pub fn find_isolated_segments(&mut self) -> Vec<&mut Segment> {
-        self.records
-            .iter_mut()
-            .filter_map(GfaRecord::as_mut_segment)
-            .filter(|s| s.get_outgoing_bridges().is_empty() && s.get_incoming_bridges().is_empty())
-            .collect()
-    }
-}
-
-/// Private helpers for GfaParser.
-impl GfaParser {
-    fn push_record_and_update_index(&mut self, parsed_line: Option<GfaRecord>) {
-        if let Some(record) = parsed_line {
-            // add to name index
-            match &record {
-                GfaRecord::Segment(s) => {
-                    self.namespace_index
-                        .insert(s.name.clone(), self.records.len());
-                }
-                GfaRecord::Path(p) => {
-                    self.namespace_index
-                        .insert(p.name.clone(), self.records.len());
-                }
-                GfaRecord::UnorderedGroup(ug) => {
-                    self.namespace_index
-                        .insert(ug.name.clone(), self.records.len());
-                }
-                GfaRecord::OrderedGroup(og) => {
-                    self.namespace_index
-                        .insert(og.name.clone(), self.records.len());
+    /// Rebuilds the line number index, the name index and the namespace from [`GfaParser::records`].
+    ///
+    /// Any code that changes the name or line number of a record (e.g. through
+    /// [`GfaParser::segments_mut`]) must call this afterwards, otherwise lookups
+    /// like [`GfaParser::find_segment_with_name`] will return stale results.
+    pub fn reindex(&mut self) {
+        self.records_index.clear();
+        self.namespace_index.clear();
+        self.namespace.clear();
+        self.walk_ranges.clear();
+
+        for (idx, record) in self.records.iter().enumerate() {
+            self.records_index.insert(record.line_no(), idx);
+
+            if let GfaRecord::Walk(w) = record {
+                let key = (w.sample_id.clone(), w.hap_index, w.seq_id.clone());
+                if let (Some(start), Some(end)) = (w.seq_start, w.seq_end) {
+                    self.walk_ranges
+                        .entry(key)
+                        .or_default()
+                        .insert((start, end, w.line_no));
                 }
-                _ => {}
             }
 
-            self.records_index
-                .insert(record.line_no(), self.records.len());
-            self.records.push(record);
-        }
-    }
-
-    fn get_available_line_no(&mut self) -> usize {
-        self.max_lines += 1;
-        self.max_lines
-    }
-
-    fn add_info_errors(&mut self) {
-        let isolated_segments = self
-            .find_isolated_segments()
-            .into_iter()
-            .map(|s| (s.line_no, s.name.clone()))
-            .collect::<Vec<_>>();
+            let names = record.declared_names();
+
+            if matches!(
+                record,
+                GfaRecord::Segment(_)
+                    | GfaRecord::Path(_)
+                    | GfaRecord::UnorderedGroup(_)
+                    | GfaRecord::OrderedGroup(_)
+                    | GfaRecord::Edge(_)
+                    | GfaRecord::Gap(_)
+            ) {
+                for name in &names {
+                    self.namespace_index.insert(name.clone(), idx);
+                }
+            }
 
-        for pair in isolated_segments {
-            self.messages.push(ParseMessage::new(
-                pair.0,
-                ParseMessageCode::IsolatedSegment,
-                pair.1,
-            ));
+            for name in names {
+                self.namespace.entry(name).or_insert(0);
+            }
         }
 
-        let dead_end_segments = self
-            .find_dead_ends()
-            .1
-            .into_iter()
-            .map(|s| (s.line_no, s.name.clone()))
-            .collect::<Vec<_>>();
+        // restore the occurrence count of names that were suffixed by ensure_name_unique
+        let suffixed: Vec<(String, u32)> = self
+            .namespace
+            .keys()
+            .filter_map(|name| {
+                let (base, occurrence) = name.rsplit_once('_')?;
+                let occurrence = occurrence.parse::<u32>().ok()?;
+                self.namespace
+                    .contains_key(base)
+                    .then(|| (base.to_string(), occurrence))
+            })
+            .collect();
 
-        for pair in dead_end_segments {
-            self.messages.push(ParseMessage::new(
-                pair.0,
-                ParseMessageCode::DeadEndTip,
-                pair.1,
-            ));
+        for (base, occurrence) in suffixed {
+            let count = self.namespace.entry(base).or_insert(0);
+            *count = (*count).max(occurrence);
         }
     }
-}
-
-/// Iterators over GFA record types.
-impl GfaParser {
-    pub fn headers(&self) -> impl Iterator<Item = &Header> {
-        self.records.iter().filter_map(GfaRecord::as_header)
-    }
 
-    pub fn segments(&self) -> impl Iterator<Item = &Segment> {
-        self.records.iter().filter_map(GfaRecord::as_segment)
+    /// Returns the [`Symbol`] of a segment name, interning it if it hasn't been seen yet.
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        self.symbols.intern(name)
     }
 
-    pub fn links(&self) -> impl Iterator<Item = &Link> {
-        self.records.iter().filter_map(GfaRecord::as_link)
+    /// Returns the segment name behind a [`Symbol`] handed out by this parser.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        self.symbols.resolve(symbol)
     }
 
-    pub fn containments(&self) -> impl Iterator<Item = &Containment> {
-        self.records.iter().filter_map(GfaRecord::as_containment)
+    /// Checks if there is a segment called exactly `name`.
+    ///
+    /// Unlike [`GfaParser::is_name_in_namespace`], this only looks at the names records
+    /// currently have, not every name that has been claimed, and only at segments.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// parser.add_line("S\ts1\tA", &ParseOptions::default()).unwrap();
+    /// parser.add_line("P\tp1\ts1+\t*", &ParseOptions::default()).unwrap();
+    ///
+    /// assert!(parser.contains_segment("s1"));
+    /// assert!(!parser.contains_segment("p1"));
+    /// assert!(parser.contains_record("p1"));
+    /// ```
+    pub fn contains_segment(&self, name: &str) -> bool {
+        self.segment_record_index(name).is_some()
     }
 
-    pub fn paths(&self) -> impl Iterator<Item = &Path> {
-        self.records.iter().filter_map(GfaRecord::as_path)
+    /// Checks if there is a record of any type called exactly `name`, such as a segment,
+    /// a path, a group or a named edge.
+    pub fn contains_record(&self, name: &str) -> bool {
+        self.find_record_with_name(name).is_some()
     }
 
-    pub fn walks(&self) -> impl Iterator<Item = &Walk> {
-        self.records.iter().filter_map(GfaRecord::as_walk)
+    /// Checks if a name is in the namespace.
+    pub fn is_name_in_namespace(&self, name: &str) -> bool {
+        self.namespace.contains_key(name)
     }
 
-    pub fn jumps(&self) -> impl Iterator<Item = &Jump> {
-        self.records.iter().filter_map(GfaRecord::as_jump)
-    }
+    /// Counts the records of every type in a single pass over [`GfaParser::records`].
+    pub fn record_counts(&self) -> RecordCounts {
+        let mut counts = RecordCounts::default();
+
+        for record in &self.records {
+            match record {
+                GfaRecord::Header(_) => counts.headers += 1,
+                GfaRecord::Segment(_) => counts.segments += 1,
+                GfaRecord::Link(_) => counts.links += 1,
+                GfaRecord::Jump(_) => counts.jumps += 1,
+                GfaRecord::Containment(_) => counts.containments += 1,
+                GfaRecord::Path(_) => counts.paths += 1,
+                GfaRecord::Walk(_) => counts.walks += 1,
+                GfaRecord::Edge(_) => counts.edges += 1,
+                GfaRecord::Fragment(_) => counts.fragments += 1,
+                GfaRecord::Gap(_) => counts.gaps += 1,
+                GfaRecord::OrderedGroup(_) => counts.ordered_groups += 1,
+                GfaRecord::UnorderedGroup(_) => counts.unordered_groups += 1,
+            }
+        }
 
-    pub fn fragments(&self) -> impl Iterator<Item = &Fragment> {
-        self.records.iter().filter_map(GfaRecord::as_fragment)
+        counts
     }
 
-    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
-        self.records.iter().filter_map(GfaRecord::as_edge)
+    /// Counts the ghost segments and ghost links the parser created in place of missing
+    /// references, see [`MissingSegmentOptions::CreateGhost`] and
+    /// [`MissingBridgeOptions::CreateGhostLink`].
+    pub fn ghost_counts(&self) -> (usize, usize) {
+        (
+            self.ghost_segments().count(),
+            self.links().filter(|l| l.is_ghost()).count(),
+        )
     }
 
-    pub fn gaps(&self) -> impl Iterator<Item = &Gap> {
-        self.records.iter().filter_map(GfaRecord::as_gap)
-    }
+    /// Returns every side of a segment that has no bridges attached to it,
+    /// see [`Segment::left_degree`] and [`Segment::right_degree`].
+    /// Isolated segments are dead ends on both sides, so they appear twice.
+    pub fn find_dead_ends(&self) -> Vec<(&Segment, SegmentSide)> {
+        let mut dead_ends = vec![];
 
-    pub fn unordered_groups(&self) -> impl Iterator<Item = &UnorderedGroup> {
-        self.records
-            .iter()
-            .filter_map(GfaRecord::as_unordered_group)
-    }
+        for segment in self.segments() {
+            if segment.left_degree(self) == 0 {
+                dead_ends.push((segment, SegmentSide::Left));
+            }
 
-    pub fn ordered_groups(&self) -> impl Iterator<Item = &OrderedGroup> {
-        self.records.iter().filter_map(GfaRecord::as_ordered_group)
-    }
+            if segment.right_degree(self) == 0 {
+                dead_ends.push((segment, SegmentSide::Right));
+            }
+        }
 
-    pub fn headers_mut(&mut self) -> impl Iterator<Item = &mut Header> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_header)
+        dead_ends
     }
 
-    pub fn segments_mut(&mut self) -> impl Iterator<Item = &mut Segment> {
+    /// Finds all segments with no bridge connections
+    pub fn find_isolated_segments(&mut self) -> Vec<&mut Segment> {
         self.records
             .iter_mut()
             .filter_map(GfaRecord::as_mut_segment)
+            .filter(|s| s.get_outgoing_bridges().is_empty() && s.get_incoming_bridges().is_empty())
+            .collect()
     }
 
-    pub fn links_mut(&mut self) -> impl Iterator<Item = &mut Link> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_link)
+    /// Returns every ghost segment, that is every segment created in place of a missing
+    /// one rather than read from the file. See [`MissingSegmentOptions::CreateGhost`].
+    pub fn ghost_segments(&self) -> impl Iterator<Item = &Segment> {
+        self.segments().filter(|s| s.is_ghost())
     }
 
-    pub fn containments_mut(&mut self) -> impl Iterator<Item = &mut Containment> {
+    /// Returns the line numbers of every link, jump, edge and gap that starts and ends on
+    /// the same segment, in record order.
+    pub fn self_loops(&self) -> Vec<usize> {
         self.records
-            .iter_mut()
-            .filter_map(GfaRecord::as_mut_containment)
+            .iter()
+            .filter(|r| match r {
+                GfaRecord::Link(l) => l.from_segment == l.to_segment,
+                GfaRecord::Jump(j) => j.from_segment == j.to_segment,
+                GfaRecord::Edge(e) => e.from_segment == e.to_segment,
+                GfaRecord::Gap(g) => g.from_segment == g.to_segment,
+                _ => false,
+            })
+            .map(GfaRecord::line_no)
+            .collect()
     }
 
-    pub fn paths_mut(&mut self) -> impl Iterator<Item = &mut Path> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_path)
-    }
+    /// Returns every path with at least one step through the named segment.
+    /// Returns an empty `Vec` if the segment does not exist.
+    pub fn paths_through_segment(&self, segment_name: &str) -> Vec<&Path> {
+        let Some(segment_id) = self.segment_line_no(segment_name) else {
+            return vec![];
+        };
 
-    pub fn walks_mut(&mut self) -> impl Iterator<Item = &mut Walk> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_walk)
+        self.paths()
+            .filter(|p| p.steps.iter().any(|s| s.segment_id as usize == segment_id))
+            .collect()
     }
 
-    pub fn jumps_mut(&mut self) -> impl Iterator<Item = &mut Jump> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_jump)
-    }
+    /// Returns every walk with at least one step through the named segment.
+    /// Returns an empty `Vec` if the segment does not exist.
+    pub fn walks_through_segment(&self, segment_name: &str) -> Vec<&Walk> {
+        let Some(segment_id) = self.segment_line_no(segment_name) else {
+            return vec![];
+        };
 
-    pub fn fragments_mut(&mut self) -> impl Iterator<Item = &mut Fragment> {
-        self.records
-            .iter_mut()
-            .filter_map(GfaRecord::as_mut_fragment)
+        self.walks()
+            .filter(|w| w.walk.iter().any(|s| s.segment_id as usize == segment_id))
+            .collect()
     }
 
-    pub fn edges_mut(&mut self) -> impl Iterator<Item = &mut Edge> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_edge)
-    }
+    /// Groups the walks by their sample, each group in line order.
+    pub fn walks_by_sample(&self) -> HashMap<String, Vec<&Walk>> {
+        let mut samples: HashMap<String, Vec<&Walk>> = HashMap::new();
 
-    pub fn gaps_mut(&mut self) -> impl Iterator<Item = &mut Gap> {
-        self.records.iter_mut().filter_map(GfaRecord::as_mut_gap)
-    }
+        for walk in self.walks() {
+            samples
+                .entry(walk.sample_id.clone())
+                .or_default()
+                .push(walk);
+        }
 
-    pub fn unordered_groups_mut(&mut self) -> impl Iterator<Item = &mut UnorderedGroup> {
-        self.records
-            .iter_mut()
-            .filter_map(GfaRecord::as_mut_unordered_group)
-    }
+        for walks in samples.values_mut() {
+            walks.sort_by_key(|w| w.line_no);
+        }
 
-    pub fn ordered_groups_mut(&mut self) -> impl Iterator<Item = &mut OrderedGroup> {
-        self.records
-            .iter_mut()
-            .filter_map(GfaRecord::as_mut_ordered_group)
+        samples
     }
-}
 
-impl_enum_find_accessors! {
-    for GfaParser;
+    /// Returns the distinct haplotype indices of the walks of a sample, in ascending order.
+    /// Returns an empty `Vec` if the sample has no walks.
+    pub fn haplotypes_for(&self, sample_id: &str) -> Vec<u32> {
+        let mut haplotypes: Vec<u32> = self
+            .walks()
+            .filter(|w| w.sample_id == sample_id)
+            .map(|w| w.hap_index)
+            .collect();
 
-    header  => Header,
-    segment => Segment,
-    link    => Link,
-    path    => Path,
-    containment => Containment,
-    walk    => Walk,
-    jump    => Jump,
-    fragment => Fragment,
-    edge    => Edge,
-    gap     => Gap,
-    unordered_group => UnorderedGroup,
-    ordered_group => OrderedGroup,
-}
+        haplotypes.sort_unstable();
+        haplotypes.dedup();
 
-impl GfaParser {
-    pub fn find_record(&self, line_no: usize) -> Option<&GfaRecord> {
-        let idx = self.records_index.get(&line_no)?;
-        self.records.get(*idx)
+        haplotypes
     }
 
-    pub fn find_record_mut(&mut self, line_no: usize) -> Option<&mut GfaRecord> {
-        let idx = self.records_index.get(&line_no)?;
-        self.records.get_mut(*idx)
+    /// Returns every segment whose `tag` holds a value of type `T` that matches `pred`.
+    /// Segments without the tag, or with a value that can't be converted to `T`, are skipped.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// parser.add_line("S\ta\tA\tRC:i:40", &ParseOptions::default()).unwrap();
+    /// parser.add_line("S\tb\tA\tRC:i:12", &ParseOptions::default()).unwrap();
+    ///
+    /// let deep = parser.segments_with_tag("RC", |depth: i32| depth > 30);
+    /// assert_eq!(deep.len(), 1);
+    /// assert_eq!(deep[0].name, "a");
+    /// ```
+    pub fn segments_with_tag<T>(&self, tag: &str, pred: impl Fn(T) -> bool) -> Vec<&Segment>
+    where
+        T: for<'a> TryFrom<&'a OptionalFieldValue>,
+    {
+        self.segments()
+            .filter(|s| s.tags.get::<T>(tag).is_some_and(&pred))
+            .collect()
     }
 
-    pub fn find_segment_with_name(&mut self, name: &str) -> Option<&mut Segment> {
-        let idx = self.namespace_index.get(name);
-        self.records
-            .get_mut(*idx?)
-            .and_then(GfaRecord::as_mut_segment)
-    }
+    /// Checks that every two consecutive steps of the named path are joined by a link that
+    /// isn't a ghost, as written or as its reverse complement.
+    ///
+    /// This doesn't depend on [`ParseOptions`], so it can also check paths added with
+    /// [`GfaParser::add_record`] or edited after parsing. Every unconnected pair is reported
+    /// as `LinkNotFound`, with the (0-based) index of the step the link should lead to.
+    /// The messages are returned rather than added to [`GfaParser::messages`], and a missing
+    /// path has none.
+    pub fn validate_path_connectivity(&self, path_name: &str) -> Vec<ParseMessage> {
+        let Some(GfaRecord::Path(path)) = self.find_record_with_name(path_name) else {
+            return vec![];
+        };
 
-    pub fn find_path_with_name(&mut self, name: &str) -> Option<&mut Path> {
-        let idx = self.namespace_index.get(name);
-        self.records.get_mut(*idx?).and_then(GfaRecord::as_mut_path)
-    }
+        let name_of = |step: &Step| {
+            self.find_segment(step.segment_id as usize)
+                .map_or("", |s| s.name.as_str())
+        };
+        let sign = |orientation: bool| if orientation { "+" } else { "-" };
+
+        let mut messages = vec![];
+
+        for (idx, pair) in path.steps.windows(2).enumerate() {
+            let (from, to) = (&pair[0], &pair[1]);
+            let (from_name, to_name) = (name_of(from), name_of(to));
+
+            let connected = self
+                .find_segment(from.segment_id as usize)
+                .is_some_and(|segment| {
+                    segment
+                        .outgoing_links
+                        .iter()
+                        .chain(&segment.incoming_links)
+                        .filter_map(|n| self.find_link(*n))
+                        .filter(|l| !l.is_ghost())
+                        .any(|l| {
+                            let l_from = self.resolve(l.from_segment);
+                            let l_to = self.resolve(l.to_segment);
+
+                            let written = l_from == from_name
+                                && l.from_orientation == from.orientation
+                                && l_to == to_name
+                                && l.to_orientation == to.orientation;
+
+                            // A+ -> B+ is the same link as B- -> A-
+                            let reversed = l_from == to_name
+                                && l.from_orientation != to.orientation
+                                && l_to == from_name
+                                && l.to_orientation != from.orientation;
+
+                            written || reversed
+                        })
+                });
+
+            if !connected {
+                messages.push(ParseMessage::new(
+                    path.line_no,
+                    ParseMessageCode::LinkNotFound,
+                    format!(
+                        "path step {}: {from_name}{} -> {to_name}{}",
+                        idx + 1,
+                        sign(from.orientation),
+                        sign(to.orientation),
+                    ),
+                ));
+            }
+        }
 
-    pub fn find_unordered_group_with_name(&mut self, name: &str) -> Option<&mut UnorderedGroup> {
-        let idx = self.namespace_index.get(name);
-        self.records
-            .get_mut(*idx?)
-            .and_then(GfaRecord::as_mut_unordered_group)
+        messages
     }
 
-    pub fn find_ordered_group_with_name(&mut self, name: &str) -> Option<&mut OrderedGroup> {
-        let idx = self.namespace_index.get(name);
-        self.records
-            .get_mut(*idx?)
-            .and_then(GfaRecord::as_mut_ordered_group)
-    }
+    /// Checks that the overlap of every link with a valid CIGAR fits within the segments it
+    /// joins: the bases it consumes from the query must fit within the `from_segment`, and
+    /// the bases it consumes from the reference within the `to_segment`. Every link that
+    /// doesn't fit gets an `OverlapExceedsSegment` message in [`GfaParser::messages`].
+    ///
+    /// Unlike the check done while parsing, this looks at the segments as they are now, so
+    /// it also catches sequences that were edited or only became known after the links.
+    /// Segments of unknown length (e.g. ghost segments) are skipped.
+    pub fn check_overlap_consistency(&mut self) {
+        let segment_length = |symbol: Symbol| {
+            let name = self.resolve(symbol);
+            let idx = self.segment_record_index(name)?;
+            let length = self.records[idx].as_segment()?.get_length();
+
+            (length > 0).then_some((name, length as u32))
+        };
 
-    /// Get the associated line number of a record by its name
-    pub fn find_line_no_with_name(&self, name: &str) -> Option<i32> {
-        let idx = self.namespace_index.get(name)?;
-        self.records.get(*idx).map(|r| r.line_no() as i32)
-    }
-}
+        let mut messages = vec![];
 
-/// Behaviour when a referenced segment does not exist in [GfaParser::records].
-#[derive(Debug, Default, PartialEq, Eq, Clone, ValueEnum)]
-pub enum MissingSegmentOptions {
-    /// Create a ghost segment to satisfy the path step. A ghost segment is just a segment with a 0 length and a `ghost` tag.
-    #[default]
-    CreateGhost,
-    /// Hard skip any bridge (link/jump/containment/edge) but only skip the path/walk/group step that references the missing segment.
-    SoftSkip,
-    /// Skip any line that references the missing segment.
-    HardSkip,
-    /// Continue parsing the line.
-    Ignore,
-}
+        for link in self.links() {
+            let overlap = link.overlap.as_str();
+            let (Some(query), Some(reference)) =
+                (cigar_query_length(overlap), cigar_reference_length(overlap))
+            else {
+                continue;
+            };
 
-impl std::fmt::Display for MissingSegmentOptions {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            MissingSegmentOptions::CreateGhost => write!(f, "create-ghost"),
-            MissingSegmentOptions::SoftSkip => write!(f, "soft-skip"),
-            MissingSegmentOptions::HardSkip => write!(f, "hard-skip"),
-            MissingSegmentOptions::Ignore => write!(f, "ignore"),
-        }
-    }
-}
+            let ends = [(link.from_segment, query), (link.to_segment, reference)];
+            for (symbol, consumed) in ends {
+                if let Some((name, length)) = segment_length(symbol)
+                    && consumed > length
+                {
+                    messages.push(ParseMessage::new(
+                        link.line_no,
+                        ParseMessageCode::OverlapExceedsSegment,
+                        format!("{overlap} consumes {consumed}bp of {name} ({length}bp)"),
+                    ));
+                }
+            }
+        }
 
-/// Behaviour when a referenced bridge (including implicit references, such as a link between two path steps) does not exist in [GfaParser::records].
-#[derive(Debug, Default, PartialEq, Eq, Clone, ValueEnum)]
-pub enum MissingBridgeOptions {
-    /// Create a ghost link to satisfy the path step.
-    #[default]
-    CreateGhostLink,
-    /// Skip the path/walk/group entirely.
-    HardSkip,
-    /// Continue parsing the line.
-    Ignore,
+        self.messages.extend(messages);
+    }
+
+    /// Lists every name that a record references but that doesn't exist, as
+    /// `(line_no, missing_name)` in record order, with each name once per record.
+    ///
+    /// These are left behind by parsing with [`MissingSegmentOptions::Ignore`] (for group
+    /// members, with anything but `HardSkip`) or by removing records afterwards. Bridge
+    /// ends, fragments and path and walk steps must be segments, while group members can
+    /// be any named record. Path and walk steps that point at no segment are reported by
+    /// the name they were given, see [`Path::missing_segments`].
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, MissingSegmentOptions, ParseOptions};
+    ///
+    /// let options = ParseOptions {
+    ///     handle_missing_segment: MissingSegmentOptions::Ignore,
+    ///     ..ParseOptions::default()
+    /// };
+    ///
+    /// let mut parser = GfaParser::new();
+    /// for line in ["S\ta\tACGT", "L\ta\t+\tb\t+\t0M", "P\tp\ta+,b+\t*"] {
+    ///     let _ = parser.add_line(line, &options);
+    /// }
+    ///
+    /// assert_eq!(
+    ///     parser.dangling_references(),
+    ///     [(2, "b".to_string()), (3, "b".to_string())]
+    /// );
+    /// ```
+    pub fn dangling_references(&self) -> Vec<(usize, String)> {
+        let is_missing = |name: &&str| self.segment_record_index(name).is_none();
+        fn step_names(missing: &[(usize, String)]) -> Vec<&str> {
+            missing.iter().map(|(_, name)| name.as_str()).collect()
+        }
+
+        let mut dangling = vec![];
+
+        for record in &self.records {
+            let mut missing: Vec<&str> = match record {
+                GfaRecord::Fragment(f) => vec![f.segment_name.as_str()],
+                GfaRecord::Path(p) => step_names(&p.missing_segments),
+                GfaRecord::Walk(w) => step_names(&w.missing_segments),
+                _ => match BridgeRef::from_record(record, self) {
+                    Some(bridge) => vec![bridge.from_segment, bridge.to_segment],
+                    None => vec![],
+                },
+            };
+            missing.retain(is_missing);
+
+            if let GfaRecord::OrderedGroup(OrderedGroup { members, .. })
+            | GfaRecord::UnorderedGroup(UnorderedGroup { members, .. }) = record
+            {
+                missing.extend(
+                    members
+                        .iter()
+                        .map(|m| m.trim_end_matches(['+', '-']))
+                        .filter(|m| !self.is_name_in_namespace(m)),
+                );
+            }
+
+            let mut seen = HashSet::new();
+            for name in missing {
+                if seen.insert(name) {
+                    dangling.push((record.line_no(), name.to_string()));
+                }
+            }
+        }
+
+        dangling
+    }
+
+    /// Per-base coverage of the named segment by the segments it contains.
+    ///
+    /// Every containment adds one to the bases from its `position` up to the length of the
+    /// contained segment, clamped to the container. Returns an empty `Vec` if the segment
+    /// does not exist.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// let options = ParseOptions::default();
+    /// for line in ["S\tref\tACGTAC", "S\tr1\tCGT", "S\tr2\tTA", "C\tref\t+\tr1\t+\t1\t3M", "C\tref\t+\tr2\t+\t3\t2M"] {
+    ///     parser.add_line(line, &options).unwrap();
+    /// }
+    ///
+    /// assert_eq!(parser.containment_depth("ref"), [0, 1, 1, 2, 1, 0]);
+    /// ```
+    pub fn containment_depth(&self, segment_name: &str) -> Vec<u32> {
+        let Some(container) = self
+            .segment_line_no(segment_name)
+            .and_then(|n| self.find_segment(n))
+        else {
+            return vec![];
+        };
+
+        let length = container.get_length().max(0) as usize;
+        let mut depth = vec![0; length];
+
+        for containment in container
+            .containments
+            .iter()
+            .filter_map(|n| self.find_containment(*n))
+        {
+            let contained_length = self
+                .segment_line_no(self.resolve(containment.contained))
+                .and_then(|n| self.find_segment(n))
+                .map_or(0, |s| s.get_length().max(0) as usize);
+
+            let start = (containment.position.max(0) as usize).min(length);
+            let end = (start + contained_length).min(length);
+
+            for base in &mut depth[start..end] {
+                *base += 1;
+            }
+        }
+
+        depth
+    }
 }
 
-impl std::fmt::Display for MissingBridgeOptions {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            MissingBridgeOptions::CreateGhostLink => write!(f, "create-ghost-link"),
-            MissingBridgeOptions::HardSkip => write!(f, "hard-skip"),
-            MissingBridgeOptions::Ignore => write!(f, "ignore"),
+/// Private helpers for GfaParser.
+impl GfaParser {
+    // record index of the ghost standing in for the missing segment `name`, created on
+    // first use. see ParseOptions::ghost_name_prefix
+    pub(crate) fn ghost_for(&mut self, name: &str, options: &ParseOptions) -> usize {
+        let ghost_name = format!("{}{name}", options.ghost_name_prefix);
+
+        if let Some(idx) = self.segment_record_index(&ghost_name)
+            && self.records[idx].is_ghost()
+        {
+            return idx;
+        }
+
+        self.create_ghost_segment(ghost_name);
+        self.records.len() - 1
+    }
+
+    // parsing was cut short by ParseOptions::max_errors or because the file isn't GFA
+    pub(crate) fn stopped_early(&self) -> bool {
+        self.messages.last().is_some_and(|m| {
+            matches!(
+                m.code,
+                ParseMessageCode::TooManyErrors | ParseMessageCode::NotAGfaFile
+            )
+        })
+    }
+
+    // samples the first lines for record types, see ParseOptions::min_record_line_fraction
+    fn check_looks_like_gfa<S: AsRef<str>>(
+        &mut self,
+        raw_lines: &[(usize, S)],
+        options: &ParseOptions,
+    ) -> bool {
+        let sample: Vec<&str> = raw_lines
+            .iter()
+            .map(|(_, line)| line.as_ref())
+            .filter(|line| !line.starts_with('#') && !line.trim().is_empty())
+            .take(NOT_GFA_SAMPLE_LINES)
+            .collect();
+
+        if sample.is_empty() {
+            return true;
+        }
+
+        let records = sample
+            .iter()
+            .filter(|line| raw_line_pass(line).is_some() && line.as_bytes().get(1) == Some(&b'\t'))
+            .count();
+
+        if (records as f64) >= options.min_record_line_fraction * sample.len() as f64 {
+            return true;
+        }
+
+        self.messages.push(ParseMessage::new(
+            0,
+            ParseMessageCode::NotAGfaFile,
+            format!("{records} of the first {} lines are records", sample.len()),
+        ));
+
+        false
+    }
+
+    // counts comments and blank lines, and rejects unknown lines in strict mode
+    fn note_unparsed_line(&mut self, n: usize, line: &str, options: &ParseOptions) {
+        if line.starts_with('#') {
+            self.comment_count += 1;
+
+            if options.capture_comments {
+                self.comments.push((n, line.to_string()));
+            }
+        } else if line.trim().is_empty() {
+            self.blank_line_count += 1;
+        } else if options.strict_line_types && is_unknown_line(line) {
+            self.messages.push(unknown_line(n, line, options));
+        }
+    }
+
+    fn push_record_and_update_index(&mut self, parsed_line: Option<GfaRecord>) {
+        if let Some(record) = parsed_line {
+            // add to name index
+            match &record {
+                GfaRecord::Segment(s) => {
+                    self.namespace_index
+                        .insert(s.name.clone(), self.records.len());
+                }
+                GfaRecord::Path(p) => {
+                    self.namespace_index
+                        .insert(p.name.clone(), self.records.len());
+                }
+                GfaRecord::UnorderedGroup(ug) => {
+                    self.namespace_index
+                        .insert(ug.name.clone(), self.records.len());
+                }
+                GfaRecord::OrderedGroup(og) => {
+                    self.namespace_index
+                        .insert(og.name.clone(), self.records.len());
+                }
+                GfaRecord::Edge(Edge { id: Some(id), .. })
+                | GfaRecord::Gap(Gap { id: Some(id), .. }) => {
+                    self.namespace_index.insert(id.clone(), self.records.len());
+                }
+                GfaRecord::Walk(w) => self.index_walk_range(w),
+                _ => {}
+            }
+
+            self.records_index
+                .insert(record.line_no(), self.records.len());
+            self.records.push(record);
+        }
+    }
+
+    fn index_walk_range(&mut self, walk: &Walk) {
+        let (Some(start), Some(end)) = (walk.seq_start, walk.seq_end) else {
+            return;
+        };
+
+        let key = (walk.sample_id.clone(), walk.hap_index, walk.seq_id.clone());
+        self.walk_ranges
+            .entry(key)
+            .or_default()
+            .insert((start, end, walk.line_no));
+    }
+
+    /// Returns `(seq_start, seq_end, line_no)` of every walk of the same sequence whose
+    /// range overlaps `start..=end`, in line order. Walks without a range are not included.
+    pub(crate) fn overlapping_walk_ranges(
+        &self,
+        (sample_id, hap_index, seq_id): (&str, u32, &str),
+        start: u32,
+        end: u32,
+    ) -> Vec<WalkRange> {
+        let key = (sample_id.to_string(), hap_index, seq_id.to_string());
+        let Some(ranges) = self.walk_ranges.get(&key) else {
+            return vec![];
+        };
+
+        let mut overlapping = ranges.overlapping(start, end);
+        overlapping.sort_unstable_by_key(|&(_, _, line_no)| line_no);
+        overlapping
+    }
+
+    // takes a segment draft as-is, without going through to_raw_line, but runs the
+    // same checks a parsed segment line would get
+    fn prepare_segment(
+        &mut self,
+        mut segment: Segment,
+        line_no: usize,
+        options: &ParseOptions,
+    ) -> Result<Segment, Vec<ParseMessage>> {
+        if !is_valid_name(&segment.name) {
+            return Err(vec![ParseMessage::new(
+                line_no,
+                ParseMessageCode::InvalidID,
+                segment.name,
+            )]);
+        }
+
+        let raw = segment.to_raw_line(self.draft_version());
+        let errors = segment.check(line_no, &raw, None, options);
+
+        segment.line_no = line_no;
+        segment.name = self.ensure_name_unique(line_no, segment.name);
+
+        // bridges register themselves when they are added
+        segment.outgoing_links.clear();
+        segment.incoming_links.clear();
+        segment.containments.clear();
+        segment.contained_by.clear();
+        segment.outgoing_jumps.clear();
+        segment.incoming_jumps.clear();
+        segment.outgoing_edges.clear();
+        segment.incoming_edges.clear();
+        segment.outgoing_gaps.clear();
+        segment.incoming_gaps.clear();
+        segment.fragments.clear();
+
+        self.messages.extend(errors);
+        Ok(segment)
+    }
+
+    // takes a link/jump/containment/edge/gap draft as-is, without going through
+    // to_raw_line, but runs the same checks a parsed bridge line would get. the bridge
+    // isn't wired into the adjacency of its segments yet
+    fn prepare_bridge(
+        &mut self,
+        mut draft: GfaRecord,
+        line_no: usize,
+        options: &ParseOptions,
+    ) -> Result<(GfaRecord, BridgeEnds), Vec<ParseMessage>> {
+        let (bridge_type, from, to) = match BridgeRef::from_record(&draft, self) {
+            Some(b) => (
+                b.bridge_type,
+                b.from_segment.to_string(),
+                b.to_segment.to_string(),
+            ),
+            None => unreachable!("prepare_bridge is only called with bridges"),
+        };
+
+        let raw = draft.to_raw_line(self.draft_version(), self);
+        let mut errors = vec![];
+
+        let Some(ends) =
+            resolve_bridge_ends(self, bridge_type, &from, &to, line_no, options, &mut errors)
+        else {
+            return Err(errors);
+        };
+
+        let overlap = match &draft {
+            GfaRecord::Link(l) => Some(l.overlap.as_str()),
+            GfaRecord::Containment(c) => Some(c.overlap.as_str()),
+            _ => None,
+        };
+
+        errors.extend(check_bridge_ends(
+            self,
+            bridge_type,
+            &ends,
+            overlap,
+            line_no,
+            &raw,
+        ));
+
+        match &draft {
+            GfaRecord::Jump(j) => errors.extend(Jump::check_shortcut(&j.tags, j.distance, line_no)),
+            GfaRecord::Containment(c) => errors.extend(Containment::check_position(
+                self,
+                &ends.from_segment,
+                c.position,
+                &c.overlap,
+                line_no,
+            )),
+            GfaRecord::Edge(e) => {
+                let intervals = [
+                    (&e.from_interval, &ends.from_segment),
+                    (&e.to_interval, &ends.to_segment),
+                ];
+
+                for (interval, name) in intervals {
+                    if let Some(segment) = self
+                        .segment_record_index(name)
+                        .and_then(|idx| self.records[idx].as_segment())
+                    {
+                        check_interval(line_no, &mut errors, interval, segment);
+                    }
+                }
+
+                errors.extend(Edge::check_alignment(
+                    self,
+                    &e.from_interval,
+                    &e.to_interval,
+                    e.alignment.as_ref(),
+                    line_no,
+                ));
+            }
+            _ => {}
+        }
+
+        let (from, to) = (
+            self.intern(&ends.from_segment),
+            self.intern(&ends.to_segment),
+        );
+
+        // edge/gap IDs that aren't valid names are dropped, like when parsing
+        let mut valid_id = |id: String, errors: &mut Vec<ParseMessage>| {
+            if is_valid_name(&id) {
+                return Some(self.ensure_name_unique(line_no, id));
+            }
+            errors.push(ParseMessage::new(line_no, ParseMessageCode::InvalidID, id));
+            None
+        };
+
+        match &mut draft {
+            GfaRecord::Link(l) => {
+                (l.from_segment, l.to_segment) = (from, to);
+                l.line_no = line_no;
+            }
+            GfaRecord::Jump(j) => {
+                (j.from_segment, j.to_segment) = (from, to);
+                j.line_no = line_no;
+            }
+            GfaRecord::Containment(c) => {
+                (c.container, c.contained) = (from, to);
+                c.line_no = line_no;
+            }
+            GfaRecord::Edge(e) => {
+                (e.from_segment, e.to_segment) = (from, to);
+                e.line_no = line_no;
+                e.id = e.id.take().and_then(|id| valid_id(id, &mut errors));
+            }
+            GfaRecord::Gap(g) => {
+                (g.from_segment, g.to_segment) = (from, to);
+                g.line_no = line_no;
+                g.id = g.id.take().and_then(|id| valid_id(id, &mut errors));
+            }
+            _ => unreachable!("prepare_bridge is only called with bridges"),
+        }
+
+        // link/jump/containment IDs live in the ID tag
+        if let GfaRecord::Link(Link { tags, .. })
+        | GfaRecord::Jump(Jump { tags, .. })
+        | GfaRecord::Containment(Containment { tags, .. }) = &mut draft
+            && let Some(id) = tags.get::<String>("ID")
+        {
+            if is_valid_name(&id) {
+                let uid = self.ensure_name_unique(line_no, id);
+                tags.add_tag("ID", OptionalFieldValue::String(uid));
+            } else {
+                errors.push(ParseMessage::new(line_no, ParseMessageCode::InvalidID, id));
+            }
+        }
+
+        self.messages.extend(errors);
+        Ok((draft, ends))
+    }
+
+    // version drafts are written in when they have to go through a raw line
+    fn draft_version(&self) -> GFAVersion {
+        match self.version {
+            GFAVersion::Unknown => GFAVersion::V2, // v2 preserves the most info
+            _ => self.version.clone(),
+        }
+    }
+
+    // index into `records` of the segment called `name`
+    pub(crate) fn segment_record_index(&self, name: &str) -> Option<usize> {
+        let idx = *self.namespace_index.get(name)?;
+        self.records.get(idx)?.as_segment().map(|_| idx)
+    }
+
+    pub(crate) fn segment_line_no(&self, name: &str) -> Option<usize> {
+        let idx = self.namespace_index.get(name)?;
+        self.records
+            .get(*idx)
+            .and_then(GfaRecord::as_segment)
+            .map(|s| s.line_no)
+    }
+
+    fn get_available_line_no(&mut self) -> usize {
+        self.max_lines += 1;
+        self.max_lines
+    }
+
+    // segments only depend on the version and the namespace, so everything but the
+    // name can be parsed in parallel. names are then claimed in line order, which
+    // gives the same renames as parsing serially
+    fn parse_segments_parallel<S: AsRef<str> + Sync>(
+        &mut self,
+        raw_lines: &[(usize, S)],
+        options: &ParseOptions,
+    ) {
+        let version = self.version.clone();
+
+        let pre_parse = || {
+            raw_lines
+                .par_iter()
+                .filter(|(_, line)| raw_line_pass(line.as_ref()) == Some(1))
+                .map(|(idx, line)| {
+                    (
+                        *idx,
+                        GfaRecord::pre_parse_segment(line.as_ref(), *idx, options, &version),
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // a pool that can't be built falls back to the global one
+        let pre_parsed = match options.segment_threads() {
+            0 => pre_parse(),
+            threads => match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+                Ok(pool) => pool.install(pre_parse),
+                Err(_) => pre_parse(),
+            },
+        };
+
+        for (idx, (segment, errs, tag_names)) in pre_parsed {
+            self.tag_names.extend(tag_names);
+
+            let segment = segment.map(|mut s| {
+                s.name = self.ensure_name_unique(idx, s.name);
+                GfaRecord::Segment(s)
+            });
+
+            self.push_record_and_update_index(segment);
+            self.messages.extend(errs);
+        }
+    }
+
+    // reports a directory or unreadable file as a message
+    fn open_gfa_file(
+        &mut self,
+        path: &std::path::Path,
+    ) -> Result<BufReader<File>, Vec<ParseMessage>> {
+        // dont run on a directory
+        if path.is_dir() {
+            self.messages.push(ParseMessage::new(
+                0,
+                ParseMessageCode::DirectoryError,
+                path.to_string_lossy().to_string(),
+            ));
+            return Err(self.messages.clone());
+        }
+
+        match File::open(path) {
+            Ok(f) => Ok(BufReader::new(f)),
+            Err(_) => {
+                self.messages.push(ParseMessage::new(
+                    0,
+                    ParseMessageCode::IOError,
+                    path.to_string_lossy().to_string(),
+                ));
+                Err(self.messages.clone())
+            }
+        }
+    }
+
+    fn check_header(&mut self, path: &std::path::Path) {
+        match self.header() {
+            Some(header) => {
+                if header.line_no != 1 {
+                    self.messages.push(ParseMessage::new(
+                        header.line_no,
+                        ParseMessageCode::HeaderNotOnFirstLine,
+                        header.raw.clone(),
+                    ));
+                }
+            }
+            None => {
+                self.messages.push(ParseMessage::new(
+                    0,
+                    ParseMessageCode::MissingHeader,
+                    path.to_string_lossy().to_string(),
+                ));
+            }
+        }
+    }
+
+    fn fatal_messages(&self) -> Result<(), Vec<ParseMessage>> {
+        if self
+            .messages
+            .iter()
+            .any(|e| e.severity() == ParseMessageSeverity::Fatal)
+        {
+            Err(self
+                .messages
+                .iter()
+                .filter(|e| e.severity() == ParseMessageSeverity::Fatal)
+                .cloned()
+                .collect())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn add_info_errors(&mut self, options: &ParseOptions) {
+        let isolated_segments = self
+            .find_isolated_segments()
+            .into_iter()
+            .map(|s| (s.line_no, s.name.clone()))
+            .collect::<Vec<_>>();
+
+        for pair in &isolated_segments {
+            self.messages.push(ParseMessage::new(
+                pair.0,
+                ParseMessageCode::IsolatedSegment,
+                pair.1.clone(),
+            ));
+        }
+
+        // isolated segments were reported above, don't report both of their sides again
+        let dead_end_segments = self
+            .find_dead_ends()
+            .into_iter()
+            .filter(|(s, _)| {
+                !isolated_segments
+                    .iter()
+                    .any(|(line_no, _)| *line_no == s.line_no)
+            })
+            .map(|(s, side)| (s.line_no, format!("{} ({side})", s.name)))
+            .collect::<Vec<_>>();
+
+        for pair in dead_end_segments {
+            self.messages.push(ParseMessage::new(
+                pair.0,
+                ParseMessageCode::DeadEndTip,
+                pair.1,
+            ));
+        }
+
+        if options.report_cycles {
+            for cycle in self.find_cycles() {
+                let names = cycle
+                    .iter()
+                    .filter_map(|n| self.find_segment(*n).map(|s| s.name.clone()))
+                    .collect::<Vec<_>>()
+                    .join(" -> ");
+
+                self.messages.push(ParseMessage::new(
+                    cycle[0],
+                    ParseMessageCode::GraphContainsCycle,
+                    names,
+                ));
+            }
+        }
+    }
+}
+
+/// Iterators over GFA record types.
+impl GfaParser {
+    pub fn headers(&self) -> impl Iterator<Item = &Header> {
+        self.records.iter().filter_map(GfaRecord::as_header)
+    }
+
+    pub fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.records.iter().filter_map(GfaRecord::as_segment)
+    }
+
+    pub fn links(&self) -> impl Iterator<Item = &Link> {
+        self.records.iter().filter_map(GfaRecord::as_link)
+    }
+
+    pub fn containments(&self) -> impl Iterator<Item = &Containment> {
+        self.records.iter().filter_map(GfaRecord::as_containment)
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.records.iter().filter_map(GfaRecord::as_path)
+    }
+
+    pub fn walks(&self) -> impl Iterator<Item = &Walk> {
+        self.records.iter().filter_map(GfaRecord::as_walk)
+    }
+
+    pub fn jumps(&self) -> impl Iterator<Item = &Jump> {
+        self.records.iter().filter_map(GfaRecord::as_jump)
+    }
+
+    pub fn fragments(&self) -> impl Iterator<Item = &Fragment> {
+        self.records.iter().filter_map(GfaRecord::as_fragment)
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = &Edge> {
+        self.records.iter().filter_map(GfaRecord::as_edge)
+    }
+
+    pub fn gaps(&self) -> impl Iterator<Item = &Gap> {
+        self.records.iter().filter_map(GfaRecord::as_gap)
+    }
+
+    /// Iterates over every link, jump, containment, edge and gap as a [`BridgeRef`].
+    pub fn bridges(&self) -> impl Iterator<Item = BridgeRef<'_>> {
+        self.records
+            .iter()
+            .filter_map(|r| BridgeRef::from_record(r, self))
+    }
+
+    pub fn unordered_groups(&self) -> impl Iterator<Item = &UnorderedGroup> {
+        self.records
+            .iter()
+            .filter_map(GfaRecord::as_unordered_group)
+    }
+
+    pub fn ordered_groups(&self) -> impl Iterator<Item = &OrderedGroup> {
+        self.records.iter().filter_map(GfaRecord::as_ordered_group)
+    }
+
+    /// Records of any type that have the optional field `tag`.
+    pub fn records_with_tag<'a>(&'a self, tag: &'a str) -> impl Iterator<Item = &'a GfaRecord> {
+        self.records_where(move |tags| tags.contains(tag))
+    }
+
+    /// Records of any type whose optional fields match `predicate`.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// parser.add_line("S\ts1\tA\tSR:i:0", &ParseOptions::default()).unwrap();
+    /// parser.add_line("S\ts2\tA\tSR:i:1", &ParseOptions::default()).unwrap();
+    ///
+    /// let reference = parser.records_where(|tags| tags.get::<i32>("SR") == Some(0));
+    /// assert_eq!(reference.map(|r| r.line_no()).collect::<Vec<_>>(), [1]);
+    /// ```
+    pub fn records_where<F: Fn(&TagMap) -> bool>(
+        &self,
+        predicate: F,
+    ) -> impl Iterator<Item = &GfaRecord> {
+        self.records.iter().filter(move |r| predicate(r.tags()))
+    }
+
+    /// Every record sorted by line number, i.e. in the order they were in the file.
+    ///
+    /// [`GfaParser::records`] is in parse order instead (headers, segments, bridges, then
+    /// paths, walks and groups). Records added after parsing come last, in the order
+    /// they were added.
+    pub fn records_in_file_order(&self) -> Vec<&GfaRecord> {
+        let mut records: Vec<&GfaRecord> = self.records.iter().collect();
+        records.sort_by_key(|r| r.line_no());
+        records
+    }
+
+    pub fn headers_mut(&mut self) -> impl Iterator<Item = &mut Header> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_header)
+    }
+
+    pub fn segments_mut(&mut self) -> impl Iterator<Item = &mut Segment> {
+        self.records
+            .iter_mut()
+            .filter_map(GfaRecord::as_mut_segment)
+    }
+
+    pub fn links_mut(&mut self) -> impl Iterator<Item = &mut Link> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_link)
+    }
+
+    pub fn containments_mut(&mut self) -> impl Iterator<Item = &mut Containment> {
+        self.records
+            .iter_mut()
+            .filter_map(GfaRecord::as_mut_containment)
+    }
+
+    pub fn paths_mut(&mut self) -> impl Iterator<Item = &mut Path> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_path)
+    }
+
+    pub fn walks_mut(&mut self) -> impl Iterator<Item = &mut Walk> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_walk)
+    }
+
+    pub fn jumps_mut(&mut self) -> impl Iterator<Item = &mut Jump> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_jump)
+    }
+
+    pub fn fragments_mut(&mut self) -> impl Iterator<Item = &mut Fragment> {
+        self.records
+            .iter_mut()
+            .filter_map(GfaRecord::as_mut_fragment)
+    }
+
+    pub fn edges_mut(&mut self) -> impl Iterator<Item = &mut Edge> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_edge)
+    }
+
+    pub fn gaps_mut(&mut self) -> impl Iterator<Item = &mut Gap> {
+        self.records.iter_mut().filter_map(GfaRecord::as_mut_gap)
+    }
+
+    pub fn unordered_groups_mut(&mut self) -> impl Iterator<Item = &mut UnorderedGroup> {
+        self.records
+            .iter_mut()
+            .filter_map(GfaRecord::as_mut_unordered_group)
+    }
+
+    pub fn ordered_groups_mut(&mut self) -> impl Iterator<Item = &mut OrderedGroup> {
+        self.records
+            .iter_mut()
+            .filter_map(GfaRecord::as_mut_ordered_group)
+    }
+}
+
+impl_enum_find_accessors! {
+    for GfaParser;
+
+    header  => Header,
+    segment => Segment,
+    link    => Link,
+    path    => Path,
+    containment => Containment,
+    walk    => Walk,
+    jump    => Jump,
+    fragment => Fragment,
+    edge    => Edge,
+    gap     => Gap,
+    unordered_group => UnorderedGroup,
+    ordered_group => OrderedGroup,
+}
+
+impl GfaParser {
+    pub fn find_record(&self, line_no: usize) -> Option<&GfaRecord> {
+        let idx = self.records_index.get(&line_no)?;
+        self.records.get(*idx)
+    }
+
+    pub fn find_record_mut(&mut self, line_no: usize) -> Option<&mut GfaRecord> {
+        let idx = self.records_index.get(&line_no)?;
+        self.records.get_mut(*idx)
+    }
+
+    pub fn find_segment_with_name(&mut self, name: &str) -> Option<&mut Segment> {
+        let idx = self.namespace_index.get(name);
+        self.records
+            .get_mut(*idx?)
+            .and_then(GfaRecord::as_mut_segment)
+    }
+
+    pub fn find_path_with_name(&mut self, name: &str) -> Option<&mut Path> {
+        let idx = self.namespace_index.get(name);
+        self.records.get_mut(*idx?).and_then(GfaRecord::as_mut_path)
+    }
+
+    pub fn find_unordered_group_with_name(&mut self, name: &str) -> Option<&mut UnorderedGroup> {
+        let idx = self.namespace_index.get(name);
+        self.records
+            .get_mut(*idx?)
+            .and_then(GfaRecord::as_mut_unordered_group)
+    }
+
+    pub fn find_ordered_group_with_name(&mut self, name: &str) -> Option<&mut OrderedGroup> {
+        let idx = self.namespace_index.get(name);
+        self.records
+            .get_mut(*idx?)
+            .and_then(GfaRecord::as_mut_ordered_group)
+    }
+
+    /// Get the associated line number of a record by its name
+    pub fn find_line_no_with_name(&self, name: &str) -> Option<i32> {
+        let idx = self.namespace_index.get(name)?;
+        self.records.get(*idx).map(|r| r.line_no() as i32)
+    }
+
+    /// Get any record by its name, without needing mutable access
+    pub(crate) fn find_record_with_name(&self, name: &str) -> Option<&GfaRecord> {
+        let idx = self.namespace_index.get(name)?;
+        self.records.get(*idx)
+    }
+}
+
+/// Behaviour when a referenced segment does not exist in [GfaParser::records].
+#[derive(Debug, Default, PartialEq, Eq, Clone, ValueEnum)]
+pub enum MissingSegmentOptions {
+    /// Create a ghost segment to satisfy the path step. A ghost segment is just a segment with a 0 length and a `ghost` tag.
+    #[default]
+    CreateGhost,
+    /// Hard skip any bridge (link/jump/containment/edge) but only skip the path/walk/group step that references the missing segment.
+    SoftSkip,
+    /// Skip any line that references the missing segment.
+    HardSkip,
+    /// Continue parsing the line.
+    Ignore,
+}
+
+impl std::fmt::Display for MissingSegmentOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingSegmentOptions::CreateGhost => write!(f, "create-ghost"),
+            MissingSegmentOptions::SoftSkip => write!(f, "soft-skip"),
+            MissingSegmentOptions::HardSkip => write!(f, "hard-skip"),
+            MissingSegmentOptions::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+/// Behaviour when a referenced bridge (including implicit references, such as a link between two path steps) does not exist in [GfaParser::records].
+#[derive(Debug, Default, PartialEq, Eq, Clone, ValueEnum)]
+pub enum MissingBridgeOptions {
+    /// Create a ghost link to satisfy the path step.
+    #[default]
+    CreateGhostLink,
+    /// Skip the path/walk/group entirely.
+    HardSkip,
+    /// Continue parsing the line.
+    Ignore,
+}
+
+impl std::fmt::Display for MissingBridgeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MissingBridgeOptions::CreateGhostLink => write!(f, "create-ghost-link"),
+            MissingBridgeOptions::HardSkip => write!(f, "hard-skip"),
+            MissingBridgeOptions::Ignore => write!(f, "ignore"),
+        }
+    }
+}
+
+impl WalkRanges {
+    // walks usually come in seq_start order, which makes this a push
+    fn insert(&mut self, range: WalkRange) {
+        let idx = self.ranges.partition_point(|&(s, _, _)| s <= range.0);
+        let max_before = idx.checked_sub(1).map_or(0, |i| self.max_end[i]);
+
+        self.ranges.insert(idx, range);
+        self.max_end.insert(idx, max_before.max(range.1));
+
+        // the maximum only grows, so stop at the first one the new range doesn't raise
+        for max_end in &mut self.max_end[idx + 1..] {
+            if *max_end >= range.1 {
+                break;
+            }
+            *max_end = range.1;
+        }
+    }
+
+    fn overlapping(&self, start: u32, end: u32) -> Vec<WalkRange> {
+        // everything past this point starts after the range ends
+        let candidates = self.ranges.partition_point(|&(s, _, _)| s <= end);
+
+        let mut overlapping = vec![];
+
+        for idx in (0..candidates).rev() {
+            if self.max_end[idx] < start {
+                break;
+            }
+
+            if self.ranges[idx].1 >= start {
+                overlapping.push(self.ranges[idx]);
+            }
+        }
+
+        overlapping
+    }
+}
+
+// counts the messages above warning level as they are added, see ParseOptions::max_errors
+pub(crate) struct ErrorCap {
+    max: Option<usize>,
+    checked: usize,
+    errors: usize,
+}
+
+impl ErrorCap {
+    pub(crate) fn new(gfa: &GfaParser, options: &ParseOptions) -> Self {
+        Self {
+            max: options.max_errors,
+            checked: gfa.messages.len(),
+            errors: 0,
+        }
+    }
+
+    // returns true once parsing should stop, after replacing the messages past the cap
+    // with a single TooManyErrors
+    pub(crate) fn exceeded(&mut self, messages: &mut Vec<ParseMessage>) -> bool {
+        let Some(max) = self.max else {
+            return false;
+        };
+
+        while let Some(message) = messages.get(self.checked) {
+            if !matches!(
+                message.severity(),
+                ParseMessageSeverity::Info | ParseMessageSeverity::Warn
+            ) {
+                self.errors += 1;
+
+                if self.errors > max {
+                    let line = message.line;
+                    messages.truncate(self.checked);
+                    messages.push(ParseMessage::new(
+                        line,
+                        ParseMessageCode::TooManyErrors,
+                        format!("more than {max} errors"),
+                    ));
+
+                    return true;
+                }
+            }
+
+            self.checked += 1;
+        }
+
+        false
+    }
+}
+
+// lines sampled by GfaParser::check_looks_like_gfa
+const NOT_GFA_SAMPLE_LINES: usize = 100;
+
+// pass 0: parse headers
+// pass 1: parse segments
+// pass 2: parse bridges (links/containments/jumps/gaps/edges/fragments)
+// pass 3: parse trails (paths/walks/groups)
+// comments, blank and unknown lines are not parsed in any pass
+fn raw_line_pass(line: &str) -> Option<usize> {
+    match line.as_bytes().first()? {
+        b'H' => Some(0),
+        b'S' => Some(1),
+        b'L' | b'J' | b'C' | b'F' | b'E' | b'G' => Some(2),
+        b'P' | b'W' | b'O' | b'U' => Some(3),
+        _ => None,
+    }
+}
+
+// lines that no pass picks up and that aren't comments or blank
+fn is_unknown_line(line: &str) -> bool {
+    raw_line_pass(line).is_none() && !line.trim().is_empty() && !line.starts_with('#')
+}
+
+/// Options that can be passed to [GfaParser::to_gfa_string] and [GfaParser::write_to_file]
+/// to customise how records are written.
+///
+/// Sorting makes the order of the lines independent of how the records were added,
+/// which helps when diffing or testing output. Tags are still written in hash order.
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// Write segments sorted by name instead of in insertion order.
+    pub sort_segments_by_name: bool,
+    /// Write links sorted by their from and to segment names, then their orientations,
+    /// instead of in insertion order.
+    pub sort_links: bool,
+    /// Write the flags the parser sets on records it creates or converts (e.g. `ghost`,
+    /// `edge` and `gap`) in an extra `PF` tag. They are left out by default, so they don't
+    /// end up in files that never had them. They aren't read back as flags.
+    pub write_internal_flags: bool,
+    /// Write records in line number order, i.e. in the order they were in the file, see
+    /// [`GfaParser::records_in_file_order`]. Records added after parsing come last. This
+    /// keeps the diff small when a hand-curated file is read and written back, but a
+    /// reference may then come before the record it references.
+    pub preserve_file_order: bool,
+}
+
+/// Options that can be passed to [GfaParser::parse]
+/// to customise parsing behavior.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// Skips checking if a sequence contains invalid characters, speeding up parsing of large GFA files.
+    pub skip_invalid_sequence_test: bool,
+    /// Store the raw lines of the GFA file in each record.
+    /// This is only useful for debugging/error reporting.
+    pub store_raw_lines: bool,
+    /// Store segment sequences in memory. When [false], sequences will
+    /// be replaced with `*` and an `LN` tag will be added if one does not exist.
+    pub store_sequences: bool,
+    /// When [true], if the path overlap column is omitted, the parser will use
+    /// the overlaps from the links between each pair of segments in the path.
+    /// This is only during validation and the derived overlaps are not exported.
+    pub substitute_path_overlaps: bool,
+    pub handle_missing_segment: MissingSegmentOptions,
+    pub handle_missing_bridge: MissingBridgeOptions,
+    /// Ignore errors produced when an implicit link can be used to satisfy
+    /// a path step.
+    ///
+    /// Example: a path references a non-existent `-/-` link but a `+/+` link exists.
+    pub allow_implicit_links: bool,
+    /// Report every cycle found by [`GfaParser::find_cycles`] as an info message after parsing.
+    pub report_cycles: bool,
+    /// Number of threads to pre-parse segment lines on before adding them in line order.
+    /// 1 parses serially, 0 uses every core. The result is the same either way.
+    ///
+    /// Only the segment pass is parallel, bridges, paths and walks are still parsed
+    /// serially. `benches/parallel_parse.rs` times the thread counts against each other.
+    pub threads: usize,
+    /// Pre-parse segment lines on every core, the same as setting [`ParseOptions::threads`]
+    /// to 0. Only used while `threads` is left at 1.
+    #[deprecated(note = "set `threads` to 0 instead")]
+    pub parallel: bool,
+    /// Treat any line that isn't a comment, blank or a known record type as fatal,
+    /// instead of skipping it.
+    pub strict_line_types: bool,
+    /// Keep every `#` comment line in [`GfaParser::comments`], for dialects that store
+    /// metadata in comments.
+    pub capture_comments: bool,
+    /// Require every base of a sequence to be an IUPAC nucleotide code
+    /// (`ACGTURYKMSWBDHVN.-`, in either case), reporting a `NonNucleotideSequence` otherwise.
+    ///
+    /// GFA allows any printable sequence, so this is off by default. It runs even when
+    /// [`ParseOptions::skip_invalid_sequence_test`] skips the printable check, and a
+    /// sequence that fails the printable check isn't reported twice.
+    pub strict_nucleotide_alphabet: bool,
+    /// Stop parsing once a parse has produced more than this many messages above warning
+    /// level, e.g. when the file isn't GFA at all. Messages past the cap are dropped and
+    /// a fatal `TooManyErrors` message is added in their place, so parsing returns `Err`.
+    pub max_errors: Option<usize>,
+    /// Give up with a fatal `NotAGfaFile` message if fewer than this fraction of the first
+    /// lines (comments and blank lines aside) start with a record type and a tab, instead
+    /// of reporting every line of e.g. a FASTQ file as invalid. `0.0` turns the check off.
+    pub min_record_line_fraction: f64,
+    /// Treat an optional field starting with `#` as a comment running to the end of the
+    /// line, as some tools write e.g. `S\ts1\tACGT\t# coverage`. Off by default, as such
+    /// a field is not valid GFA.
+    pub allow_inline_comments: bool,
+    /// Prepended to the name of a missing segment to name the ghost segment created in
+    /// its place (see [`MissingSegmentOptions::CreateGhost`]), so that ghosts are easy to
+    /// tell apart later. Empty by default: the ghost has the missing segment's name.
+    pub ghost_name_prefix: String,
+    /// Prepended to `O_<line>` or `U_<line>` to name groups without a name (`*`).
+    /// Defaults to `anon_`.
+    pub anon_group_prefix: String,
+}
+
+impl ParseOptions {
+    // threads for the segment pass, with the deprecated `parallel` folded in
+    #[allow(deprecated)]
+    fn segment_threads(&self) -> usize {
+        if self.parallel && self.threads == 1 {
+            0
+        } else {
+            self.threads
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    #[allow(deprecated)]
+    fn default() -> Self {
+        Self {
+            skip_invalid_sequence_test: false,
+            store_raw_lines: false,
+            store_sequences: true,
+            substitute_path_overlaps: true,
+            handle_missing_segment: MissingSegmentOptions::CreateGhost,
+            handle_missing_bridge: MissingBridgeOptions::CreateGhostLink,
+            allow_implicit_links: true,
+            report_cycles: false,
+            threads: 1,
+            parallel: false,
+            strict_line_types: false,
+            capture_comments: false,
+            strict_nucleotide_alphabet: false,
+            max_errors: None,
+            min_record_line_fraction: 0.5,
+            allow_inline_comments: false,
+            ghost_name_prefix: String::new(),
+            anon_group_prefix: "anon_".to_string(),
+        }
+    }
+}
+
+/// Messages of a single parse counted by severity, see [`GfaParser::parse_with_report`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ParseReport<'a> {
+    /// The messages produced by this parse, in the order they were reported.
+    pub messages: &'a [ParseMessage],
+
+    pub info: usize,
+    pub warn: usize,
+    pub severe: usize,
+    pub error: usize,
+    pub fatal: usize,
+}
+
+impl<'a> ParseReport<'a> {
+    pub fn from_messages(messages: &'a [ParseMessage]) -> Self {
+        let mut report = Self {
+            messages,
+            ..Self::default()
+        };
+
+        for message in messages {
+            match message.severity() {
+                ParseMessageSeverity::Info => report.info += 1,
+                ParseMessageSeverity::Warn => report.warn += 1,
+                ParseMessageSeverity::Severe => report.severe += 1,
+                ParseMessageSeverity::Error => report.error += 1,
+                ParseMessageSeverity::Fatal => report.fatal += 1,
+            }
+        }
+
+        report
+    }
+
+    /// True if the file could not be parsed, i.e. [`GfaParser::parse`] would return [`Err`].
+    pub fn is_fatal(&self) -> bool {
+        self.fatal > 0
+    }
+
+    pub fn total(&self) -> usize {
+        self.info + self.warn + self.severe + self.error + self.fatal
+    }
+}
+
+/// Number of records of each type, see [`GfaParser::record_counts`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct RecordCounts {
+    pub headers: usize,
+    pub segments: usize,
+    pub links: usize,
+    pub jumps: usize,
+    pub containments: usize,
+    pub paths: usize,
+    pub walks: usize,
+    pub edges: usize,
+    pub fragments: usize,
+    pub gaps: usize,
+    pub ordered_groups: usize,
+    pub unordered_groups: usize,
+}
+
+impl RecordCounts {
+    pub fn total(&self) -> usize {
+        self.headers
+            + self.segments
+            + self.links
+            + self.jumps
+            + self.containments
+            + self.paths
+            + self.walks
+            + self.edges
+            + self.fragments
+            + self.gaps
+            + self.ordered_groups
+            + self.unordered_groups
+    }
+}
+
+/// Prints one `name: count` line per record type, skipping types with no records.
+impl std::fmt::Display for RecordCounts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = [
+            ("headers", self.headers),
+            ("segments", self.segments),
+            ("links", self.links),
+            ("jumps", self.jumps),
+            ("containments", self.containments),
+            ("paths", self.paths),
+            ("walks", self.walks),
+            ("edges", self.edges),
+            ("fragments", self.fragments),
+            ("gaps", self.gaps),
+            ("ordered groups", self.ordered_groups),
+            ("unordered groups", self.unordered_groups),
+        ];
+
+        for (name, count) in counts.iter().filter(|(_, count)| *count > 0) {
+            writeln!(f, "{name}: {count}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// GFA file format version.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub enum GFAVersion {
+    V1,
+    V1_1,
+    V1_2,
+    V2,
+    #[default]
+    Unknown,
+}
+
+impl From<String> for GFAVersion {
+    fn from(val: String) -> Self {
+        match val.as_str() {
+            "1.0" => GFAVersion::V1,
+            "1.1" => GFAVersion::V1_1,
+            "1.2" => GFAVersion::V1_2,
+            "2.0" => GFAVersion::V2,
+            _ => GFAVersion::Unknown,
+        }
+    }
+}
+
+impl std::fmt::Display for GFAVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GFAVersion::V1 => write!(f, "1.0"),
+            GFAVersion::V1_1 => write!(f, "1.1"),
+            GFAVersion::V1_2 => write!(f, "1.2"),
+            GFAVersion::V2 => write!(f, "2.0"),
+            GFAVersion::Unknown => write!(f, "1.0"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gfa;
+    use crate::optional_field::OptionalFieldValue;
+
+    #[test]
+    fn no_parse_errors() {
+        let mut newgfa = gfa::GfaParser::new();
+        let parse_outcome = newgfa.parse(
+            "test/gfa_working.gfa",
+            &gfa::ParseOptions {
+                skip_invalid_sequence_test: false,
+                store_raw_lines: true,
+                store_sequences: true,
+                substitute_path_overlaps: true,
+                handle_missing_segment: gfa::MissingSegmentOptions::CreateGhost,
+                handle_missing_bridge: gfa::MissingBridgeOptions::CreateGhostLink,
+                allow_implicit_links: true,
+                report_cycles: false,
+                threads: 1,
+                strict_line_types: false,
+                capture_comments: false,
+                strict_nucleotide_alphabet: false,
+                max_errors: None,
+                min_record_line_fraction: 0.5,
+                allow_inline_comments: false,
+                ghost_name_prefix: String::new(),
+                anon_group_prefix: "anon_".to_string(),
+                ..gfa::ParseOptions::default()
+            },
+        );
+
+        for error in &newgfa.messages {
+            error.print_formatted_error();
+        }
+
+        assert!(
+            parse_outcome.is_ok(),
+            "Failed to parse GFA file: {:?}",
+            newgfa.messages
+        );
+        assert!(
+            newgfa.messages.is_empty(),
+            "There should be no parse errors, but found: {:?}",
+            newgfa.messages
+        );
+    }
+
+    #[test]
+    fn report_counts_this_parse() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        let report = newgfa.parse_with_report("test/does_not_exist.gfa", &options);
+        assert!(report.is_fatal());
+        assert_eq!(report.fatal, 1);
+
+        // messages of the failed parse above stay out of the next report
+        let report = newgfa.parse_with_report("test/gfa_working.gfa", &options);
+        assert!(!report.is_fatal());
+        assert_eq!(report.total(), report.messages.len());
+        assert!(report.messages.len() < newgfa.messages.len());
+    }
+
+    #[test]
+    fn display_without_parser() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t-\t2M",
+            "P\tp\ta+,b-\t2M",
+        ] {
+            newgfa.add_line(line, &options).unwrap();
+        }
+
+        let a = newgfa.intern("a").to_string();
+        let b = newgfa.intern("b").to_string();
+
+        let lines: Vec<String> = newgfa.records.iter().map(|r| r.to_string()).collect();
+        assert_eq!(lines[0], "S\ta\tACGT");
+        assert_eq!(lines[2], format!("L\t{a}\t+\t{b}\t-\t2M"));
+        assert_eq!(lines[3], "P\tp\t1+,2-\t2M");
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut newgfa = gfa::GfaParser::new();
+        let parse_outcome = newgfa.parse(
+            "test/gfa_working.gfa",
+            &gfa::ParseOptions {
+                skip_invalid_sequence_test: false,
+                store_raw_lines: false,
+                store_sequences: true,
+                substitute_path_overlaps: true,
+                handle_missing_segment: gfa::MissingSegmentOptions::Ignore,
+                handle_missing_bridge: gfa::MissingBridgeOptions::Ignore,
+                allow_implicit_links: true,
+                report_cycles: false,
+                threads: 1,
+                strict_line_types: false,
+                capture_comments: false,
+                strict_nucleotide_alphabet: false,
+                max_errors: None,
+                min_record_line_fraction: 0.5,
+                allow_inline_comments: false,
+                ghost_name_prefix: String::new(),
+                anon_group_prefix: "anon_".to_string(),
+                ..gfa::ParseOptions::default()
+            },
+        );
+
+        for error in &newgfa.messages {
+            error.print_formatted_error();
+        }
+
+        newgfa
+            .write_to_file(
+                "test/gfa_working_roundtrip.gfa",
+                gfa::GFAVersion::V1_2,
+                &gfa::WriteOptions::default(),
+            )
+            .expect("Failed to save GFA file");
+
+        let mut newgfa2 = gfa::GfaParser::new();
+        let parse_outcome2 = newgfa2.parse(
+            "test/gfa_working_roundtrip.gfa",
+            &gfa::ParseOptions {
+                skip_invalid_sequence_test: false,
+                store_raw_lines: false,
+                store_sequences: true,
+                substitute_path_overlaps: true,
+                handle_missing_segment: gfa::MissingSegmentOptions::Ignore,
+                handle_missing_bridge: gfa::MissingBridgeOptions::Ignore,
+                allow_implicit_links: true,
+                report_cycles: false,
+                threads: 1,
+                strict_line_types: false,
+                capture_comments: false,
+                strict_nucleotide_alphabet: false,
+                max_errors: None,
+                min_record_line_fraction: 0.5,
+                allow_inline_comments: false,
+                ghost_name_prefix: String::new(),
+                anon_group_prefix: "anon_".to_string(),
+                ..gfa::ParseOptions::default()
+            },
+        );
+
+        for error in &newgfa2.messages {
+            error.print_formatted_error();
+        }
+
+        assert!(
+            parse_outcome2.is_ok(),
+            "Failed to parse GFA file: {:?}",
+            newgfa2.messages
+        );
+
+        assert_eq!(
+            newgfa.records.len(),
+            newgfa2.records.len(),
+            "Record count mismatch after roundtrip"
+        );
+        assert_eq!(
+            newgfa.messages.len(),
+            newgfa2.messages.len(),
+            "Error count mismatch after roundtrip"
+        );
+
+        assert!(
+            parse_outcome.is_ok(),
+            "Failed to parse GFA file: {:?}",
+            newgfa.messages
+        );
+    }
+
+    #[test]
+    fn gfa_string_matches_file() {
+        let mut newgfa = gfa::GfaParser::new();
+        let _ = newgfa.parse("test/path.gfa", &gfa::ParseOptions::default());
+
+        let out = std::env::temp_dir().join("parfait_gfa_string_matches_file.gfa");
+        newgfa
+            .write_to_file(
+                out.to_str().unwrap(),
+                gfa::GFAVersion::V1,
+                &gfa::WriteOptions::default(),
+            )
+            .unwrap();
+        let written = std::fs::read_to_string(&out).unwrap();
+        let _ = std::fs::remove_file(&out);
+
+        let string = newgfa.to_gfa_string(gfa::GFAVersion::V1, &gfa::WriteOptions::default());
+        assert_eq!(string, written);
+        assert!(string.starts_with("H\t"));
+        assert_eq!(string.lines().count(), newgfa.records.len());
+    }
+
+    #[test]
+    fn internal_flags_are_not_written() {
+        let mut gfa = gfa::GfaParser::new();
+        gfa.add_line("S\ta\tA\tPF:Z:mine", &gfa::ParseOptions::default())
+            .expect("test line should parse");
+        gfa.create_ghost_segment("b".to_string());
+
+        let written = gfa.to_gfa_string(gfa::GFAVersion::V1, &gfa::WriteOptions::default());
+        assert!(!written.contains("ghost"));
+        assert!(
+            written
+                .lines()
+                .any(|l| l.starts_with("S\tb\t") && !l.contains("PF:Z:"))
+        );
+        assert!(written.contains("PF:Z:mine"));
+
+        let write_options = gfa::WriteOptions {
+            write_internal_flags: true,
+            ..Default::default()
+        };
+        let written = gfa.to_gfa_string(gfa::GFAVersion::V1, &write_options);
+        assert!(written.contains("PF:Z:ghost"));
+    }
+
+    #[test]
+    fn preserve_file_order_on_write() {
+        let lines = [
+            "H\tVN:Z:1.0",
+            "S\ta\tACGT",
+            "L\ta\t+\tb\t+\t0M",
+            "S\tb\tACGT",
+        ];
+        let out = std::env::temp_dir().join("parfait_gfa_preserve_file_order_on_write.gfa");
+        std::fs::write(&out, lines.join("\n")).unwrap();
+
+        let mut newgfa = gfa::GfaParser::new();
+        let _ = newgfa.parse(&out, &gfa::ParseOptions::default());
+        let _ = std::fs::remove_file(&out);
+        newgfa
+            .add_line("S\tc\tACGT", &gfa::ParseOptions::default())
+            .unwrap();
+
+        let write_options = gfa::WriteOptions {
+            preserve_file_order: true,
+            ..Default::default()
+        };
+        let written = newgfa.to_gfa_string(gfa::GFAVersion::V1, &write_options);
+        let types: String = written.lines().map(|l| &l[..1]).collect();
+        assert_eq!(types, "HSLSS");
+        assert!(written.ends_with("S\tc\tACGT\n"));
+
+        let written = newgfa.to_gfa_string(gfa::GFAVersion::V1, &gfa::WriteOptions::default());
+        let types: String = written.lines().map(|l| &l[..1]).collect();
+        assert_eq!(types, "HSSSL");
+    }
+
+    #[test]
+    fn sorted_output_is_independent_of_insertion_order() {
+        let options = gfa::ParseOptions::default();
+        let write_options = gfa::WriteOptions {
+            sort_segments_by_name: true,
+            sort_links: true,
+            write_internal_flags: false,
+            preserve_file_order: false,
+        };
+
+        let lines = [
+            "S\tb\tA",
+            "S\ta\tA",
+            "S\tc\tA",
+            "L\tb\t+\tc\t+\t0M",
+            "L\ta\t-\tb\t+\t0M",
+            "L\ta\t+\tb\t+\t0M",
+        ];
+
+        let mut forward = gfa::GfaParser::new();
+        let mut reversed = gfa::GfaParser::new();
+        for line in lines {
+            forward
+                .add_line(line, &options)
+                .expect("test line should parse");
+        }
+        for line in lines[..3].iter().rev().chain(lines[3..].iter().rev()) {
+            reversed
+                .add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let written = forward.to_gfa_string(gfa::GFAVersion::V1, &write_options);
+        assert_eq!(
+            written,
+            reversed.to_gfa_string(gfa::GFAVersion::V1, &write_options)
+        );
+
+        let order: Vec<&str> = written.lines().map(|l| &l[2..]).collect();
+        assert_eq!(
+            order,
+            [
+                "a\tA",
+                "b\tA",
+                "c\tA",
+                "a\t-\tb\t+\t0M",
+                "a\t+\tb\t+\t0M",
+                "b\t+\tc\t+\t0M",
+            ]
+        );
+    }
+
+    #[test]
+    fn trails_through_segment() {
+        let mut newgfa = gfa::GfaParser::new();
+        let _ = newgfa.parse("test/gfa_working.gfa", &gfa::ParseOptions::default());
+
+        assert_eq!(newgfa.paths_through_segment("2").len(), 1);
+        assert!(newgfa.paths_through_segment("3").is_empty());
+        assert!(newgfa.paths_through_segment("missing").is_empty());
+
+        let mut newgfa = gfa::GfaParser::new();
+        let _ = newgfa.parse("test/walk.gfa", &gfa::ParseOptions::default());
+
+        let walks = newgfa.walks_through_segment("s5");
+        assert_eq!(walks.len(), 1);
+        assert_eq!(walks[0].sample_id, "walk1");
+        assert_eq!(newgfa.walks_through_segment("s6").len(), 2);
+    }
+
+    #[test]
+    fn rgfa_coordinates() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        let full = newgfa
+            .add_line("S\ts1\tACGT\tSN:Z:chr1\tSO:i:100\tSR:i:0", &options)
+            .unwrap();
+        let partial = newgfa
+            .add_line("S\ts2\tACGT\tSN:Z:chr1\tSO:i:104", &options)
+            .unwrap();
+
+        assert_eq!(
+            newgfa.find_segment(full).unwrap().rgfa_coordinate(),
+            Some(("chr1".to_string(), 100, 0))
+        );
+        assert_eq!(
+            newgfa.find_segment(partial).unwrap().rgfa_coordinate(),
+            None
+        );
+
+        let incomplete: Vec<_> = newgfa
+            .messages
+            .iter()
+            .filter(|m| m.code == crate::errors::ParseMessageCode::IncompleteRGFATags)
+            .collect();
+        assert_eq!(incomplete.len(), 1);
+        assert_eq!(incomplete[0].line, partial);
+    }
+
+    #[test]
+    fn fasta_export() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        newgfa
+            .add_line("S\ts1\tACGTACGTAC\tSN:Z:chr1\tSO:i:0\tSR:i:0", &options)
+            .unwrap();
+        newgfa.add_line("S\ts2\t*\tLN:i:4", &options).unwrap();
+        newgfa.add_line("S\ts3\tGGCC", &options).unwrap();
+        newgfa.create_ghost_segment("s4".to_string());
+
+        let mut fasta = vec![];
+        newgfa.write_fasta(&mut fasta, Some(4)).unwrap();
+
+        assert_eq!(
+            String::from_utf8(fasta).unwrap(),
+            ">s1 SN:Z:chr1 SO:i:0 SR:i:0\nACGT\nACGT\nAC\n>s3\nGGCC\n"
+        );
+    }
+
+    #[test]
+    fn bridges_cover_every_type() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t-\t2M",
+            "J\ta\t-\tb\t+\t10",
+            "C\ta\t+\tb\t+\t0\t4M",
+        ] {
+            newgfa.add_line(line, &options).unwrap();
+        }
+
+        let bridges: Vec<_> = newgfa.bridges().collect();
+        assert_eq!(bridges.len(), 3);
+
+        assert_eq!(bridges[0].bridge_type, crate::line::BridgeType::Link);
+        assert_eq!(bridges[0].overlap, Some("2M"));
+        assert!(!bridges[0].to_orientation);
+
+        assert_eq!(bridges[1].bridge_type, crate::line::BridgeType::Jump);
+        assert_eq!(bridges[1].overlap, None);
+        assert!(!bridges[1].from_orientation);
+
+        assert_eq!(bridges[2].from_segment, "a");
+        assert_eq!(bridges[2].to_segment, "b");
+    }
+
+    #[test]
+    fn walk_ranges_out_of_order() {
+        let mut ranges = gfa::WalkRanges::default();
+
+        for range in [(50, 60, 1), (0, 100, 2), (10, 20, 3), (70, 80, 4)] {
+            ranges.insert(range);
+        }
+
+        assert_eq!(ranges.max_end, vec![100, 100, 100, 100]);
+
+        let mut overlapping = ranges.overlapping(75, 90);
+        overlapping.sort_unstable();
+        assert_eq!(overlapping, vec![(0, 100, 2), (70, 80, 4)]);
+
+        assert_eq!(ranges.overlapping(101, 200), vec![]);
+    }
+
+    #[test]
+    fn reindex_after_rename() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        newgfa.add_line("S\ts1\tACGT", &options).unwrap();
+        newgfa.add_line("S\ts1\tACGT", &options).unwrap();
+        newgfa.add_line("S\ts2\tACGT", &options).unwrap();
+
+        newgfa.segments_mut().last().unwrap().name = "renamed".to_string();
+        assert!(newgfa.find_segment_with_name("renamed").is_none());
+
+        newgfa.reindex();
+
+        assert!(newgfa.find_segment_with_name("renamed").is_some());
+        assert!(newgfa.find_segment_with_name("s2").is_none());
+        assert!(!newgfa.is_name_in_namespace("s2"));
+        assert!(newgfa.find_segment_with_name("s1_1").is_some());
+
+        // suffixes already handed out are not reused
+        assert_eq!(newgfa.ensure_name_unique(0, "s1".to_string()), "s1_2");
+    }
+
+    #[test]
+    fn record_counts_match_iterators() {
+        let mut newgfa = gfa::GfaParser::new();
+        let _ = newgfa.parse("test/gfa_working.gfa", &gfa::ParseOptions::default());
+
+        let counts = newgfa.record_counts();
+        assert_eq!(counts.segments, newgfa.segments().count());
+        assert_eq!(counts.links, newgfa.links().count());
+        assert_eq!(counts.paths, newgfa.paths().count());
+        assert_eq!(counts.total(), newgfa.records.len());
+    }
+
+    #[test]
+    fn add_records_in_any_order() {
+        use crate::line::{link::Link, record::GfaRecord, segment::Segment};
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+        let (a, b) = (newgfa.intern("a"), newgfa.intern("b"));
+
+        let results = newgfa.add_records(
+            vec![
+                GfaRecord::Link(Link::builder(a, b).overlap("0M").build()),
+                GfaRecord::Segment(Segment::builder("a").sequence("ACGT").build()),
+                GfaRecord::Segment(Segment::builder("b").sequence("ACGT").build()),
+                GfaRecord::Segment(Segment::builder("bad name").build()),
+            ],
+            &options,
+        );
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().ok(), Some(&1));
+        assert_eq!(results[2].as_ref().ok(), Some(&3));
+        assert!(results[3].is_err());
+
+        let a = newgfa.find_segment_with_name("a").unwrap();
+        assert_eq!(a.outgoing_links, vec![1]);
+        let b = newgfa.find_segment_with_name("b").unwrap();
+        assert_eq!(b.incoming_links, vec![1]);
+        assert_eq!(newgfa.resolve(newgfa.find_link(1).unwrap().to_segment), "b");
+    }
+
+    #[test]
+    fn ln_tag_read_at_parse_time() {
+        use crate::line::segment::LengthSource;
+
+        let mut newgfa = gfa::GfaParser::new();
+        let line_no = newgfa
+            .add_line("S\ta\t*\tLN:i:12", &gfa::ParseOptions::default())
+            .unwrap();
+
+        let segment = newgfa.find_segment_with_name("a").unwrap();
+        assert_eq!(segment.line_no, line_no);
+        assert_eq!(segment.ln_length(), Some(12));
+        assert_eq!(segment.get_length(), 12);
+
+        segment.set_ln_tag(Some(5));
+        assert_eq!(segment.tags.get::<i32>("LN"), Some(5));
+        assert_eq!(segment.get_length(), 5);
+
+        segment.set_ln_tag(None);
+        assert!(!segment.tags.contains("LN"));
+        assert_eq!(segment.length_source(), LengthSource::Defaulted);
+    }
+
+    #[test]
+    fn add_records_like_add_record() {
+        use crate::line::{record::GfaRecord, segment::Segment};
+
+        let options = gfa::ParseOptions::default();
+        let mut v2 = gfa::GfaParser::new();
+        for line in [
+            "H\tVN:Z:2.0",
+            "S\ta\t8\tACGTACGT",
+            "F\ta\tread-\t2\t6\t0\t4\t4M",
+        ] {
+            v2.add_line(line, &options).unwrap();
+        }
+        let fragment = GfaRecord::Fragment(v2.fragments().next().unwrap().clone());
+
+        let v1 = || {
+            let mut gfa = gfa::GfaParser::new();
+            for line in ["H\tVN:Z:1.0", "S\ta\tACGTACGT", "S\tread\tACGT"] {
+                gfa.add_line(line, &options).unwrap();
+            }
+            gfa
+        };
+
+        let mut one_by_one = v1();
+        let fragment_line = one_by_one.add_record(fragment.clone(), &options).unwrap();
+        let segment_line = one_by_one
+            .add_record(
+                GfaRecord::Segment(Segment::builder("b").length(10).build()),
+                &options,
+            )
+            .unwrap();
+
+        let mut at_once = v1();
+        let results = at_once.add_records(
+            vec![
+                fragment,
+                GfaRecord::Segment(Segment::builder("b").length(10).build()),
+            ],
+            &options,
+        );
+        assert_eq!(results[0].as_ref().ok(), Some(&fragment_line));
+        assert_eq!(results[1].as_ref().ok(), Some(&segment_line));
+
+        // a fragment in a v1 graph becomes a containment, and the length isn't lost
+        for gfa in [&mut one_by_one, &mut at_once] {
+            assert!(gfa.find_containment(fragment_line).is_some());
+            assert_eq!(gfa.find_segment(segment_line).unwrap().get_length(), 10);
+        }
+    }
+
+    #[test]
+    fn add_record_without_round_trip() {
+        use crate::line::{link::Link, record::GfaRecord, segment::Segment};
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+        newgfa.add_line("H\tVN:Z:1.0", &options).unwrap();
+
+        // a v1 line would drop the length column
+        let a = newgfa
+            .add_record(
+                GfaRecord::Segment(Segment::builder("a").length(10).build()),
+                &options,
+            )
+            .unwrap();
+        newgfa
+            .add_record(
+                GfaRecord::Segment(Segment::builder("b").sequence("ACGT").build()),
+                &options,
+            )
+            .unwrap();
+
+        let (from, to) = (newgfa.intern("a"), newgfa.intern("b"));
+        let link = newgfa
+            .add_record(
+                GfaRecord::Link(
+                    Link::builder(from, to)
+                        .overlap("1M")
+                        .tag("ID", OptionalFieldValue::String("a".to_string()))
+                        .build(),
+                ),
+                &options,
+            )
+            .unwrap();
+
+        assert_eq!(newgfa.find_segment(a).unwrap().get_length(), 10);
+        assert_eq!(newgfa.find_segment(a).unwrap().outgoing_links, vec![link]);
+        assert_eq!(
+            newgfa.find_segment_with_name("b").unwrap().incoming_links,
+            vec![link]
+        );
+
+        // the ID collides with segment a and gets a suffix
+        assert_eq!(
+            newgfa.find_link(link).unwrap().tags.get::<String>("ID"),
+            Some("a_1".to_string())
+        );
+
+        assert!(
+            newgfa
+                .add_record(
+                    GfaRecord::Segment(Segment::builder("bad name").build()),
+                    &options
+                )
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn add_record_runs_line_checks() {
+        use crate::errors::ParseMessageCode;
+        use crate::line::{link::Link, record::GfaRecord, segment::Segment};
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+        newgfa.add_line("H\tVN:Z:1.0", &options).unwrap();
+
+        let add = |newgfa: &mut gfa::GfaParser, draft| {
+            let line_no = newgfa.add_record(draft, &options).unwrap();
+            let codes: Vec<_> = newgfa
+                .messages
+                .iter()
+                .filter(|m| m.line == line_no)
+                .map(|m| m.code.clone())
+                .collect();
+            (line_no, codes)
+        };
+
+        let a = Segment::builder("a").sequence("AC GT").build();
+        let (_, codes) = add(&mut newgfa, GfaRecord::Segment(a));
+        assert_eq!(codes, vec![ParseMessageCode::InvalidSequence]);
+
+        let b = Segment::builder("b")
+            .sequence("ACGT")
+            .tag("LN", OptionalFieldValue::Int(5))
+            .build();
+        let (_, codes) = add(&mut newgfa, GfaRecord::Segment(b));
+        assert_eq!(codes, vec![ParseMessageCode::SegmentLengthMismatch]);
+
+        let (from, to) = (newgfa.intern("a"), newgfa.intern("b"));
+        let link = Link::builder(from, to).overlap("9M").build();
+        let (_, codes) = add(&mut newgfa, GfaRecord::Link(link));
+        assert_eq!(codes, vec![ParseMessageCode::OverlapExceedsSegment]);
+
+        let link = Link::builder(from, to).overlap("4Q").build();
+        let (_, codes) = add(&mut newgfa, GfaRecord::Link(link));
+        assert_eq!(codes, vec![ParseMessageCode::InvalidCIGAR]);
+
+        // reported on the line the record would have had
+        let errors = newgfa
+            .add_record(
+                GfaRecord::Segment(Segment::builder("bad name").build()),
+                &options,
+            )
+            .unwrap_err();
+        assert_eq!(errors[0].code, ParseMessageCode::InvalidID);
+        assert_eq!(errors[0].line, newgfa.max_lines);
+    }
+
+    #[test]
+    fn rebuild_adjacency_after_out_of_order_lines() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions {
+            handle_missing_segment: gfa::MissingSegmentOptions::Ignore,
+            ..gfa::ParseOptions::default()
+        };
+
+        let link = newgfa.add_line("L\ta\t+\tb\t+\t0M", &options).unwrap();
+        newgfa.add_line("S\ta\tACGT", &options).unwrap();
+        newgfa.add_line("S\tb\tACGT", &options).unwrap();
+
+        assert!(
+            newgfa
+                .find_segment_with_name("a")
+                .unwrap()
+                .outgoing_links
+                .is_empty()
+        );
+
+        newgfa.rebuild_adjacency();
+
+        assert_eq!(
+            newgfa.find_segment_with_name("a").unwrap().outgoing_links,
+            vec![link]
+        );
+        assert_eq!(
+            newgfa.find_segment_with_name("b").unwrap().incoming_links,
+            vec![link]
+        );
+    }
+
+    #[test]
+    fn overlap_longer_than_segment() {
+        use crate::errors::ParseMessageCode;
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\ta\tACGT",
+            "S\tb\tACGTACGT",
+            "L\ta\t+\tb\t+\t4M",
+            "L\ta\t+\tb\t+\t6M",
+        ] {
+            newgfa
+                .add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let exceeded: Vec<usize> = newgfa
+            .messages
+            .iter()
+            .filter(|m| m.code == ParseMessageCode::OverlapExceedsSegment)
+            .map(|m| m.line)
+            .collect();
+
+        assert_eq!(exceeded, vec![4]);
+    }
+
+    #[test]
+    fn overlap_consistency() {
+        use crate::errors::ParseMessageCode;
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        // 2M3I consumes 5bp of a but only 2bp of b, 1M4D the other way around
+        for line in [
+            "S\ta\tACGT",
+            "S\tb\tACGTACGT",
+            "L\ta\t+\tb\t+\t2M",
+            "L\ta\t+\tb\t+\t2M3I",
+            "L\tb\t+\ta\t+\t1M4D",
+            "L\ta\t+\tb\t+\t*",
+        ] {
+            newgfa
+                .add_line(line, &options)
+                .expect("test line should parse");
+        }
+        newgfa.messages.clear();
+
+        newgfa.check_overlap_consistency();
+
+        let exceeded: Vec<(usize, &str)> = newgfa
+            .messages
+            .iter()
+            .filter(|m| m.code == ParseMessageCode::OverlapExceedsSegment)
+            .map(|m| (m.line, m.offender.as_str()))
+            .collect();
+
+        assert_eq!(
+            exceeded,
+            vec![
+                (4, "2M3I consumes 5bp of a (4bp)"),
+                (5, "1M4D consumes 5bp of a (4bp)"),
+            ]
+        );
+    }
+
+    #[test]
+    fn dangling_references_after_lenient_parse() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions {
+            handle_missing_segment: gfa::MissingSegmentOptions::Ignore,
+            ..gfa::ParseOptions::default()
+        };
+
+        for line in [
+            "S\ta\tACGT",
+            "L\ta\t+\tx\t+\t0M",
+            "C\tx\t+\tx\t-\t0\t*",
+            "P\tp\ta+,x+,a+\t*",
+            "W\ts\t0\tc\t0\t8\t>a<y",
+            "U\tu\ta p z",
+            "P\tq\ta+,a+\t*",
+        ] {
+            let _ = newgfa.add_line(line, &options);
+        }
+
+        let dangling = newgfa.dangling_references();
+        let dangling: Vec<(usize, &str)> = dangling
+            .iter()
+            .map(|(n, name)| (*n, name.as_str()))
+            .collect();
+
+        assert_eq!(dangling, [(2, "x"), (3, "x"), (4, "x"), (5, "y"), (6, "z")]);
+
+        let path = newgfa.find_path_with_name("p").unwrap().clone();
+        assert_eq!(
+            path.to_raw_line(gfa::GFAVersion::V1, &newgfa),
+            "P\tp\ta+,x+,a+\t*"
+        );
+    }
+
+    #[test]
+    fn dead_ends_by_side() {
+        use crate::line::segment::SegmentSide;
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in ["S\ta\tA", "S\tb\tA", "S\tc\tA", "L\ta\t+\tb\t-\t0M"] {
+            newgfa
+                .add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let dead_ends: Vec<(String, SegmentSide)> = newgfa
+            .find_dead_ends()
+            .into_iter()
+            .map(|(s, side)| (s.name.clone(), side))
+            .collect();
+
+        assert_eq!(
+            dead_ends,
+            vec![
+                ("a".to_string(), SegmentSide::Left),
+                ("b".to_string(), SegmentSide::Left),
+                ("c".to_string(), SegmentSide::Left),
+                ("c".to_string(), SegmentSide::Right),
+            ]
+        );
+    }
+
+    #[test]
+    fn path_connectivity_after_parsing() {
+        use crate::errors::ParseMessageCode;
+        use crate::line::{
+            path::{Path, Step},
+            record::GfaRecord,
+        };
+
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\ta\tA",
+            "S\tb\tA",
+            "S\tc\tA",
+            "L\ta\t+\tb\t+\t0M",
+            "L\tc\t+\tb\t-\t0M",
+        ] {
+            newgfa
+                .add_line(line, &options)
+                .expect("test line should parse");
         }
-    }
-}
 
-/// Options that can be passed to [GfaParser::parse]
-/// to customise parsing behavior.
-#[derive(Debug)]
-pub struct ParseOptions {
-    /// Skips checking if a sequence contains invalid characters, speeding up parsing of large GFA files.
-    pub skip_invalid_sequence_test: bool,
-    /// Store the raw lines of the GFA file in each record.
-    /// This is only useful for debugging/error reporting.
-    pub store_raw_lines: bool,
-    /// Store segment sequences in memory. When [false], sequences will
-    /// be replaced with `*` and an `LN` tag will be added if one does not exist.
-    pub store_sequences: bool,
-    /// When [true], if the path overlap column is omitted, the parser will use
-    /// the overlaps from the links between each pair of segments in the path.
-    /// This is only during validation and the derived overlaps are not exported.
-    pub substitute_path_overlaps: bool,
-    pub handle_missing_segment: MissingSegmentOptions,
-    pub handle_missing_bridge: MissingBridgeOptions,
-    /// Ignore errors produced when an implicit link can be used to satisfy
-    /// a path step.
-    ///
-    /// Example: a path references a non-existent `-/-` link but a `+/+` link exists.
-    pub allow_implicit_links: bool,
-}
+        let step = |name: &str, orientation: bool| Step {
+            segment_id: newgfa.segment_line_no(name).unwrap() as u32,
+            orientation,
+        };
+        let steps = vec![
+            step("a", true),
+            step("b", true),
+            step("c", false),
+            step("a", true),
+        ];
+        let path = Path {
+            name: "p1".to_string(),
+            overlaps: vec!["*".to_string(); 3],
+            steps,
+            ..Path::default()
+        };
+        newgfa
+            .add_record(GfaRecord::Path(path), &options)
+            .expect("path should be added");
 
-impl Default for ParseOptions {
-    fn default() -> Self {
-        Self {
-            skip_invalid_sequence_test: false,
-            store_raw_lines: false,
-            store_sequences: true,
-            substitute_path_overlaps: true,
-            handle_missing_segment: MissingSegmentOptions::CreateGhost,
-            handle_missing_bridge: MissingBridgeOptions::CreateGhostLink,
-            allow_implicit_links: true,
+        let messages = newgfa.validate_path_connectivity("p1");
+
+        // b+ -> c- is the reverse of c+ -> b-, but nothing joins c- to a+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].code, ParseMessageCode::LinkNotFound);
+        assert_eq!(messages[0].offender, "path step 3: c- -> a+");
+        assert!(newgfa.validate_path_connectivity("missing").is_empty());
+    }
+
+    #[test]
+    fn containment_depth_is_clamped() {
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\tref\tACGTAC",
+            "S\tr1\tCGT",
+            "C\tref\t+\tr1\t+\t4\t*",
+            "C\tref\t+\tr1\t-\t9\t*",
+        ] {
+            newgfa
+                .add_line(line, &options)
+                .expect("test line should parse");
         }
+
+        assert_eq!(newgfa.containment_depth("ref"), [0, 0, 0, 0, 1, 1]);
+        assert_eq!(newgfa.containment_depth("r1"), [0, 0, 0]);
+        assert!(newgfa.containment_depth("missing").is_empty());
     }
-}
 
-/// GFA file format version.
-#[derive(Clone, Debug, PartialEq, Default)]
-pub enum GFAVersion {
-    V1,
-    V1_1,
-    V1_2,
-    V2,
-    #[default]
-    Unknown,
-}
+    #[test]
+    fn containment_overlap_must_fit_container() {
+        use crate::errors::ParseMessageCode;
 
-impl From<String> for GFAVersion {
-    fn from(val: String) -> Self {
-        match val.as_str() {
-            "1.0" => GFAVersion::V1,
-            "1.1" => GFAVersion::V1_1,
-            "1.2" => GFAVersion::V1_2,
-            "2.0" => GFAVersion::V2,
-            _ => GFAVersion::Unknown,
+        let mut newgfa = gfa::GfaParser::new();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\tref\tACGTAC",
+            "S\tr1\tCGT",
+            "C\tref\t+\tr1\t+\t3\t3M",
+            "C\tref\t+\tr1\t+\t4\t3M",
+            "C\tref\t+\tr1\t+\t4\t*",
+            "C\tghost\t+\tr1\t+\t0\t3M",
+        ] {
+            newgfa
+                .add_line(line, &options)
+                .expect("test line should parse");
         }
+
+        let overruns: Vec<_> = newgfa
+            .messages
+            .iter()
+            .filter(|m| m.code == ParseMessageCode::InvalidContainmentPositionRange)
+            .collect();
+
+        assert_eq!(overruns.len(), 1);
+        assert_eq!(overruns[0].offender, "4 + 3M");
     }
-}
 
-impl std::fmt::Display for GFAVersion {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            GFAVersion::V1 => write!(f, "1.0"),
-            GFAVersion::V1_1 => write!(f, "1.1"),
-            GFAVersion::V1_2 => write!(f, "1.2"),
-            GFAVersion::V2 => write!(f, "2.0"),
-            GFAVersion::Unknown => write!(f, "1.0"),
+    #[test]
+    fn deferred_lines_wait_for_segments() {
+        use crate::errors::ParseMessageCode;
+
+        let options = gfa::ParseOptions::default();
+        let mut newgfa = gfa::GfaParser::new();
+
+        for line in [
+            "P\tp1\ta+,b+\t*",
+            "L\ta\t+\tb\t+\t0M",
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+        ] {
+            newgfa
+                .add_line_deferred(line, &options)
+                .expect("test line should queue");
         }
+
+        assert_eq!(newgfa.links().count(), 0);
+        newgfa.flush(&options).expect("deferred lines should parse");
+
+        assert_eq!(newgfa.ghost_counts(), (0, 0));
+        assert!(
+            newgfa
+                .messages
+                .iter()
+                .all(|m| m.code != ParseMessageCode::NamespaceCollision)
+        );
+
+        let link = newgfa.links().next().unwrap();
+        assert_eq!(link.line_no, 2);
+        assert_eq!(
+            newgfa.find_segment_with_name("a").unwrap().outgoing_links,
+            [2]
+        );
+        assert!(newgfa.validate_path_connectivity("p1").is_empty());
+
+        assert!(newgfa.flush(&options).is_ok());
+        assert!(newgfa.add_line_deferred("L\ta\t+", &options).is_ok());
+        assert!(newgfa.flush(&options).is_err());
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::gfa;
+    #[test]
+    fn strict_nucleotide_alphabet() {
+        use crate::errors::ParseMessageCode;
+
+        let lines = ["S\ta\tACGTNryk.-", "S\tb\tACG?T", "S\tc\t*"];
+        let non_nucleotide = |strict_nucleotide_alphabet: bool, skip_invalid_sequence_test| {
+            let options = gfa::ParseOptions {
+                strict_nucleotide_alphabet,
+                skip_invalid_sequence_test,
+                ..gfa::ParseOptions::default()
+            };
+            let mut newgfa = gfa::GfaParser::new();
+            for line in lines {
+                newgfa
+                    .add_line(line, &options)
+                    .expect("test line should parse");
+            }
+
+            newgfa
+                .messages
+                .iter()
+                .filter(|m| m.code == ParseMessageCode::NonNucleotideSequence)
+                .map(|m| (m.line, m.column))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(non_nucleotide(false, false).is_empty());
+        assert_eq!(non_nucleotide(true, false), [(2, Some(8))]);
+        assert_eq!(non_nucleotide(true, true), [(2, Some(8))]);
+    }
 
     #[test]
-    fn no_parse_errors() {
-        let mut newgfa = gfa::GfaParser::new();
-        let parse_outcome = newgfa.parse(
-            "test/gfa_working.gfa",
-            &gfa::ParseOptions {
-                skip_invalid_sequence_test: false,
-                store_raw_lines: true,
-                store_sequences: true,
-                substitute_path_overlaps: true,
-                handle_missing_segment: gfa::MissingSegmentOptions::CreateGhost,
-                handle_missing_bridge: gfa::MissingBridgeOptions::CreateGhostLink,
-                allow_implicit_links: true,
-            },
-        );
+    fn max_errors_stops_parsing() {
+        use crate::errors::ParseMessageCode;
 
-        for error in &newgfa.messages {
-            error.print_formatted_error();
+        let out = std::env::temp_dir().join("parfait_gfa_max_errors_stops_parsing.gfa");
+        std::fs::write(&out, "H\tVN:Z:1.0\n".to_string() + &"S\tx\n".repeat(10)).unwrap();
+
+        let options = gfa::ParseOptions {
+            max_errors: Some(3),
+            ..gfa::ParseOptions::default()
+        };
+
+        let mut parsed = gfa::GfaParser::new();
+        let parsed_result = parsed.parse(&out, &options);
+        let mut streamed = gfa::GfaParser::new();
+        let streamed_result = streamed.parse_streaming(&out, &options, |_| {});
+        let _ = std::fs::remove_file(&out);
+
+        for (newgfa, result) in [(&parsed, parsed_result), (&streamed, streamed_result)] {
+            let codes: Vec<_> = newgfa.messages.iter().map(|m| m.code.clone()).collect();
+            assert_eq!(codes.len(), 4);
+            assert!(
+                codes[..3]
+                    .iter()
+                    .all(|c| *c == ParseMessageCode::InvalidLine)
+            );
+            assert_eq!(codes[3], ParseMessageCode::TooManyErrors);
+            assert_eq!(newgfa.messages[3].line, 5);
+            assert!(result.is_err());
         }
+    }
 
-        assert!(
-            parse_outcome.is_ok(),
-            "Failed to parse GFA file: {:?}",
-            newgfa.messages
+    #[test]
+    fn not_a_gfa_file() {
+        use crate::errors::ParseMessageCode;
+
+        let out = std::env::temp_dir().join("parfait_gfa_not_a_gfa_file.fastq");
+        std::fs::write(&out, "@read1\nGATTACA\n+\nIIIIIII\n".repeat(5)).unwrap();
+
+        let mut parsed = gfa::GfaParser::new();
+        let result = parsed.parse(&out, &gfa::ParseOptions::default());
+
+        let mut unchecked = gfa::GfaParser::new();
+        let options = gfa::ParseOptions {
+            min_record_line_fraction: 0.0,
+            ..gfa::ParseOptions::default()
+        };
+        let _ = unchecked.parse(&out, &options);
+        let _ = std::fs::remove_file(&out);
+
+        assert!(result.is_err());
+        assert_eq!(parsed.messages.len(), 1);
+        assert_eq!(parsed.messages[0].code, ParseMessageCode::NotAGfaFile);
+        assert_eq!(
+            parsed.messages[0].offender,
+            "0 of the first 20 lines are records"
         );
         assert!(
-            newgfa.messages.is_empty(),
-            "There should be no parse errors, but found: {:?}",
-            newgfa.messages
+            unchecked
+                .messages
+                .iter()
+                .all(|m| m.code != ParseMessageCode::NotAGfaFile)
         );
     }
 
     #[test]
-    fn roundtrip() {
+    fn records_with_tag_spans_record_types() {
         let mut newgfa = gfa::GfaParser::new();
-        let parse_outcome = newgfa.parse(
-            "test/gfa_working.gfa",
-            &gfa::ParseOptions {
-                skip_invalid_sequence_test: false,
-                store_raw_lines: false,
-                store_sequences: true,
-                substitute_path_overlaps: true,
-                handle_missing_segment: gfa::MissingSegmentOptions::Ignore,
-                handle_missing_bridge: gfa::MissingBridgeOptions::Ignore,
-                allow_implicit_links: true,
-            },
-        );
-
-        for error in &newgfa.messages {
-            error.print_formatted_error();
+        let options = gfa::ParseOptions::default();
+
+        for line in [
+            "S\ta\tACGT\tRC:i:3",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t+\t0M\tRC:i:1",
+        ] {
+            newgfa.add_line(line, &options).unwrap();
         }
 
-        newgfa
-            .write_to_file("test/gfa_working_roundtrip.gfa", gfa::GFAVersion::V1_2)
-            .expect("Failed to save GFA file");
+        let lines: Vec<_> = newgfa.records_with_tag("RC").map(|r| r.line_no()).collect();
+        assert_eq!(lines, [1, 3]);
+        assert_eq!(newgfa.records_with_tag("KC").count(), 0);
+    }
 
-        let mut newgfa2 = gfa::GfaParser::new();
-        let parse_outcome2 = newgfa2.parse(
-            "test/gfa_working_roundtrip.gfa",
-            &gfa::ParseOptions {
-                skip_invalid_sequence_test: false,
-                store_raw_lines: false,
-                store_sequences: true,
-                substitute_path_overlaps: true,
-                handle_missing_segment: gfa::MissingSegmentOptions::Ignore,
-                handle_missing_bridge: gfa::MissingBridgeOptions::Ignore,
-                allow_implicit_links: true,
-            },
-        );
+    #[test]
+    fn records_in_file_order() {
+        let lines = [
+            "H\tVN:Z:1.0",
+            "S\ta\tACGT",
+            "P\tp\ta+,b+\t*",
+            "L\ta\t+\tb\t+\t0M",
+            "S\tb\tACGT",
+        ];
+        let out = std::env::temp_dir().join("parfait_gfa_records_in_file_order.gfa");
+        std::fs::write(&out, lines.join("\n")).unwrap();
 
-        for error in &newgfa2.messages {
-            error.print_formatted_error();
+        let mut newgfa = gfa::GfaParser::new();
+        let _ = newgfa.parse(&out, &gfa::ParseOptions::default());
+        let _ = std::fs::remove_file(&out);
+
+        let parsed: Vec<_> = newgfa.records.iter().map(|r| r.line_no()).collect();
+        assert_eq!(parsed, [1, 2, 5, 4, 3]);
+
+        let in_file: Vec<_> = newgfa
+            .records_in_file_order()
+            .iter()
+            .map(|r| r.line_no())
+            .collect();
+        assert_eq!(in_file, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn synthetic_name_prefixes() {
+        let options = gfa::ParseOptions {
+            ghost_name_prefix: "ghost:".to_string(),
+            anon_group_prefix: "group:".to_string(),
+            ..gfa::ParseOptions::default()
+        };
+
+        let mut newgfa = gfa::GfaParser::new();
+        let lines = [
+            "S\ta\tACGT",
+            "L\ta\t+\tx\t+\t0M",
+            "L\tx\t+\ta\t+\t0M",
+            "P\tp\ta+,x+\t*",
+        ];
+        for line in lines {
+            let _ = newgfa.add_line(line, &options);
         }
+        let group_no = newgfa.add_line("U\t*\ta x", &options).unwrap();
 
-        assert!(
-            parse_outcome2.is_ok(),
-            "Failed to parse GFA file: {:?}",
-            newgfa2.messages
-        );
+        let ghosts: Vec<_> = newgfa.ghost_segments().map(|s| s.name.as_str()).collect();
+        assert_eq!(ghosts, ["ghost:x"]);
 
+        let ghost = newgfa.find_segment_with_name("ghost:x").unwrap();
         assert_eq!(
-            newgfa.records.len(),
-            newgfa2.records.len(),
-            "Record count mismatch after roundtrip"
+            (ghost.incoming_links.len(), ghost.outgoing_links.len()),
+            (1, 1)
         );
+        let ghost_no = ghost.line_no as u32;
         assert_eq!(
-            newgfa.messages.len(),
-            newgfa2.messages.len(),
-            "Error count mismatch after roundtrip"
+            newgfa.find_path_with_name("p").unwrap().steps[1].segment_id,
+            ghost_no
+        );
+
+        assert!(newgfa.contains_record(&format!("group:U_{group_no}")));
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        use crate::errors::ParseMessageCode;
+
+        let lines = [
+            "H\tVN:Z:1.0",
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t+\t2M",
+        ];
+        let out = std::env::temp_dir().join("parfait_gfa_crlf_line_endings.gfa");
+        std::fs::write(&out, lines.join("\r\n") + "\r\n").unwrap();
+
+        let mut parsed = gfa::GfaParser::new();
+        let _ = parsed.parse(&out, &gfa::ParseOptions::default());
+        let mut mapped = gfa::GfaParser::new();
+        let _ = mapped.parse_mmap(&out, &gfa::ParseOptions::default());
+        let _ = std::fs::remove_file(&out);
+
+        let mut added = gfa::GfaParser::new();
+        for line in lines {
+            added
+                .add_line(&format!("{line}\r"), &gfa::ParseOptions::default())
+                .expect("test line should parse");
+        }
+
+        for newgfa in [parsed, mapped, added] {
+            assert!(!newgfa.messages.iter().any(|m| matches!(
+                m.code,
+                ParseMessageCode::InvalidSequence | ParseMessageCode::InvalidCIGAR
+            )));
+            assert_eq!(newgfa.segments().next().unwrap().sequence, "ACGT");
+            assert_eq!(newgfa.links().next().unwrap().overlap, "2M");
+        }
+    }
+
+    #[test]
+    fn comments_and_blank_lines() {
+        let out = std::env::temp_dir().join("parfait_gfa_comments_and_blank_lines.gfa");
+        std::fs::write(&out, "# CHROM chr1\nH\tVN:Z:1.0\n\nS\ta\tA\n  \n#\n").unwrap();
+
+        let mut counted = gfa::GfaParser::new();
+        let _ = counted.parse(&out, &gfa::ParseOptions::default());
+
+        let mut captured = gfa::GfaParser::new();
+        let options = gfa::ParseOptions {
+            capture_comments: true,
+            ..gfa::ParseOptions::default()
+        };
+        let _ = captured.parse_streaming(&out, &options, |_| {});
+        let _ = std::fs::remove_file(&out);
+
+        for newgfa in [&counted, &captured] {
+            assert_eq!(newgfa.comment_count, 2);
+            assert_eq!(newgfa.blank_line_count, 2);
+        }
+
+        assert!(counted.comments.is_empty());
+        assert_eq!(
+            captured.comments,
+            [(1, "# CHROM chr1".to_string()), (6, "#".to_string())]
         );
+    }
+
+    #[test]
+    fn strict_line_types_reject_unknown_lines() {
+        use crate::errors::{ParseMessageCode, ParseMessageSeverity};
+
+        let out = std::env::temp_dir().join("parfait_gfa_strict_line_types.gfa");
+        std::fs::write(
+            &out,
+            "H\tVN:Z:1.0\n# comment\n\nS\ta\tA\nX\tunknown\nSx\tb\tA\n",
+        )
+        .unwrap();
+
+        let mut lenient = gfa::GfaParser::new();
+        assert!(lenient.parse(&out, &gfa::ParseOptions::default()).is_ok());
+        assert_eq!(lenient.segments().count(), 1);
+
+        let mut strict = gfa::GfaParser::new();
+        let options = gfa::ParseOptions {
+            strict_line_types: true,
+            ..gfa::ParseOptions::default()
+        };
+        let errors = strict.parse(&out, &options).unwrap_err();
+        let _ = std::fs::remove_file(&out);
 
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, [5, 6]);
         assert!(
-            parse_outcome.is_ok(),
-            "Failed to parse GFA file: {:?}",
-            newgfa.messages
+            errors
+                .iter()
+                .all(|e| e.code == ParseMessageCode::UnknownLine)
         );
+        assert!(
+            errors
+                .iter()
+                .all(|e| e.severity() == ParseMessageSeverity::Fatal)
+        );
+
+        // a record type that is recognised by its first byte only is reported, not a panic
+        let errors = lenient
+            .add_line("Sx\tb\tA", &gfa::ParseOptions::default())
+            .unwrap_err();
+        assert_eq!(errors[0].code, ParseMessageCode::UnknownLine);
+        assert_eq!(errors[0].severity(), ParseMessageSeverity::Info);
+    }
+
+    #[test]
+    fn parallel_parse_matches_serial() {
+        let parse = |file: &str, threads: usize| {
+            let mut newgfa = gfa::GfaParser::new();
+            let options = gfa::ParseOptions {
+                threads,
+                ..gfa::ParseOptions::default()
+            };
+            let _ = newgfa.parse(file, &options);
+            newgfa
+        };
+
+        for file in ["test/gfa_working.gfa", "test/fragment.gfa", "test/path.gfa"] {
+            let serial = parse(file, 1);
+
+            let names = |g: &gfa::GfaParser| {
+                g.segments()
+                    .map(|s| (s.line_no, s.name.clone()))
+                    .collect::<Vec<_>>()
+            };
+
+            for parallel in [parse(file, 0), parse(file, 2)] {
+                assert_eq!(names(&serial), names(&parallel));
+                assert_eq!(serial.records.len(), parallel.records.len());
+                assert_eq!(serial.messages.len(), parallel.messages.len());
+                assert_eq!(serial.tag_names, parallel.tag_names);
+            }
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn parallel_means_every_core() {
+        let options = |parallel: bool, threads: usize| gfa::ParseOptions {
+            parallel,
+            threads,
+            ..gfa::ParseOptions::default()
+        };
+
+        assert_eq!(options(true, 1).segment_threads(), 0);
+        assert_eq!(options(true, 2).segment_threads(), 2);
+        assert_eq!(options(false, 1).segment_threads(), 1);
     }
 }
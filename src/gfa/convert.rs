@@ -0,0 +1,722 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    errors::{ParseMessage, ParseMessageCode},
+    gfa::{GFAVersion, GfaParser},
+    line::{
+        containment::Containment,
+        edge::{Edge, FROM_INTERVAL_TAG, TO_INTERVAL_TAG},
+        gap::Gap,
+        jump::Jump,
+        link::Link,
+        ordered::OrderedGroup,
+        path::{Path, Step},
+        record::GfaRecord,
+        segment::Segment,
+        symbol::SymbolTable,
+        utils::{
+            Alignment, Interval, IntervalPosition, cigar_query_length, cigar_reference_length,
+        },
+    },
+    optional_field::{OptionalFieldValue, TagMap},
+};
+
+/// Conversion between GFA versions.
+impl GfaParser {
+    /// Rewrites every record in place so that it can be represented in `target`.
+    ///
+    /// Going to v2:
+    /// - links and containments become edges, with intervals computed from segment lengths
+    /// - links written from edges get their `fi`/`ti` intervals and `TS` trace back
+    /// - jumps become gaps
+    /// - paths and walks become ordered groups
+    ///
+    /// Going to v1:
+    /// - edges become containments when one side covers a whole segment, links otherwise;
+    ///   links keep the intervals and trace in tags (see [`crate::line::edge::FROM_INTERVAL_TAG`])
+    /// - gaps become jumps in v1.2, and links with a `DI` tag before that
+    /// - ordered groups become paths, with edge members expanded to the segments they
+    ///   connect (see [`OrderedGroup::to_path`])
+    /// - fragments become containments of their external sequence in their segment, see
+    ///   [`crate::line::fragment::Fragment::to_containment`]. The external sequence is
+    ///   added as a segment without a sequence, flagged `external`, unless a segment of
+    ///   that name already exists
+    /// - unordered groups are dropped
+    ///
+    /// Anything that loses information produces a `LossyConversion` message,
+    /// which are returned rather than added to [`GfaParser::messages`].
+    pub fn convert_to(&mut self, target: GFAVersion) -> Vec<ParseMessage> {
+        let mut messages = vec![];
+
+        let lengths: HashMap<String, i32> = self
+            .segments()
+            .map(|s| (s.name.clone(), s.get_length()))
+            .collect();
+
+        let names: HashMap<usize, String> = self
+            .segments()
+            .map(|s| (s.line_no, s.name.clone()))
+            .collect();
+
+        let to_v2 = target == GFAVersion::V2;
+
+        // groups can reference edges, so they are expanded before the edges are converted
+        let mut group_paths: HashMap<usize, Path> = HashMap::new();
+        if !to_v2 {
+            for group in self.ordered_groups() {
+                let (path, group_messages) = group.to_path(self);
+                messages.extend(group_messages);
+                group_paths.extend(path.map(|p| (group.line_no, p)));
+            }
+        }
+
+        let mut converted = Vec::with_capacity(self.records.len());
+        let mut synthesized: HashSet<String> = HashSet::new();
+
+        for record in std::mem::take(&mut self.records) {
+            if let GfaRecord::Fragment(f) = &record
+                && !to_v2
+                && !lengths.contains_key(&f.external_name.reference)
+                && synthesized.insert(f.external_name.reference.clone())
+            {
+                let segment = self.external_segment(&f.external_name.reference);
+                messages.push(lossy(
+                    f.line_no,
+                    &format!("external sequence {} added as segment", segment.name),
+                ));
+                converted.push(GfaRecord::Segment(segment));
+            }
+
+            let record = if to_v2 {
+                self.record_to_v2(record, &lengths, &names, &mut messages)
+            } else {
+                record_to_v1(
+                    record,
+                    &target,
+                    &lengths,
+                    &mut group_paths,
+                    &mut self.symbols,
+                    &mut messages,
+                )
+            };
+
+            converted.extend(record);
+        }
+
+        self.records = converted;
+
+        for header in self.headers_mut() {
+            header.version = Some(target.to_string());
+            header
+                .tags
+                .add_tag("VN", OptionalFieldValue::String(target.to_string()));
+        }
+
+        self.version = target;
+
+        self.reindex();
+        self.rebuild_adjacency();
+
+        messages
+    }
+
+    // stands in for the external sequence of a fragment, which isn't part of the graph
+    fn external_segment(&mut self, name: &str) -> Segment {
+        let mut segment = Segment::builder(name).build();
+        segment.line_no = self.get_available_line_no();
+        segment.tags.add_flag("external");
+
+        segment
+    }
+
+    fn record_to_v2(
+        &mut self,
+        record: GfaRecord,
+        lengths: &HashMap<String, i32>,
+        names: &HashMap<usize, String>,
+        messages: &mut Vec<ParseMessage>,
+    ) -> Option<GfaRecord> {
+        let length_of = |name: &str| lengths.get(name).copied().unwrap_or(0);
+
+        let converted = match record {
+            GfaRecord::Segment(mut s) => {
+                s.length = Some(s.get_length());
+                s.set_ln_tag(None);
+                GfaRecord::Segment(s)
+            }
+            // the flag isn't written out, but the interval tags are
+            GfaRecord::Link(mut l)
+                if l.tags.has_flag("edge")
+                    || l.tags.contains(FROM_INTERVAL_TAG)
+                    || l.tags.contains(TO_INTERVAL_TAG) =>
+            {
+                // written from an edge, so the intervals and trace may have been kept
+                let intervals = Edge::take_stashed_intervals(&mut l.tags);
+                let trace = l.tags.get::<String>("TS");
+
+                let alignment = match trace {
+                    Some(trace) if l.overlap == "*" => {
+                        l.tags.remove_tag("TS");
+                        Some(Alignment::Trace(trace))
+                    }
+                    _ => cigar_alignment(&l.overlap),
+                };
+
+                let (from_interval, to_interval) = intervals.unwrap_or_else(|| {
+                    dovetail_intervals(
+                        &l,
+                        length_of(self.resolve(l.from_segment)),
+                        length_of(self.resolve(l.to_segment)),
+                    )
+                });
+
+                l.tags.remove_flag("edge");
+
+                GfaRecord::Edge(Edge {
+                    line_no: l.line_no,
+                    raw: l.raw,
+                    id: take_id(&mut l.tags),
+                    tags: l.tags,
+                    from_segment: l.from_segment,
+                    from_orientation: l.from_orientation,
+                    to_segment: l.to_segment,
+                    to_orientation: l.to_orientation,
+                    from_interval,
+                    to_interval,
+                    alignment,
+                })
+            }
+            GfaRecord::Link(mut l) => {
+                let (from_interval, to_interval) = dovetail_intervals(
+                    &l,
+                    length_of(self.resolve(l.from_segment)),
+                    length_of(self.resolve(l.to_segment)),
+                );
+
+                GfaRecord::Edge(Edge {
+                    line_no: l.line_no,
+                    raw: l.raw,
+                    id: take_id(&mut l.tags),
+                    tags: l.tags,
+                    from_segment: l.from_segment,
+                    from_orientation: l.from_orientation,
+                    to_segment: l.to_segment,
+                    to_orientation: l.to_orientation,
+                    from_interval,
+                    to_interval,
+                    alignment: cigar_alignment(&l.overlap),
+                })
+            }
+            GfaRecord::Containment(mut c) => {
+                let container_length = length_of(self.resolve(c.container));
+                let contained_length = length_of(self.resolve(c.contained));
+
+                let covered = match overlap_lengths(&c.overlap) {
+                    (0, _) => contained_length,
+                    (covered, _) => covered,
+                };
+
+                GfaRecord::Edge(Edge {
+                    line_no: c.line_no,
+                    raw: c.raw,
+                    id: take_id(&mut c.tags),
+                    tags: c.tags,
+                    from_segment: c.container,
+                    from_orientation: c.container_orientation,
+                    to_segment: c.contained,
+                    to_orientation: c.contained_orientation,
+                    from_interval: interval(c.position, c.position + covered, container_length),
+                    to_interval: interval(0, contained_length, contained_length),
+                    alignment: cigar_alignment(&c.overlap),
+                })
+            }
+            GfaRecord::Jump(mut j) => {
+                if j.distance.is_none() {
+                    messages.push(lossy(
+                        j.line_no,
+                        "jump has no distance; gap distance set to 0",
+                    ));
+                }
+
+                GfaRecord::Gap(Gap {
+                    line_no: j.line_no,
+                    raw: j.raw,
+                    id: take_id(&mut j.tags),
+                    tags: j.tags,
+                    from_segment: j.from_segment,
+                    from_orientation: j.from_orientation,
+                    to_segment: j.to_segment,
+                    to_orientation: j.to_orientation,
+                    distance: j.distance.unwrap_or(0),
+                    variance: None,
+                })
+            }
+            GfaRecord::Path(p) => {
+                if p.overlaps.iter().any(|o| o != "*") {
+                    messages.push(lossy(
+                        p.line_no,
+                        &format!("overlaps of path {} dropped", p.name),
+                    ));
+                }
+
+                let members = step_names(&p.steps, names);
+
+                GfaRecord::OrderedGroup(OrderedGroup {
+                    line_no: p.line_no,
+                    raw: p.raw,
+                    tags: p.tags,
+                    name: p.name,
+                    members,
+                })
+            }
+            GfaRecord::Walk(w) => {
+                let name = w.to_pansn_path_name();
+
+                messages.push(lossy(
+                    w.line_no,
+                    &format!("walk {name} became an ordered group; sequence range dropped"),
+                ));
+
+                let members = step_names(&w.walk, names);
+
+                GfaRecord::OrderedGroup(OrderedGroup {
+                    line_no: w.line_no,
+                    raw: w.raw,
+                    tags: w.tags,
+                    name: self.ensure_name_unique(w.line_no, name),
+                    members,
+                })
+            }
+            other => other,
+        };
+
+        Some(converted)
+    }
+}
+
+// segment names with an orientation suffix, as used by group members
+fn step_names(steps: &[Step], names: &HashMap<usize, String>) -> Vec<String> {
+    steps
+        .iter()
+        .filter_map(|step| {
+            names
+                .get(&(step.segment_id as usize))
+                .map(|name| format!("{name}{}", if step.orientation { "+" } else { "-" }))
+        })
+        .collect()
+}
+
+fn record_to_v1(
+    record: GfaRecord,
+    target: &GFAVersion,
+    lengths: &HashMap<String, i32>,
+    group_paths: &mut HashMap<usize, Path>,
+    symbols: &mut SymbolTable,
+    messages: &mut Vec<ParseMessage>,
+) -> Option<GfaRecord> {
+    let converted = match record {
+        GfaRecord::Segment(mut s) => {
+            if let Some(length) = s.length.take()
+                && (s.sequence == "*" || s.sequence.len() as i32 != length)
+            {
+                s.set_ln_tag(Some(length));
+            }
+
+            GfaRecord::Segment(s)
+        }
+        GfaRecord::Edge(e) => {
+            let is_whole = |i: &Interval, name: &str| {
+                i.begin.position == 0
+                    && i.end.is_last
+                    && lengths.get(name).is_some_and(|l| *l == i.end.position)
+            };
+
+            let from_whole = is_whole(&e.from_interval, symbols.resolve(e.from_segment));
+            let to_whole = is_whole(&e.to_interval, symbols.resolve(e.to_segment));
+
+            let mut tags = e.tags;
+            let overlap = match e.alignment {
+                Some(Alignment::CIGAR(cigar)) => cigar,
+                Some(Alignment::Trace(trace)) => {
+                    tags.add_tag("TS", OptionalFieldValue::String(trace));
+                    "*".to_string()
+                }
+                None => "*".to_string(),
+            };
+
+            if let Some(id) = e.id {
+                tags.add_tag("ID", OptionalFieldValue::String(id));
+            }
+
+            if to_whole != from_whole {
+                let (container, contained, position) = if to_whole {
+                    (
+                        (e.from_segment, e.from_orientation),
+                        (e.to_segment, e.to_orientation),
+                        e.from_interval.begin.position,
+                    )
+                } else {
+                    (
+                        (e.to_segment, e.to_orientation),
+                        (e.from_segment, e.from_orientation),
+                        e.to_interval.begin.position,
+                    )
+                };
+
+                GfaRecord::Containment(Containment {
+                    line_no: e.line_no,
+                    raw: e.raw,
+                    tags,
+                    container: container.0,
+                    container_orientation: container.1,
+                    contained: contained.0,
+                    contained_orientation: contained.1,
+                    position,
+                    overlap,
+                })
+            } else {
+                let from_end = e.from_interval.begin.position == 0 || e.from_interval.end.is_last;
+                let to_end = e.to_interval.begin.position == 0 || e.to_interval.end.is_last;
+
+                if !(from_end && to_end) {
+                    messages.push(lossy(
+                        e.line_no,
+                        &format!(
+                            "edge intervals {} | {} are not a dovetail; converted to a link with fi/ti tags",
+                            e.from_interval, e.to_interval
+                        ),
+                    ));
+                }
+
+                // this link was an edge in another life
+                tags.add_flag("edge");
+                Edge::stash_intervals(&e.from_interval, &e.to_interval, &mut tags);
+
+                GfaRecord::Link(Link {
+                    line_no: e.line_no,
+                    raw: e.raw,
+                    tags,
+                    from_segment: e.from_segment,
+                    from_orientation: e.from_orientation,
+                    to_segment: e.to_segment,
+                    to_orientation: e.to_orientation,
+                    overlap,
+                })
+            }
+        }
+        GfaRecord::Gap(g) => {
+            let mut tags = g.tags;
+
+            if let Some(id) = g.id {
+                tags.add_tag("ID", OptionalFieldValue::String(id));
+            }
+
+            if let Some(variance) = g.variance {
+                tags.add_tag("VA", OptionalFieldValue::Int(variance));
+            }
+
+            // this link/jump was a gap in another life
+            tags.add_flag("gap");
+
+            if *target == GFAVersion::V1_2 {
+                GfaRecord::Jump(Jump {
+                    line_no: g.line_no,
+                    raw: g.raw,
+                    tags,
+                    from_segment: g.from_segment,
+                    from_orientation: g.from_orientation,
+                    to_segment: g.to_segment,
+                    to_orientation: g.to_orientation,
+                    distance: Some(g.distance),
+                })
+            } else {
+                // jumps only exist in v1.2, use a link before that
+                messages.push(lossy(g.line_no, "gap became a link with a DI tag"));
+                tags.add_tag("DI", OptionalFieldValue::Int(g.distance));
+
+                GfaRecord::Link(Link {
+                    line_no: g.line_no,
+                    raw: g.raw,
+                    tags,
+                    from_segment: g.from_segment,
+                    from_orientation: g.from_orientation,
+                    to_segment: g.to_segment,
+                    to_orientation: g.to_orientation,
+                    overlap: "*".to_string(),
+                })
+            }
+        }
+        GfaRecord::Fragment(f) => {
+            messages.push(lossy(
+                f.line_no,
+                &format!(
+                    "fragment of {} became a containment; fragment interval {} dropped",
+                    f.segment_name, f.fragment_interval
+                ),
+            ));
+
+            let segment = symbols.intern(&f.segment_name);
+            let external = symbols.intern(&f.external_name.reference);
+            GfaRecord::Containment(f.to_containment(segment, external))
+        }
+        GfaRecord::OrderedGroup(o) => match group_paths.remove(&o.line_no) {
+            Some(path) => GfaRecord::Path(path),
+            None => {
+                messages.push(lossy(
+                    o.line_no,
+                    &format!("ordered group {} dropped", o.name),
+                ));
+                return None;
+            }
+        },
+        GfaRecord::UnorderedGroup(u) => {
+            messages.push(lossy(
+                u.line_no,
+                &format!("unordered group {} dropped", u.name),
+            ));
+            return None;
+        }
+        other => other,
+    };
+
+    Some(converted)
+}
+
+// the overlap sits at the end of the from segment and the start of the
+// to segment, on whichever strand they are traversed
+fn dovetail_intervals(link: &Link, from_length: i32, to_length: i32) -> (Interval, Interval) {
+    let (from_overlap, to_overlap) = overlap_lengths(&link.overlap);
+
+    let from_interval = if link.from_orientation {
+        interval(from_length - from_overlap, from_length, from_length)
+    } else {
+        interval(0, from_overlap, from_length)
+    };
+
+    let to_interval = if link.to_orientation {
+        interval(0, to_overlap, to_length)
+    } else {
+        interval(to_length - to_overlap, to_length, to_length)
+    };
+
+    (from_interval, to_interval)
+}
+
+fn lossy(line_no: usize, what: &str) -> ParseMessage {
+    ParseMessage::new(line_no, ParseMessageCode::LossyConversion, what.to_string())
+}
+
+fn interval(begin: i32, end: i32, length: i32) -> Interval {
+    Interval {
+        begin: IntervalPosition {
+            position: begin,
+            is_last: begin == length,
+        },
+        end: IntervalPosition {
+            position: end,
+            is_last: end == length,
+        },
+    }
+}
+
+fn cigar_alignment(overlap: &str) -> Option<Alignment> {
+    (overlap != "*").then(|| Alignment::CIGAR(overlap.to_string()))
+}
+
+fn take_id(tags: &mut TagMap) -> Option<String> {
+    let id = tags.get::<String>("ID");
+    tags.tags.remove("ID");
+    id
+}
+
+// (from, to) lengths covered by an overlap CIGAR, (0, 0) when unknown
+fn overlap_lengths(cigar: &str) -> (i32, i32) {
+    (
+        cigar_reference_length(cigar).unwrap_or(0) as i32,
+        cigar_query_length(cigar).unwrap_or(0) as i32,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::ParseMessageCode,
+        gfa::{GFAVersion, GfaParser, ParseOptions, WriteOptions},
+    };
+
+    fn build(lines: &[&str]) -> GfaParser {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in lines {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        gfa
+    }
+
+    #[test]
+    fn v1_to_v2_and_back() {
+        let mut gfa = build(&[
+            "H\tVN:Z:1.0",
+            "S\ta\tACGTACGT",
+            "S\tb\tACGT",
+            "S\tc\tGT",
+            "L\ta\t+\tb\t+\t2M",
+            "C\ta\t+\tc\t+\t2\t2M",
+            "J\tb\t+\tc\t-\t10",
+            "P\tp1\ta+,b+\t2M",
+        ]);
+
+        let messages = gfa.convert_to(GFAVersion::V2);
+        assert_eq!(gfa.version, GFAVersion::V2);
+
+        let edges: Vec<_> = gfa.edges().collect();
+        assert_eq!(edges.len(), 2);
+        assert_eq!(edges[0].from_interval.to_string(), "6..8$");
+        assert_eq!(edges[0].to_interval.to_string(), "0..2");
+        assert_eq!(edges[1].from_interval.to_string(), "2..4");
+        assert_eq!(edges[1].to_interval.to_string(), "0..2$");
+
+        assert_eq!(gfa.gaps().next().unwrap().distance, 10);
+        assert_eq!(gfa.ordered_groups().next().unwrap().members, ["a+", "b+"]);
+        assert_eq!(messages.len(), 1);
+
+        let a = gfa.find_segment_with_name("a").unwrap();
+        assert_eq!(a.outgoing_edges.len(), 2);
+        assert!(a.outgoing_links.is_empty());
+
+        let messages = gfa.convert_to(GFAVersion::V1_2);
+
+        assert_eq!(gfa.links().count(), 1);
+        assert_eq!(gfa.containments().next().unwrap().position, 2);
+        assert_eq!(gfa.jumps().next().unwrap().distance, Some(10));
+        assert_eq!(gfa.ordered_groups().count(), 0);
+        assert!(
+            gfa.to_gfa_string(GFAVersion::V1_2, &WriteOptions::default())
+                .contains("P\tp1\ta+,b+\t*")
+        );
+        assert!(
+            messages
+                .iter()
+                .all(|m| m.code == ParseMessageCode::LossyConversion)
+        );
+
+        let b = gfa.find_segment_with_name("b").unwrap();
+        assert_eq!(b.incoming_links.len(), 1);
+        assert_eq!(b.outgoing_jumps.len(), 1);
+    }
+
+    #[test]
+    fn fragment_becomes_containment() {
+        let mut gfa = build(&[
+            "H\tVN:Z:2.0",
+            "S\ta\t8\tACGTACGT",
+            "F\ta\tread-\t2\t6\t0\t4\t4M",
+            "F\ta\tread+\t0\t2\t10\t12\t2,1",
+        ]);
+
+        let messages = gfa.convert_to(GFAVersion::V1_2);
+
+        // one for the synthetic segment, one per fragment
+        assert_eq!(messages.len(), 3);
+        assert!(
+            messages
+                .iter()
+                .all(|m| m.code == ParseMessageCode::LossyConversion)
+        );
+
+        let external: Vec<_> = gfa
+            .segments()
+            .filter(|s| s.tags.has_flag("external"))
+            .collect();
+        assert_eq!(external.len(), 1);
+        assert_eq!(external[0].name, "read");
+
+        let lines: Vec<String> = gfa
+            .containments()
+            .map(|c| c.to_raw_line(GFAVersion::V1_2, &gfa))
+            .collect();
+        assert!(lines[0].starts_with("C\ta\t+\tread\t-\t2\t4M"));
+        assert!(lines[1].starts_with("C\ta\t+\tread\t+\t0\t*"));
+        assert!(lines[1].contains("TS:Z:2,1"));
+
+        let a = gfa.find_segment_with_name("a").unwrap();
+        assert_eq!(a.containments.len(), 2);
+
+        let written = gfa.to_gfa_string(GFAVersion::V1_2, &WriteOptions::default());
+        assert!(written.contains("S\tread\t*\n"));
+        assert!(!written.contains("PF:Z:"));
+    }
+
+    #[test]
+    fn edge_survives_v1_file_round_trip() {
+        let mut gfa = build(&[
+            "H\tVN:Z:2.0",
+            "S\ta\t8\tACGTACGT",
+            "S\tb\t4\tACGT",
+            "E\te1\ta+\tb-\t2\t5\t1\t3\t2,1",
+        ]);
+
+        gfa.convert_to(GFAVersion::V1_2);
+        let written = gfa.to_gfa_string(GFAVersion::V1_2, &WriteOptions::default());
+        assert!(!written.contains("edge"));
+
+        let mut gfa = build(&written.lines().collect::<Vec<_>>());
+        gfa.convert_to(GFAVersion::V2);
+
+        let edge = gfa.edges().next().unwrap();
+        assert_eq!(
+            edge.to_raw_line(GFAVersion::V2, &gfa),
+            "E\te1\ta+\tb-\t2\t5\t1\t3\t2,1"
+        );
+    }
+
+    #[test]
+    fn fragment_written_as_v1() {
+        let gfa = build(&[
+            "H\tVN:Z:2.0",
+            "S\ta\t8\tACGTACGT",
+            "F\ta\tread-\t2\t6\t0\t4\t4M",
+            "F\ta\tread+\t0\t2\t10\t12\t2M",
+        ]);
+
+        let written = gfa.to_gfa_string(GFAVersion::V1_2, &WriteOptions::default());
+        let lines: Vec<&str> = written.lines().skip(2).collect();
+
+        assert_eq!(
+            lines,
+            [
+                "S\tread\t*",
+                "C\ta\t+\tread\t-\t2\t4M",
+                "C\ta\t+\tread\t+\t0\t2M",
+            ]
+        );
+    }
+
+    #[test]
+    fn edge_survives_v1_round_trip() {
+        let mut gfa = build(&[
+            "H\tVN:Z:2.0",
+            "S\ta\t8\tACGTACGT",
+            "S\tb\t4\tACGT",
+            "E\te1\ta+\tb-\t2\t5\t1\t3\t2,1",
+        ]);
+
+        gfa.convert_to(GFAVersion::V1_2);
+
+        let link = gfa.links().next().unwrap();
+        assert_eq!(link.overlap, "*");
+        assert_eq!(link.tags.get::<String>("fi").as_deref(), Some("2..5"));
+        assert_eq!(link.tags.get::<String>("TS").as_deref(), Some("2,1"));
+
+        gfa.convert_to(GFAVersion::V2);
+
+        let edge = gfa.edges().next().unwrap();
+        assert_eq!(
+            edge.to_raw_line(GFAVersion::V2, &gfa),
+            "E\te1\ta+\tb-\t2\t5\t1\t3\t2,1"
+        );
+    }
+}
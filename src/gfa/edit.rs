@@ -0,0 +1,822 @@
+use std::collections::{HashMap, HashSet, hash_map::Entry};
+
+use crate::{
+    errors::{ParseMessage, ParseMessageCode},
+    gfa::{GfaParser, MissingSegmentOptions, ParseOptions},
+    line::{
+        link::Link,
+        path::Step,
+        record::GfaRecord,
+        rename_member,
+        segment::Segment,
+        symbol::Symbol,
+        utils::{cigar_reference_length, is_valid_name, parse_cigar},
+    },
+    optional_field::{OptionalFieldValue, TagMap},
+};
+
+// tags that count something, so duplicates add up instead of being dropped
+static COUNT_TAGS: [&str; 3] = ["RC", "FC", "KC"];
+
+/// Editing the graph after parsing.
+impl GfaParser {
+    /// Removes the segment called `name` along with every link, jump, containment,
+    /// edge, gap and fragment that references it. Returns false if there is no
+    /// segment with that name.
+    ///
+    /// `handle_trails` decides what happens to paths, walks and groups that step
+    /// through the segment:
+    /// - [`MissingSegmentOptions::CreateGhost`] keeps them by putting a ghost segment
+    ///   with the same name in place of the removed one
+    /// - [`MissingSegmentOptions::SoftSkip`] drops the steps (or group members)
+    /// - [`MissingSegmentOptions::HardSkip`] removes the whole path, walk or group
+    /// - [`MissingSegmentOptions::Ignore`] keeps the steps, pointing at no segment, and
+    ///   the group members (see [`crate::line::path::Path::missing_segments`])
+    pub fn remove_segment(&mut self, name: &str, handle_trails: MissingSegmentOptions) -> bool {
+        let Some(segment) = self
+            .segment_line_no(name)
+            .and_then(|n| self.find_segment(n))
+        else {
+            return false;
+        };
+
+        let line_no = segment.line_no;
+
+        let mut removed: HashSet<usize> = HashSet::from([line_no]);
+        removed.extend(segment.get_outgoing_bridges());
+        removed.extend(segment.get_incoming_bridges());
+        removed.extend(segment.fragments.iter());
+
+        self.remove_from_trails(line_no, name, &handle_trails, &mut removed);
+        self.remove_records(&removed);
+
+        if handle_trails == MissingSegmentOptions::CreateGhost {
+            let ghost_no = self.create_ghost_segment(name.to_string()).line_no as u32;
+
+            for steps in self.records.iter_mut().filter_map(|r| match r {
+                GfaRecord::Path(p) => Some(&mut p.steps),
+                GfaRecord::Walk(w) => Some(&mut w.walk),
+                _ => None,
+            }) {
+                steps
+                    .iter_mut()
+                    .filter(|s| s.segment_id as usize == line_no)
+                    .for_each(|s| s.segment_id = ghost_no);
+            }
+        }
+
+        if handle_trails == MissingSegmentOptions::Ignore {
+            for (steps, missing) in self.records.iter_mut().filter_map(|r| match r {
+                GfaRecord::Path(p) => Some((&mut p.steps, &mut p.missing_segments)),
+                GfaRecord::Walk(w) => Some((&mut w.walk, &mut w.missing_segments)),
+                _ => None,
+            }) {
+                for (idx, step) in steps.iter_mut().enumerate() {
+                    if step.segment_id as usize == line_no {
+                        step.segment_id = 0;
+                        missing.push((idx, name.to_string()));
+                    }
+                }
+            }
+        }
+
+        true
+    }
+
+    /// Renames the segment called `old` to `new`, along with every link, jump,
+    /// containment, edge, gap, fragment and group member that references it. Paths and
+    /// walks refer to segments by line number, so they follow along without changes.
+    ///
+    /// Returns `SegmentNotFound` if there is no segment called `old`, `InvalidID` if
+    /// `new` isn't a valid name, and `NamespaceCollision` if a record called `new`
+    /// already exists. Nothing is changed in those cases.
+    pub fn rename_segment(&mut self, old: &str, new: &str) -> Result<(), ParseMessageCode> {
+        let Some(idx) = self.segment_record_index(old) else {
+            return Err(ParseMessageCode::SegmentNotFound);
+        };
+
+        if !is_valid_name(new) {
+            return Err(ParseMessageCode::InvalidID);
+        }
+
+        if self.contains_record(new) {
+            return Err(ParseMessageCode::NamespaceCollision);
+        }
+
+        if let Some(segment) = self.records[idx].as_mut_segment() {
+            segment.name = new.to_string();
+        }
+
+        let old_symbol = self.intern(old);
+        let new_symbol = self.intern(new);
+
+        let rename_symbol = |symbol: &mut Symbol| {
+            if *symbol == old_symbol {
+                *symbol = new_symbol;
+            }
+        };
+        let rename = |name: &mut String| {
+            if name == old {
+                *name = new.to_string();
+            }
+        };
+
+        for record in self.records.iter_mut() {
+            match record {
+                GfaRecord::Link(l) => {
+                    rename_symbol(&mut l.from_segment);
+                    rename_symbol(&mut l.to_segment);
+                }
+                GfaRecord::Jump(j) => {
+                    rename_symbol(&mut j.from_segment);
+                    rename_symbol(&mut j.to_segment);
+                }
+                GfaRecord::Containment(c) => {
+                    rename_symbol(&mut c.container);
+                    rename_symbol(&mut c.contained);
+                }
+                GfaRecord::Edge(e) => {
+                    rename_symbol(&mut e.from_segment);
+                    rename_symbol(&mut e.to_segment);
+                }
+                GfaRecord::Gap(g) => {
+                    rename_symbol(&mut g.from_segment);
+                    rename_symbol(&mut g.to_segment);
+                }
+                GfaRecord::Fragment(f) => rename(&mut f.segment_name),
+                GfaRecord::OrderedGroup(o) => {
+                    o.members
+                        .iter_mut()
+                        .for_each(|m| rename_member(m, |n| (n == old).then_some(new)));
+                }
+                GfaRecord::UnorderedGroup(u) => {
+                    u.members
+                        .iter_mut()
+                        .for_each(|m| rename_member(m, |n| (n == old).then_some(new)));
+                }
+                _ => {}
+            }
+        }
+
+        self.reindex();
+
+        Ok(())
+    }
+
+    /// Replaces the link at `link_line_no`, say `A+ -> B-`, with `new_segment` and two
+    /// links through it: `A+ -> C+` and `C+ -> B-`. The overlap of the removed link is cut
+    /// in half (at the middle of the bases it covers), with the first half going to the
+    /// link into the new segment. An unknown (`*`) overlap stays unknown on both links.
+    ///
+    /// The new links have no tags. Paths and walks stepping from `A` to `B` are not
+    /// changed, so they no longer follow a link until the new segment is added to them.
+    ///
+    /// Returns `LinkNotFound` if there is no link at `link_line_no`, `SegmentNotFound` if
+    /// one of its segments is missing, `InvalidID` if the segment's name isn't valid, and
+    /// `NamespaceCollision` if a record with that name already exists. Nothing is changed
+    /// in those cases.
+    pub fn split_link(
+        &mut self,
+        link_line_no: usize,
+        new_segment: Segment,
+    ) -> Result<(), ParseMessageCode> {
+        let Some(link) = self.find_link(link_line_no) else {
+            return Err(ParseMessageCode::LinkNotFound);
+        };
+
+        if !is_valid_name(&new_segment.name) {
+            return Err(ParseMessageCode::InvalidID);
+        }
+
+        if self.contains_record(&new_segment.name) {
+            return Err(ParseMessageCode::NamespaceCollision);
+        }
+
+        let (from, from_orientation) = (link.from_segment, link.from_orientation);
+        let (to, to_orientation) = (link.to_segment, link.to_orientation);
+        let (first_overlap, second_overlap) = split_overlap(&link.overlap);
+
+        // a link read with MissingSegmentOptions::Ignore can point at no segment. checked
+        // before the link is removed, so adding the new links below can't fail halfway
+        if [from, to]
+            .iter()
+            .any(|s| self.segment_record_index(self.resolve(*s)).is_none())
+        {
+            return Err(ParseMessageCode::SegmentNotFound);
+        }
+
+        self.remove_records(&HashSet::from([link_line_no]));
+
+        let first_error = |errors: Vec<ParseMessage>| {
+            errors
+                .into_iter()
+                .next()
+                .map_or(ParseMessageCode::UnspecifiedError, |e| e.code)
+        };
+
+        let options = ParseOptions::default();
+
+        let name = new_segment.name.clone();
+        self.add_record(GfaRecord::Segment(new_segment), &options)
+            .map_err(first_error)?;
+        let middle = self.intern(&name);
+
+        for link in [
+            Link::builder(from, middle)
+                .from_orientation(from_orientation)
+                .overlap(&first_overlap)
+                .build(),
+            Link::builder(middle, to)
+                .to_orientation(to_orientation)
+                .overlap(&second_overlap)
+                .build(),
+        ] {
+            self.add_record(GfaRecord::Link(link), &options)
+                .map_err(first_error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes every link with the same segments, orientations and overlap as an
+    /// earlier link, keeping the first one. Links are compared by [`Link::canonical`], so
+    /// a link written from the other strand (`B- -> A-` for `A+ -> B+`) is a duplicate
+    /// too. Returns how many links were removed, and adds an info message for each of
+    /// them to [`GfaParser::messages`].
+    ///
+    /// With `merge_tags`, the read, fragment and k-mer counts (`RC`, `FC` and `KC`) of
+    /// the removed links are added to the kept link, and any other tag the kept link
+    /// doesn't have is copied over.
+    pub fn dedup_links(&mut self, merge_tags: bool) -> usize {
+        let mut kept: HashMap<_, usize> = HashMap::new();
+        let mut removed: HashSet<usize> = HashSet::new();
+        let mut merges: Vec<(usize, TagMap)> = vec![];
+        let mut messages: Vec<ParseMessage> = vec![];
+
+        for (idx, record) in self.records.iter().enumerate() {
+            let GfaRecord::Link(l) = record else {
+                continue;
+            };
+
+            match kept.entry(l.canonical_key(self)) {
+                Entry::Occupied(first) => {
+                    removed.insert(l.line_no);
+                    messages.push(ParseMessage::new(
+                        l.line_no,
+                        ParseMessageCode::DuplicateLinkRemoved,
+                        format!("duplicate of line {}", self.records[*first.get()].line_no()),
+                    ));
+
+                    if merge_tags {
+                        merges.push((*first.get(), l.tags.clone()));
+                    }
+                }
+                Entry::Vacant(first) => {
+                    first.insert(idx);
+                }
+            }
+        }
+
+        for (idx, tags) in merges {
+            if let GfaRecord::Link(l) = &mut self.records[idx] {
+                merge_link_tags(&mut l.tags, tags);
+            }
+        }
+
+        if removed.is_empty() {
+            return 0;
+        }
+
+        self.messages.extend(messages);
+        self.records.retain(|r| !removed.contains(&r.line_no()));
+
+        for segment in self.segments_mut() {
+            segment.outgoing_links.retain(|n| !removed.contains(n));
+            segment.incoming_links.retain(|n| !removed.contains(n));
+        }
+
+        self.reindex();
+
+        removed.len()
+    }
+
+    /// Removes every self-loop, see [`GfaParser::self_loops`]. Returns how many were removed.
+    pub fn remove_self_loops(&mut self) -> usize {
+        let removed: HashSet<usize> = self.self_loops().into_iter().collect();
+
+        if !removed.is_empty() {
+            self.remove_records(&removed);
+        }
+
+        removed.len()
+    }
+
+    /// Removes every ghost segment and every ghost link, leaving only the records that
+    /// were read from the file. See [`MissingSegmentOptions::CreateGhost`] and
+    /// [`MissingBridgeOptions::CreateGhostLink`].
+    ///
+    /// Anything else that references a ghost segment is removed with it, and paths, walks
+    /// and groups drop their steps through it, as with [`MissingSegmentOptions::SoftSkip`].
+    /// Returns how many ghost segments and ghost links were removed.
+    ///
+    /// [`MissingBridgeOptions::CreateGhostLink`]: crate::gfa::MissingBridgeOptions::CreateGhostLink
+    pub fn purge_ghosts(&mut self) -> usize {
+        let ghosts: Vec<(usize, String)> = self
+            .ghost_segments()
+            .map(|s| (s.line_no, s.name.clone()))
+            .collect();
+
+        let ghost_links: HashSet<usize> = self
+            .links()
+            .filter(|l| l.is_ghost())
+            .map(|l| l.line_no)
+            .collect();
+
+        let purged = ghosts.len() + ghost_links.len();
+        if purged == 0 {
+            return 0;
+        }
+
+        let mut removed = ghost_links;
+
+        for (line_no, name) in &ghosts {
+            if let Some(segment) = self.find_segment(*line_no) {
+                removed.insert(*line_no);
+                removed.extend(segment.get_outgoing_bridges());
+                removed.extend(segment.get_incoming_bridges());
+                removed.extend(segment.fragments.iter());
+            }
+
+            self.remove_from_trails(
+                *line_no,
+                name,
+                &MissingSegmentOptions::SoftSkip,
+                &mut removed,
+            );
+        }
+
+        self.remove_records(&removed);
+
+        purged
+    }
+
+    // adds the trails to remove entirely to `removed`
+    fn remove_from_trails(
+        &mut self,
+        line_no: usize,
+        name: &str,
+        handle_trails: &MissingSegmentOptions,
+        removed: &mut HashSet<usize>,
+    ) {
+        for record in self.records.iter_mut() {
+            let trail_no = record.line_no();
+
+            let remove_trail = match record {
+                GfaRecord::Path(p) => {
+                    remove_steps(&mut p.steps, Some(&mut p.overlaps), line_no, handle_trails)
+                }
+                GfaRecord::Walk(w) => remove_steps(&mut w.walk, None, line_no, handle_trails),
+                GfaRecord::OrderedGroup(o) => remove_members(&mut o.members, name, handle_trails),
+                GfaRecord::UnorderedGroup(u) => remove_members(&mut u.members, name, handle_trails),
+                _ => false,
+            };
+
+            if remove_trail {
+                removed.insert(trail_no);
+            }
+        }
+    }
+
+    fn remove_records(&mut self, removed: &HashSet<usize>) {
+        self.records.retain(|r| !removed.contains(&r.line_no()));
+
+        // the far end of every removed bridge still points at it
+        for other in self.segments_mut() {
+            for bridges in [
+                &mut other.outgoing_links,
+                &mut other.incoming_links,
+                &mut other.containments,
+                &mut other.contained_by,
+                &mut other.outgoing_jumps,
+                &mut other.incoming_jumps,
+                &mut other.outgoing_edges,
+                &mut other.incoming_edges,
+                &mut other.outgoing_gaps,
+                &mut other.incoming_gaps,
+                &mut other.fragments,
+            ] {
+                bridges.retain(|n| !removed.contains(n));
+            }
+        }
+
+        // names declared by removed records can be reused
+        self.reindex();
+    }
+}
+
+// cuts a CIGAR at the middle of the reference bases it covers, splitting the operation
+// that straddles the middle. anything that isn't a CIGAR is unknown on both sides
+fn split_overlap(overlap: &str) -> (String, String) {
+    let (Ok(operations), Some(length)) = (parse_cigar(overlap), cigar_reference_length(overlap))
+    else {
+        return ("*".to_string(), "*".to_string());
+    };
+
+    let mut remaining = length / 2;
+    let (mut first, mut second) = (String::new(), String::new());
+
+    for (count, op) in operations {
+        let taken = if "MDN=X".contains(op) {
+            count.min(remaining)
+        } else if remaining > 0 {
+            count
+        } else {
+            0
+        };
+
+        if taken > 0 {
+            first.push_str(&format!("{taken}{op}"));
+        }
+        if count > taken {
+            second.push_str(&format!("{}{op}", count - taken));
+        }
+
+        if "MDN=X".contains(op) {
+            remaining -= taken;
+        }
+    }
+
+    let or_empty = |cigar: String| {
+        if cigar.is_empty() {
+            "0M".to_string()
+        } else {
+            cigar
+        }
+    };
+    (or_empty(first), or_empty(second))
+}
+
+fn merge_link_tags(kept: &mut TagMap, duplicate: TagMap) {
+    for (tag, value) in duplicate.tags {
+        match (kept.tags.get_mut(&tag), value) {
+            (Some(OptionalFieldValue::Int(count)), OptionalFieldValue::Int(more))
+                if COUNT_TAGS.contains(&tag.as_str()) =>
+            {
+                *count = count.saturating_add(more);
+            }
+            (None, value) => {
+                kept.tags.insert(tag, value);
+            }
+            _ => {}
+        }
+    }
+}
+
+// returns true if the whole group should be removed
+fn remove_members(
+    members: &mut Vec<String>,
+    name: &str,
+    handle_trails: &MissingSegmentOptions,
+) -> bool {
+    let is_member = |m: &String| m.trim_end_matches(['+', '-']) == name;
+
+    if !members.iter().any(is_member) {
+        return false;
+    }
+
+    match handle_trails {
+        MissingSegmentOptions::HardSkip => true,
+        MissingSegmentOptions::SoftSkip => {
+            members.retain(|m| !is_member(m));
+            false
+        }
+        MissingSegmentOptions::CreateGhost | MissingSegmentOptions::Ignore => false,
+    }
+}
+
+// returns true if the whole path or walk should be removed
+fn remove_steps(
+    steps: &mut Vec<Step>,
+    overlaps: Option<&mut Vec<String>>,
+    line_no: usize,
+    handle_trails: &MissingSegmentOptions,
+) -> bool {
+    if !steps.iter().any(|s| s.segment_id as usize == line_no) {
+        return false;
+    }
+
+    match handle_trails {
+        MissingSegmentOptions::HardSkip => true,
+        MissingSegmentOptions::SoftSkip => {
+            match overlaps {
+                Some(overlaps) => drop_path_steps(steps, overlaps, line_no),
+                None => steps.retain(|s| s.segment_id as usize != line_no),
+            }
+            false
+        }
+        MissingSegmentOptions::CreateGhost | MissingSegmentOptions::Ignore => false,
+    }
+}
+
+// overlaps sit between steps, so the overlap joining the removed step is dropped
+// and the one bridging the new gap becomes unknown
+fn drop_path_steps(steps: &mut Vec<Step>, overlaps: &mut Vec<String>, line_no: usize) {
+    while let Some(idx) = steps.iter().position(|s| s.segment_id as usize == line_no) {
+        if !overlaps.is_empty() && overlaps.len() + 1 == steps.len() {
+            overlaps.remove(idx.min(overlaps.len() - 1));
+
+            if idx > 0 && idx < steps.len() - 1 {
+                overlaps[idx - 1] = "*".to_string();
+            }
+        }
+
+        steps.remove(idx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gfa::{GfaParser, MissingSegmentOptions, ParseOptions};
+
+    fn build(lines: &[&str]) -> GfaParser {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in lines {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        gfa
+    }
+
+    fn graph() -> GfaParser {
+        build(&[
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "S\tc\tACGT",
+            "L\ta\t+\tb\t+\t1M",
+            "L\tb\t+\tc\t+\t1M",
+            "L\ta\t+\tc\t+\t1M",
+            "P\tp1\ta+,b+,c+\t1M,1M",
+            "P\tp2\ta+,c+\t1M",
+        ])
+    }
+
+    #[test]
+    fn rename_segment_updates_references() {
+        use crate::errors::ParseMessageCode;
+
+        let mut gfa = graph();
+        gfa.add_line("C\tc\t+\ta\t-\t0\t4M", &ParseOptions::default())
+            .unwrap();
+        gfa.add_line("U\tu1\ta b", &ParseOptions::default())
+            .unwrap();
+
+        assert_eq!(gfa.rename_segment("a", "x"), Ok(()));
+        assert_eq!(
+            gfa.rename_segment("a", "y"),
+            Err(ParseMessageCode::SegmentNotFound)
+        );
+        assert_eq!(
+            gfa.rename_segment("x", "b"),
+            Err(ParseMessageCode::NamespaceCollision)
+        );
+        assert_eq!(
+            gfa.rename_segment("x", "p1"),
+            Err(ParseMessageCode::NamespaceCollision)
+        );
+        assert_eq!(
+            gfa.rename_segment("x", "*"),
+            Err(ParseMessageCode::InvalidID)
+        );
+
+        assert!(!gfa.contains_record("a"));
+        let x = gfa.find_segment_with_name("x").unwrap();
+        assert_eq!(x.line_no, 1);
+        assert_eq!(x.outgoing_links.len(), 2);
+
+        let from: Vec<_> = gfa.links().map(|l| gfa.resolve(l.from_segment)).collect();
+        assert_eq!(from, ["x", "b", "x"]);
+        let contained: Vec<_> = gfa
+            .containments()
+            .map(|c| gfa.resolve(c.contained))
+            .collect();
+        assert_eq!(contained, ["x"]);
+        assert_eq!(
+            gfa.find_unordered_group_with_name("u1").unwrap().members,
+            ["x", "b"]
+        );
+
+        let p1 = gfa.find_path_with_name("p1").unwrap();
+        assert_eq!(p1.steps[0].segment_id, 1);
+    }
+
+    #[test]
+    fn split_link_inserts_segment() {
+        use crate::{errors::ParseMessageCode, line::segment::Segment};
+
+        let mut gfa = build(&["S\ta\tACGTACGT", "S\tb\tACGTACGT", "L\ta\t+\tb\t-\t5M"]);
+        let c = || Segment::builder("c").sequence("GGGGG").build();
+
+        assert_eq!(gfa.split_link(2, c()), Err(ParseMessageCode::LinkNotFound));
+        let a = Segment::builder("a").build();
+        assert_eq!(
+            gfa.split_link(3, a),
+            Err(ParseMessageCode::NamespaceCollision)
+        );
+        assert_eq!(gfa.split_link(3, c()), Ok(()));
+        assert!(gfa.find_link(3).is_none());
+
+        let links: Vec<_> = gfa
+            .links()
+            .map(|l| {
+                let (from, to) = (gfa.resolve(l.from_segment), gfa.resolve(l.to_segment));
+                (
+                    from,
+                    l.from_orientation,
+                    to,
+                    l.to_orientation,
+                    l.overlap.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            links,
+            [("a", true, "c", true, "2M"), ("c", true, "b", false, "3M")]
+        );
+
+        let adjacency: Vec<_> = gfa
+            .segments()
+            .map(|s| {
+                (
+                    s.name.as_str(),
+                    s.incoming_links.len(),
+                    s.outgoing_links.len(),
+                )
+            })
+            .collect();
+        assert_eq!(adjacency, [("a", 0, 1), ("b", 1, 0), ("c", 1, 1)]);
+    }
+
+    #[test]
+    fn split_link_to_missing_segment() {
+        use crate::{errors::ParseMessageCode, line::segment::Segment};
+
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions {
+            handle_missing_segment: MissingSegmentOptions::Ignore,
+            ..ParseOptions::default()
+        };
+        gfa.add_line("S\ta\tACGT", &options).unwrap();
+        let link = gfa.add_line("L\ta\t+\tb\t+\t*", &options).unwrap();
+
+        let c = Segment::builder("c").sequence("GG").build();
+        assert_eq!(
+            gfa.split_link(link, c),
+            Err(ParseMessageCode::SegmentNotFound)
+        );
+        assert!(gfa.find_link(link).is_some());
+        assert!(gfa.find_segment_with_name("c").is_none());
+    }
+
+    #[test]
+    fn dedup_links_keeps_first() {
+        let mut gfa = build(&[
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t+\t1M\tRC:i:2",
+            "L\ta\t+\tb\t+\t1M\tRC:i:3\tKC:i:7",
+            "L\ta\t+\tb\t+\t2M",
+            "L\ta\t+\tb\t+\t1M",
+        ]);
+
+        assert_eq!(gfa.dedup_links(true), 2);
+        assert_eq!(gfa.dedup_links(true), 0);
+
+        let links: Vec<_> = gfa.links().collect();
+        assert_eq!(links.len(), 2);
+        assert_eq!(links[0].line_no, 3);
+        assert_eq!(links[0].tags.get::<i32>("RC"), Some(5));
+        assert_eq!(links[0].tags.get::<i32>("KC"), Some(7));
+
+        let a = gfa.find_segment_with_name("a").unwrap();
+        assert_eq!(a.outgoing_links, vec![3, 5]);
+
+        let removed: Vec<_> = gfa
+            .messages
+            .iter()
+            .map(|m| (m.line, m.offender.as_str()))
+            .collect();
+        assert_eq!(
+            removed,
+            [(4, "duplicate of line 3"), (6, "duplicate of line 3")]
+        );
+    }
+
+    #[test]
+    fn dedup_links_across_strands() {
+        let mut gfa = build(&[
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t-\t2M1I",
+            "L\tb\t+\ta\t-\t1D2M",
+            "L\tb\t+\ta\t-\t2M1I",
+        ]);
+
+        assert_eq!(gfa.dedup_links(false), 1);
+
+        let kept: Vec<_> = gfa.links().map(|l| l.line_no).collect();
+        assert_eq!(kept, [3, 5]);
+        assert_eq!(gfa.messages[0].offender, "duplicate of line 3");
+    }
+
+    #[test]
+    fn remove_segment_and_bridges() {
+        let mut gfa = graph();
+
+        assert!(gfa.remove_segment("b", MissingSegmentOptions::SoftSkip));
+        assert!(!gfa.remove_segment("b", MissingSegmentOptions::SoftSkip));
+
+        assert!(gfa.find_segment_with_name("b").is_none());
+        assert_eq!(gfa.links().count(), 1);
+        assert!(!gfa.is_name_in_namespace("b"));
+
+        let a = gfa.find_segment_with_name("a").unwrap();
+        assert_eq!(a.outgoing_links.len(), 1);
+
+        let p1 = gfa.find_path_with_name("p1").unwrap();
+        assert_eq!(p1.steps.len(), 2);
+        assert_eq!(p1.overlaps, ["*"]);
+    }
+
+    #[test]
+    fn remove_segment_drops_trails() {
+        let mut gfa = graph();
+
+        assert!(gfa.remove_segment("b", MissingSegmentOptions::HardSkip));
+        assert!(gfa.find_path_with_name("p1").is_none());
+        assert!(gfa.find_path_with_name("p2").is_some());
+    }
+
+    #[test]
+    fn remove_segment_leaves_ghost() {
+        let mut gfa = graph();
+
+        assert!(gfa.remove_segment("b", MissingSegmentOptions::CreateGhost));
+
+        let ghost = gfa.find_segment_with_name("b").unwrap();
+        assert!(ghost.is_ghost());
+        let ghost_no = ghost.line_no as u32;
+
+        let p1 = gfa.find_path_with_name("p1").unwrap();
+        assert_eq!(p1.steps[1].segment_id, ghost_no);
+    }
+
+    #[test]
+    fn purge_ghosts_keeps_parsed_records() {
+        let mut gfa = graph();
+        gfa.add_line("P\tp3\ta+,x+,c+\t*", &ParseOptions::default())
+            .expect("test line should parse");
+
+        assert_eq!(gfa.ghost_segments().count(), 1);
+        let ghost_links = gfa.links().filter(|l| l.is_ghost()).count();
+        assert_eq!(ghost_links, 2);
+        assert_eq!(gfa.ghost_counts(), (1, 2));
+        assert_eq!(gfa.records.iter().filter(|r| r.is_ghost()).count(), 3);
+
+        assert_eq!(gfa.purge_ghosts(), 1 + ghost_links);
+        assert_eq!(gfa.purge_ghosts(), 0);
+
+        assert_eq!(gfa.ghost_counts(), (0, 0));
+        assert!(gfa.find_segment_with_name("x").is_none());
+        assert_eq!(gfa.links().count(), 3);
+
+        let p3 = gfa.find_path_with_name("p3").unwrap();
+        assert_eq!(p3.steps.len(), 2);
+
+        let a = gfa.find_segment_with_name("a").unwrap();
+        assert_eq!(a.outgoing_links.len(), 2);
+    }
+
+    #[test]
+    fn remove_self_loops_keeps_other_bridges() {
+        let mut gfa = graph();
+        let loop_no = gfa
+            .add_line("L\tb\t+\tb\t-\t0M", &ParseOptions::default())
+            .unwrap();
+        gfa.add_line("J\tc\t+\tc\t+\t*", &ParseOptions::default())
+            .unwrap();
+
+        assert_eq!(gfa.self_loops().len(), 2);
+        assert_eq!(gfa.self_loops()[0], loop_no);
+
+        assert_eq!(gfa.remove_self_loops(), 2);
+        assert!(gfa.self_loops().is_empty());
+        assert_eq!(gfa.links().count(), 3);
+
+        let b = gfa.find_segment_with_name("b").unwrap();
+        assert_eq!(b.outgoing_links.len(), 1);
+        assert_eq!(b.incoming_links.len(), 1);
+    }
+}
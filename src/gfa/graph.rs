@@ -0,0 +1,856 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque, hash_map::Entry},
+};
+
+use crate::{
+    errors::{ParseMessage, ParseMessageCode},
+    gfa::GfaParser,
+    line::{BridgeRef, BridgeType},
+};
+
+/// Graph traversal over the bridge records of a [`GfaParser`].
+///
+/// Segments are treated as oriented nodes: a link `A+ -> B-` can be followed
+/// from `A` in the forward orientation to `B` in the reverse orientation, and
+/// implicitly from `B+` back to `A-`.
+impl GfaParser {
+    /// Returns the oriented successors of the segment at `line_no` when it is
+    /// traversed in the given orientation (true for +, false for -).
+    ///
+    /// Links, jumps, and edges are all treated as directed bridges.
+    pub fn neighbours(&self, line_no: usize, orientation: bool) -> Vec<(usize, bool)> {
+        let Some(segment) = self.find_segment(line_no) else {
+            return vec![];
+        };
+
+        let mut neighbours = vec![];
+
+        let outgoing = segment
+            .outgoing_links
+            .iter()
+            .chain(segment.outgoing_jumps.iter())
+            .chain(segment.outgoing_edges.iter());
+
+        for bridge_no in outgoing {
+            let Some((_, from_orientation, to, to_orientation)) = self.bridge_endpoints(*bridge_no)
+            else {
+                continue;
+            };
+
+            if from_orientation != orientation {
+                continue;
+            }
+
+            if let Some(to_no) = self.segment_line_no(to) {
+                neighbours.push((to_no, to_orientation));
+            }
+        }
+
+        // a bridge into this segment can be walked backwards on the opposite strand
+        let incoming = segment
+            .incoming_links
+            .iter()
+            .chain(segment.incoming_jumps.iter())
+            .chain(segment.incoming_edges.iter());
+
+        for bridge_no in incoming {
+            let Some((from, from_orientation, _, to_orientation)) =
+                self.bridge_endpoints(*bridge_no)
+            else {
+                continue;
+            };
+
+            if to_orientation == orientation {
+                continue;
+            }
+
+            if let Some(from_no) = self.segment_line_no(from) {
+                neighbours.push((from_no, !from_orientation));
+            }
+        }
+
+        neighbours
+    }
+
+    /// Returns the line numbers of every segment reachable from `start` in
+    /// breadth-first order, starting with `start` itself in the forward orientation.
+    ///
+    /// Each segment is only returned once, even if it is reachable in both orientations.
+    /// Returns an empty `Vec` if `start` does not exist.
+    pub fn bfs(&self, start: &str) -> Vec<usize> {
+        let Some(start_no) = self.segment_line_no(start) else {
+            return vec![];
+        };
+
+        let mut order = vec![];
+        let mut seen_segments = HashSet::new();
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::new();
+
+        visited.insert((start_no, true));
+        queue.push_back((start_no, true));
+
+        while let Some((line_no, orientation)) = queue.pop_front() {
+            if seen_segments.insert(line_no) {
+                order.push(line_no);
+            }
+
+            for next in self.neighbours(line_no, orientation) {
+                if visited.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        order
+    }
+
+    // hop distance from the closest of `starts` to every oriented node within `max_hops`,
+    // following bridges forwards or, if `forward` is false, backwards
+    pub(crate) fn hop_distances(
+        &self,
+        starts: &[(usize, bool)],
+        max_hops: usize,
+        forward: bool,
+    ) -> HashMap<(usize, bool), usize> {
+        let mut distances = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        for start in starts {
+            if distances.insert(*start, 0).is_none() {
+                queue.push_back(*start);
+            }
+        }
+
+        while let Some(node) = queue.pop_front() {
+            let distance = distances[&node];
+
+            if distance == max_hops {
+                continue;
+            }
+
+            let next_nodes = if forward {
+                self.neighbours(node.0, node.1)
+            } else {
+                self.predecessors(node.0, node.1)
+            };
+
+            for next in next_nodes {
+                if let Entry::Vacant(e) = distances.entry(next) {
+                    e.insert(distance + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        distances
+    }
+
+    /// Checks if the segment `to` is downstream of the segment `from`.
+    /// A segment is always reachable from itself.
+    pub fn is_reachable(&self, from: &str, to: &str) -> bool {
+        match self.segment_line_no(to) {
+            Some(to_no) => self.bfs(from).contains(&to_no),
+            None => false,
+        }
+    }
+
+    /// Returns the line numbers of the segments on a path from `from` to `to` with the
+    /// fewest bridges, starting at `from` in the forward orientation and ending at `to`
+    /// in either orientation. Both ends are included, so a segment's path to itself is
+    /// just that segment.
+    ///
+    /// Returns `None` if either segment does not exist or `to` is not reachable.
+    pub fn shortest_path(&self, from: &str, to: &str) -> Option<Vec<usize>> {
+        let from_no = self.segment_line_no(from)?;
+        let to_no = self.segment_line_no(to)?;
+
+        let start = (from_no, true);
+        let mut predecessors: HashMap<(usize, bool), (usize, bool)> = HashMap::new();
+        let mut visited = HashSet::from([start]);
+        let mut queue = VecDeque::from([start]);
+
+        while let Some(node) = queue.pop_front() {
+            if node.0 == to_no {
+                let mut path = vec![node.0];
+                let mut current = node;
+
+                while let Some(previous) = predecessors.get(&current) {
+                    path.push(previous.0);
+                    current = *previous;
+                }
+
+                path.reverse();
+                return Some(path);
+            }
+
+            for next in self.neighbours(node.0, node.1) {
+                if visited.insert(next) {
+                    predecessors.insert(next, node);
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the line numbers of the segments on the path from `from` to `to` with the
+    /// fewest bases, and that number of bases, following the same bridges and
+    /// orientations as [`GfaParser::shortest_path`].
+    ///
+    /// Every segment on the path counts its [`crate::line::segment::Segment::get_length`], both
+    /// ends included, and overlaps are not subtracted.
+    ///
+    /// Returns `None` if either segment does not exist or `to` is not reachable.
+    pub fn shortest_path_by_length(&self, from: &str, to: &str) -> Option<(Vec<usize>, u64)> {
+        let from_no = self.segment_line_no(from)?;
+        let to_no = self.segment_line_no(to)?;
+
+        let length = |line_no: usize| {
+            self.find_segment(line_no)
+                .map_or(0, |segment| segment.get_length().max(0) as u64)
+        };
+
+        let start = (from_no, true);
+        let mut costs = HashMap::from([(start, length(from_no))]);
+        let mut predecessors: HashMap<(usize, bool), (usize, bool)> = HashMap::new();
+        let mut heap = BinaryHeap::from([Reverse((costs[&start], start))]);
+
+        while let Some(Reverse((cost, node))) = heap.pop() {
+            // stale entry, the node was reached more cheaply since it was pushed
+            if costs.get(&node).is_some_and(|best| cost > *best) {
+                continue;
+            }
+
+            if node.0 == to_no {
+                let mut path = vec![node.0];
+                let mut current = node;
+
+                while let Some(previous) = predecessors.get(&current) {
+                    path.push(previous.0);
+                    current = *previous;
+                }
+
+                path.reverse();
+                return Some((path, cost));
+            }
+
+            for next in self.neighbours(node.0, node.1) {
+                let next_cost = cost + length(next.0);
+
+                if costs.get(&next).is_none_or(|best| next_cost < *best) {
+                    costs.insert(next, next_cost);
+                    predecessors.insert(next, node);
+                    heap.push(Reverse((next_cost, next)));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Bubble detection.
+impl GfaParser {
+    /// Returns every simple superbubble in the graph as `(source, sink, interior)`
+    /// segment line numbers.
+    ///
+    /// A bubble is found from a source segment with two or more outgoing bridges
+    /// that all reconverge at a single sink, with no bridges entering or leaving
+    /// the interior from outside of the bubble. This uses the linear-time check
+    /// from Onodera et al. (2013) for each candidate source, so the whole search
+    /// is O(n * m) in the worst case.
+    ///
+    /// Bubbles are searched for in both orientations, but a bubble and its reverse
+    /// complement are only reported once.
+    pub fn find_simple_bubbles(&self) -> Vec<(usize, usize, Vec<usize>)> {
+        let mut bubbles = vec![];
+        let mut seen = HashSet::new();
+
+        for segment in self.segments() {
+            for orientation in [true, false] {
+                let source = (segment.line_no, orientation);
+
+                if self.neighbours(source.0, source.1).len() < 2 {
+                    continue;
+                }
+
+                let Some((sink, interior)) = self.bubble_from(source) else {
+                    continue;
+                };
+
+                let key = (source.0.min(sink.0), source.0.max(sink.0));
+                if seen.insert(key) {
+                    bubbles.push((source.0, sink.0, interior));
+                }
+            }
+        }
+
+        bubbles
+    }
+}
+
+/// Degree statistics.
+impl GfaParser {
+    /// Maps each degree value to the number of segments with that degree.
+    ///
+    /// See [`Segment::degree`](crate::line::segment::Segment::degree) for what is counted.
+    pub fn degree_distribution(
+        &self,
+        include_gaps_and_containments: bool,
+    ) -> HashMap<usize, usize> {
+        let mut distribution = HashMap::new();
+
+        for segment in self.segments() {
+            *distribution
+                .entry(segment.degree(include_gaps_and_containments))
+                .or_insert(0) += 1;
+        }
+
+        distribution
+    }
+}
+
+/// Topological ordering.
+impl GfaParser {
+    /// Returns segment line numbers in topological order using Kahn's algorithm
+    /// over the `outgoing_links`/`incoming_links` of each segment.
+    ///
+    /// Unlike [`GfaParser::find_cycles`], links are treated as plain directed
+    /// edges from `from_segment` to `to_segment` and orientation is ignored.
+    ///
+    /// If the graph is not a DAG, returns a `GraphHasCycle` message as an [`Err`], on the
+    /// line of the first segment that lies on (or between) cycles, with the names of all
+    /// of them as the offender.
+    pub fn topological_order(&self) -> Result<Vec<usize>, ParseMessage> {
+        let mut in_degree: HashMap<usize, usize> = HashMap::new();
+        let mut successors: HashMap<usize, Vec<usize>> = HashMap::new();
+
+        for segment in self.segments() {
+            in_degree.entry(segment.line_no).or_insert(0);
+
+            for link_no in &segment.outgoing_links {
+                let Some(link) = self.find_link(*link_no) else {
+                    continue;
+                };
+
+                if let Some(to_no) = self.segment_line_no(self.resolve(link.to_segment)) {
+                    successors.entry(segment.line_no).or_default().push(to_no);
+                    *in_degree.entry(to_no).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut queue: VecDeque<usize> = self
+            .segments()
+            .map(|s| s.line_no)
+            .filter(|n| in_degree[n] == 0)
+            .collect();
+
+        let mut order = Vec::with_capacity(in_degree.len());
+
+        while let Some(line_no) = queue.pop_front() {
+            order.push(line_no);
+
+            for next in successors.get(&line_no).into_iter().flatten() {
+                let degree = in_degree
+                    .get_mut(next)
+                    .expect("every successor has an in-degree");
+                *degree -= 1;
+
+                if *degree == 0 {
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        if order.len() == in_degree.len() {
+            return Ok(order);
+        }
+
+        // everything left has an unresolved incoming link, but segments that are
+        // only downstream of a cycle can be peeled off from the other end
+        let mut remaining: HashSet<usize> = in_degree
+            .iter()
+            .filter(|(_, degree)| **degree > 0)
+            .map(|(line_no, _)| *line_no)
+            .collect();
+
+        loop {
+            let sinks: Vec<usize> = remaining
+                .iter()
+                .copied()
+                .filter(|n| {
+                    successors
+                        .get(n)
+                        .is_none_or(|next| next.iter().all(|m| !remaining.contains(m)))
+                })
+                .collect();
+
+            if sinks.is_empty() {
+                break;
+            }
+
+            for sink in sinks {
+                remaining.remove(&sink);
+            }
+        }
+
+        let mut members: Vec<usize> = remaining.into_iter().collect();
+        members.sort_unstable();
+
+        let names: Vec<&str> = members
+            .iter()
+            .filter_map(|n| self.find_segment(*n))
+            .map(|s| s.name.as_str())
+            .collect();
+
+        Err(ParseMessage::new(
+            members.first().copied().unwrap_or(0),
+            ParseMessageCode::GraphHasCycle,
+            names.join(", "),
+        ))
+    }
+}
+
+// keeps pathological graphs from hanging the cycle search
+const MAX_REPORTED_CYCLES: usize = 1000;
+
+/// Cycle detection.
+impl GfaParser {
+    /// Returns simple cycles in the graph as sequences of segment line numbers,
+    /// found by a depth-first search over the oriented bridges.
+    ///
+    /// The search is iterative, so it is safe to run on very large graphs.
+    /// One cycle is reported per back edge found by the search, so this is not an
+    /// exhaustive enumeration of every simple cycle. A cycle and its reverse complement
+    /// visit the same segments and are only reported once.
+    /// At most 1000 cycles are returned.
+    pub fn find_cycles(&self) -> Vec<Vec<usize>> {
+        self.collect_cycles(MAX_REPORTED_CYCLES)
+    }
+
+    /// Checks if the graph is a directed acyclic graph.
+    pub fn is_acyclic(&self) -> bool {
+        self.collect_cycles(1).is_empty()
+    }
+}
+
+/// Private helpers for graph traversal.
+impl GfaParser {
+    fn collect_cycles(&self, limit: usize) -> Vec<Vec<usize>> {
+        let mut search = CycleSearch {
+            limit,
+            on_stack: HashMap::new(),
+            stack: vec![],
+            cycles: vec![],
+            seen: HashSet::new(),
+        };
+
+        for segment in self.segments() {
+            for orientation in [true, false] {
+                if search.is_full() {
+                    return search.cycles;
+                }
+
+                let node = (segment.line_no, orientation);
+                if !search.on_stack.contains_key(&node) {
+                    self.visit_for_cycles(node, &mut search);
+                }
+            }
+        }
+
+        search.cycles
+    }
+
+    /// Returns the oriented predecessors of a segment, i.e. the reverse of [`GfaParser::neighbours`].
+    fn predecessors(&self, line_no: usize, orientation: bool) -> Vec<(usize, bool)> {
+        self.neighbours(line_no, !orientation)
+            .into_iter()
+            .map(|(n, o)| (n, !o))
+            .collect()
+    }
+
+    // finds the sink and interior of the superbubble starting at `source`, if any
+    fn bubble_from(&self, source: (usize, bool)) -> Option<((usize, bool), Vec<usize>)> {
+        let mut visited = HashSet::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![source];
+
+        seen.insert(source);
+
+        while let Some(node) = stack.pop() {
+            visited.insert(node);
+            seen.remove(&node);
+
+            let children = self.neighbours(node.0, node.1);
+            if children.is_empty() {
+                return None; // tip
+            }
+
+            for child in children {
+                if child == source {
+                    return None; // cycle back to the source
+                }
+
+                seen.insert(child);
+
+                let parents = self.predecessors(child.0, child.1);
+                if parents.iter().all(|p| visited.contains(p)) {
+                    stack.push(child);
+                }
+            }
+
+            if stack.len() == 1 && seen.len() == 1 {
+                let sink = stack[0];
+
+                if self.neighbours(sink.0, sink.1).contains(&source) {
+                    return None;
+                }
+
+                let mut interior: Vec<usize> = visited
+                    .iter()
+                    .filter(|n| **n != source)
+                    .map(|(line_no, _)| *line_no)
+                    .collect();
+
+                interior.sort_unstable();
+                interior.dedup();
+
+                return Some((sink, interior));
+            }
+        }
+
+        None
+    }
+
+    // iterative so that long chains of segments can't overflow the call stack
+    fn visit_for_cycles(&self, root: (usize, bool), search: &mut CycleSearch) {
+        search.on_stack.insert(root, true);
+        search.stack.push(root);
+
+        let mut frames = vec![(self.neighbours(root.0, root.1), 0)];
+
+        while let Some((next_nodes, next_idx)) = frames.last_mut() {
+            if search.is_full() {
+                return;
+            }
+
+            let Some(next) = next_nodes.get(*next_idx).copied() else {
+                // every neighbour has been explored, so backtrack
+                frames.pop();
+                if let Some(node) = search.stack.pop() {
+                    search.on_stack.insert(node, false);
+                }
+                continue;
+            };
+
+            *next_idx += 1;
+
+            match search.on_stack.get(&next) {
+                Some(true) => search.record_cycle(next),
+                Some(false) => {}
+                None => {
+                    search.on_stack.insert(next, true);
+                    search.stack.push(next);
+                    frames.push((self.neighbours(next.0, next.1), 0));
+                }
+            }
+        }
+    }
+
+    /// Returns `(from, from_orientation, to, to_orientation)` for a link, jump, or edge.
+    fn bridge_endpoints(&self, line_no: usize) -> Option<(&str, bool, &str, bool)> {
+        let bridge = BridgeRef::from_record(self.find_record(line_no)?, self)?;
+
+        match bridge.bridge_type {
+            BridgeType::Link | BridgeType::Jump | BridgeType::Edge => Some((
+                bridge.from_segment,
+                bridge.from_orientation,
+                bridge.to_segment,
+                bridge.to_orientation,
+            )),
+            BridgeType::Containment | BridgeType::Gap => None,
+        }
+    }
+}
+
+struct CycleSearch {
+    limit: usize,
+    // true while the node is on the DFS stack, false once it has been fully explored
+    on_stack: HashMap<(usize, bool), bool>,
+    stack: Vec<(usize, bool)>,
+    cycles: Vec<Vec<usize>>,
+    seen: HashSet<Vec<usize>>,
+}
+
+impl CycleSearch {
+    fn is_full(&self) -> bool {
+        self.cycles.len() >= self.limit
+    }
+
+    // the back edge points at `target`, so the cycle is everything above it on the stack
+    fn record_cycle(&mut self, target: (usize, bool)) {
+        let Some(start) = self.stack.iter().position(|n| *n == target) else {
+            return;
+        };
+
+        let cycle: Vec<usize> = self.stack[start..]
+            .iter()
+            .map(|(line_no, _)| *line_no)
+            .collect();
+
+        let mut key = cycle.clone();
+        key.sort_unstable();
+
+        if self.seen.insert(key) {
+            self.cycles.push(cycle);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::ParseMessageCode;
+    use crate::gfa::{GfaParser, ParseOptions};
+
+    fn parse(path: &str) -> GfaParser {
+        let mut gfa = GfaParser::new();
+        let _ = gfa.parse(path, &ParseOptions::default());
+        gfa
+    }
+
+    fn names(gfa: &GfaParser, line_nos: &[usize]) -> Vec<String> {
+        line_nos
+            .iter()
+            .map(|n| gfa.find_segment(*n).unwrap().name.clone())
+            .collect()
+    }
+
+    fn build(lines: &[&str]) -> GfaParser {
+        let mut gfa = GfaParser::new();
+        for line in lines {
+            gfa.add_line(line, &ParseOptions::default())
+                .expect("test lines should be valid");
+        }
+        gfa
+    }
+
+    #[test]
+    fn bfs_follows_outgoing_bridges() {
+        let gfa = parse("test/walk.gfa");
+
+        assert_eq!(names(&gfa, &gfa.bfs("s2")), ["s2", "s3", "s5", "s4", "s6"]);
+        assert!(gfa.bfs("missing").is_empty());
+    }
+
+    #[test]
+    fn reachability_respects_direction() {
+        let gfa = parse("test/walk.gfa");
+
+        assert!(gfa.is_reachable("s1", "s4"));
+        assert!(gfa.is_reachable("s7", "s2")); // via the s7 -> s1 jump
+        assert!(!gfa.is_reachable("s2", "s1"));
+        assert!(gfa.is_reachable("s4", "s4"));
+    }
+
+    #[test]
+    fn shortest_path_takes_fewest_hops() {
+        let gfa = parse("test/walk.gfa");
+        let path = |from, to| gfa.shortest_path(from, to).map(|p| names(&gfa, &p));
+
+        assert_eq!(path("s2", "s6").unwrap(), ["s2", "s5", "s6"]);
+        assert_eq!(path("s7", "s4").unwrap(), ["s7", "s6", "s4"]);
+        assert_eq!(path("s4", "s4").unwrap(), ["s4"]);
+        assert_eq!(path("s2", "s1"), None);
+        assert_eq!(path("s1", "missing"), None);
+    }
+
+    #[test]
+    fn shortest_path_by_length_takes_fewest_bases() {
+        let gfa = build(&[
+            "S\ta\tACGT",
+            "S\tlong\t*\tLN:i:100",
+            "S\tb\tAC",
+            "S\tc\tA",
+            "S\td\tACGT",
+            "L\ta\t+\tlong\t+\t0M",
+            "L\tlong\t+\td\t+\t0M",
+            "L\ta\t+\tb\t+\t0M",
+            "L\tb\t+\tc\t-\t0M",
+            "L\tc\t-\td\t+\t0M",
+        ]);
+
+        // more segments than through `long`, but fewer bases
+        let (path, length) = gfa.shortest_path_by_length("a", "d").unwrap();
+        assert_eq!(names(&gfa, &path), ["a", "b", "c", "d"]);
+        assert_eq!(length, 11);
+        assert_eq!(
+            names(&gfa, &gfa.shortest_path("a", "d").unwrap()),
+            ["a", "long", "d"]
+        );
+
+        assert_eq!(gfa.shortest_path_by_length("a", "a"), Some((vec![1], 4)));
+        assert_eq!(gfa.shortest_path_by_length("d", "a"), None);
+    }
+
+    #[test]
+    fn finds_cycles() {
+        let gfa = parse("test/walk.gfa");
+
+        // the only cycle goes through the s7 -> s1 jump
+        let cycles = gfa.find_cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(names(&gfa, &cycles[0]), ["s1", "s7"]);
+        assert!(!gfa.is_acyclic());
+    }
+
+    #[test]
+    fn cycles_are_reported_when_requested() {
+        let mut gfa = GfaParser::new();
+        let _ = gfa.parse(
+            "test/gfa_working.gfa",
+            &ParseOptions {
+                report_cycles: true,
+                ..ParseOptions::default()
+            },
+        );
+
+        assert!(
+            gfa.messages
+                .iter()
+                .any(|m| m.code == ParseMessageCode::GraphContainsCycle)
+        );
+    }
+
+    #[test]
+    fn dag_has_no_cycles() {
+        let gfa = build(&[
+            "S\ta\tA",
+            "S\tb\tC",
+            "S\tc\tG",
+            "L\ta\t+\tb\t+\t0M",
+            "L\ta\t+\tc\t-\t0M",
+            "L\tb\t+\tc\t-\t0M",
+        ]);
+
+        assert!(gfa.find_cycles().is_empty());
+        assert!(gfa.is_acyclic());
+    }
+
+    #[test]
+    fn long_chain_does_not_overflow() {
+        let mut lines = vec![];
+        for i in 0..20_000 {
+            lines.push(format!("S\t{i}\tA"));
+        }
+        for i in 1..20_000 {
+            lines.push(format!("L\t{}\t+\t{i}\t+\t0M", i - 1));
+        }
+        lines.push("L\t19999\t+\t0\t+\t0M".to_string());
+
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let gfa = build(&lines);
+
+        assert_eq!(gfa.find_cycles()[0].len(), 20_000);
+    }
+
+    #[test]
+    fn self_link_is_a_cycle() {
+        let gfa = build(&["S\ta\tA", "L\ta\t+\ta\t+\t0M"]);
+
+        assert_eq!(gfa.find_cycles().len(), 1);
+    }
+
+    #[test]
+    fn finds_simple_bubble() {
+        let gfa = build(&[
+            "S\ta\tA",
+            "S\tb\tC",
+            "S\tc\tG",
+            "S\td\tT",
+            "S\te\tT",
+            "L\ta\t+\tb\t+\t0M",
+            "L\ta\t+\tc\t+\t0M",
+            "L\tb\t+\td\t+\t0M",
+            "L\tc\t+\td\t+\t0M",
+            "L\td\t+\te\t+\t0M",
+        ]);
+
+        let bubbles = gfa.find_simple_bubbles();
+        assert_eq!(bubbles.len(), 1);
+
+        let (source, sink, interior) = &bubbles[0];
+        assert_eq!(names(&gfa, &[*source, *sink]), ["a", "d"]);
+        assert_eq!(names(&gfa, interior), ["b", "c"]);
+    }
+
+    #[test]
+    fn bubble_with_external_bridge_is_ignored() {
+        // s6 is entered from s7, which is outside of the s2 -> s4 bubble
+        let gfa = parse("test/walk.gfa");
+
+        assert!(gfa.find_simple_bubbles().is_empty());
+    }
+
+    #[test]
+    fn topological_order_of_dag() {
+        let gfa = build(&[
+            "S\td\tT",
+            "S\tc\tG",
+            "S\tb\tC",
+            "S\ta\tA",
+            "L\ta\t+\tb\t+\t0M",
+            "L\ta\t+\tc\t+\t0M",
+            "L\tb\t+\td\t+\t0M",
+            "L\tc\t+\td\t+\t0M",
+        ]);
+
+        let order = gfa.topological_order().expect("graph is a DAG");
+        assert_eq!(names(&gfa, &order), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn topological_order_reports_cycle_members() {
+        let gfa = build(&[
+            "S\ta\tA",
+            "S\tb\tC",
+            "S\tc\tG",
+            "S\td\tT",
+            "L\ta\t+\tb\t+\t0M",
+            "L\tb\t+\tc\t+\t0M",
+            "L\tc\t+\tb\t+\t0M",
+            "L\tc\t+\td\t+\t0M",
+        ]);
+
+        let cycle = gfa.topological_order().expect_err("b and c form a cycle");
+        assert_eq!(cycle.code, ParseMessageCode::GraphHasCycle);
+        assert_eq!(cycle.line, 2);
+        assert_eq!(cycle.offender, "b, c");
+    }
+
+    #[test]
+    fn degree_distribution_counts_segments() {
+        let gfa = build(&[
+            "S\ta\tA",
+            "S\tb\tC",
+            "S\tc\tG",
+            "S\td\tT",
+            "L\ta\t+\tb\t+\t0M",
+            "L\ta\t+\tc\t+\t0M",
+            "L\ta\t+\td\t+\t0M",
+            "C\ta\t+\tb\t+\t0\t1M",
+        ]);
+
+        let distribution = gfa.degree_distribution(false);
+        assert_eq!(distribution.get(&3), Some(&1));
+        assert_eq!(distribution.get(&1), Some(&3));
+
+        let distribution = gfa.degree_distribution(true);
+        assert_eq!(distribution.get(&4), Some(&1));
+        assert_eq!(distribution.get(&2), Some(&1));
+        assert_eq!(distribution.get(&1), Some(&2));
+    }
+}
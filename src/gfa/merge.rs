@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use crate::{
+    errors::{ParseMessage, ParseMessageCode},
+    gfa::{GFAVersion, GfaParser, ParseOptions},
+    line::{
+        record::GfaRecord,
+        rename_member,
+        segment::Segment,
+        symbol::{Symbol, SymbolTable},
+    },
+    optional_field::{OptionalFieldValue, TagMap},
+};
+
+/// Combining several GFA graphs into one.
+impl GfaParser {
+    /// Appends every record of `other` to this graph, and returns the messages the
+    /// merge itself produced (they are also added to [`GfaParser::messages`]).
+    ///
+    /// Records are moved to line numbers after the last line of this graph, and every
+    /// name is run through [`GfaParser::ensure_name_unique`], so a name that already
+    /// exists here is suffixed instead of overwriting the existing record. Each rename
+    /// is reported as a `NamespaceCollision` message, and every reference in `other`
+    /// to a renamed record is rewritten to the new name.
+    ///
+    /// Without `rename_collisions`, a segment of `other` with the same name as a segment
+    /// here is the same segment instead: it is not added, and everything in `other` that
+    /// references it is attached to the existing one. If both have a sequence and they
+    /// differ, the existing one is kept and a `MergedSegmentMismatch` message is added.
+    /// Other records (paths, groups, named edges, ...) are always renamed.
+    ///
+    /// Headers of `other` are only kept if this graph has no header of its own.
+    /// `options` controls whether raw lines and sequences of the merged records are kept.
+    pub fn merge(
+        &mut self,
+        other: GfaParser,
+        rename_collisions: bool,
+        options: &ParseOptions,
+    ) -> Vec<ParseMessage> {
+        let offset = self.max_lines;
+        let has_header = self.header().is_some();
+        let first_message = self.messages.len();
+
+        // line numbers of the segments of `other` that are merged into one of ours
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+
+        if !rename_collisions {
+            for segment in other.segments() {
+                if let Some(line_no) = self.segment_line_no(&segment.name) {
+                    shared.insert(segment.line_no, line_no);
+                }
+            }
+        }
+
+        // claim every name first so that forward references can be rewritten too
+        let mut renames: HashMap<String, String> = HashMap::new();
+
+        for record in &other.records {
+            let line_no = record.line_no() + offset;
+
+            if shared.contains_key(&record.line_no()) {
+                continue;
+            }
+
+            for name in record.declared_names() {
+                let unique = self.ensure_name_unique(line_no, name.clone());
+
+                if unique != name {
+                    renames.insert(name, unique);
+                }
+            }
+        }
+
+        let rename = |name: &mut String| {
+            if let Some(new_name) = renames.get(name.as_str()) {
+                name.clone_from(new_name);
+            }
+        };
+
+        // symbols of `other` mean nothing here, so they go through the names
+        let other_symbols = &other.symbols;
+        let rename_symbol = |symbol: &mut Symbol, symbols: &mut SymbolTable| {
+            let name = other_symbols.resolve(*symbol);
+            *symbol = symbols.intern(renames.get(name).map_or(name, String::as_str));
+        };
+
+        let rename_id_tag = |tags: &mut TagMap| {
+            let new_name = tags.get::<String>("ID").and_then(|id| renames.get(&id));
+
+            if let Some(new_name) = new_name {
+                tags.tags
+                    .insert("ID".into(), OptionalFieldValue::String(new_name.clone()));
+            }
+        };
+
+        let shift = |line_nos: &mut Vec<usize>| {
+            line_nos.iter_mut().for_each(|n| *n += offset);
+        };
+
+        let step_target = |segment_id: u32| match shared.get(&(segment_id as usize)) {
+            Some(line_no) => *line_no as u32,
+            None => segment_id + offset as u32,
+        };
+
+        for mut record in other.records {
+            match &mut record {
+                GfaRecord::Header(h) => {
+                    if has_header {
+                        continue;
+                    }
+
+                    h.line_no += offset;
+                }
+                GfaRecord::Segment(s) => {
+                    s.line_no += offset;
+                    rename(&mut s.name);
+
+                    shift(&mut s.outgoing_links);
+                    shift(&mut s.incoming_links);
+                    shift(&mut s.containments);
+                    shift(&mut s.contained_by);
+                    shift(&mut s.outgoing_jumps);
+                    shift(&mut s.incoming_jumps);
+                    shift(&mut s.outgoing_edges);
+                    shift(&mut s.incoming_edges);
+                    shift(&mut s.outgoing_gaps);
+                    shift(&mut s.incoming_gaps);
+                    shift(&mut s.fragments);
+
+                    if let Some(line_no) = shared.get(&(s.line_no - offset)) {
+                        self.merge_shared_segment(*line_no, s);
+                        continue;
+                    }
+
+                    if !options.store_sequences && s.sequence != "*" {
+                        // same as when parsing, keep the length around as an LN tag
+                        if !s.tags.contains("LN") && s.length.is_none() {
+                            s.set_ln_tag(Some(s.sequence.len() as i32));
+                        }
+
+                        s.sequence = "*".to_string();
+                    }
+                }
+                GfaRecord::Link(l) => {
+                    l.line_no += offset;
+                    rename_symbol(&mut l.from_segment, &mut self.symbols);
+                    rename_symbol(&mut l.to_segment, &mut self.symbols);
+                    rename_id_tag(&mut l.tags);
+                }
+                GfaRecord::Jump(j) => {
+                    j.line_no += offset;
+                    rename_symbol(&mut j.from_segment, &mut self.symbols);
+                    rename_symbol(&mut j.to_segment, &mut self.symbols);
+                    rename_id_tag(&mut j.tags);
+                }
+                GfaRecord::Containment(c) => {
+                    c.line_no += offset;
+                    rename_symbol(&mut c.container, &mut self.symbols);
+                    rename_symbol(&mut c.contained, &mut self.symbols);
+                    rename_id_tag(&mut c.tags);
+                }
+                GfaRecord::Edge(e) => {
+                    e.line_no += offset;
+                    if let Some(id) = e.id.as_mut() {
+                        rename(id);
+                    }
+                    rename_symbol(&mut e.from_segment, &mut self.symbols);
+                    rename_symbol(&mut e.to_segment, &mut self.symbols);
+                }
+                GfaRecord::Gap(g) => {
+                    g.line_no += offset;
+                    if let Some(id) = g.id.as_mut() {
+                        rename(id);
+                    }
+                    rename_symbol(&mut g.from_segment, &mut self.symbols);
+                    rename_symbol(&mut g.to_segment, &mut self.symbols);
+                }
+                GfaRecord::Fragment(f) => {
+                    f.line_no += offset;
+                    rename(&mut f.segment_name);
+                }
+                GfaRecord::Path(p) => {
+                    p.line_no += offset;
+                    rename(&mut p.name);
+                    p.steps
+                        .iter_mut()
+                        .for_each(|s| s.segment_id = step_target(s.segment_id));
+                }
+                GfaRecord::Walk(w) => {
+                    w.line_no += offset;
+                    w.walk
+                        .iter_mut()
+                        .for_each(|s| s.segment_id = step_target(s.segment_id));
+                }
+                GfaRecord::OrderedGroup(o) => {
+                    o.line_no += offset;
+                    rename(&mut o.name);
+                    o.members
+                        .iter_mut()
+                        .for_each(|m| rename_member(m, |n| renames.get(n).map(String::as_str)));
+                }
+                GfaRecord::UnorderedGroup(u) => {
+                    u.line_no += offset;
+                    rename(&mut u.name);
+                    u.members
+                        .iter_mut()
+                        .for_each(|m| rename_member(m, |n| renames.get(n).map(String::as_str)));
+                }
+            }
+
+            if !options.store_raw_lines {
+                clear_raw(&mut record);
+            }
+
+            self.push_record_and_update_index(Some(record));
+        }
+
+        let merge_messages = self.messages[first_message..].to_vec();
+
+        self.messages
+            .extend(other.messages.into_iter().map(|mut m| {
+                if m.line != 0 {
+                    m.line += offset;
+                }
+                m
+            }));
+
+        self.tag_names.extend(other.tag_names);
+        self.max_lines += other.max_lines;
+
+        if self.version == GFAVersion::Unknown {
+            self.version = other.version;
+        }
+
+        merge_messages
+    }
+
+    // attaches the (already shifted) bridges of a merged segment to the segment here,
+    // which takes over the sequence if it is only a ghost
+    fn merge_shared_segment(&mut self, line_no: usize, merged: &mut Segment) {
+        let Some(segment) = self.find_segment_mut(line_no) else {
+            return;
+        };
+
+        for (existing, more) in [
+            (&mut segment.outgoing_links, &mut merged.outgoing_links),
+            (&mut segment.incoming_links, &mut merged.incoming_links),
+            (&mut segment.containments, &mut merged.containments),
+            (&mut segment.contained_by, &mut merged.contained_by),
+            (&mut segment.outgoing_jumps, &mut merged.outgoing_jumps),
+            (&mut segment.incoming_jumps, &mut merged.incoming_jumps),
+            (&mut segment.outgoing_edges, &mut merged.outgoing_edges),
+            (&mut segment.incoming_edges, &mut merged.incoming_edges),
+            (&mut segment.outgoing_gaps, &mut merged.outgoing_gaps),
+            (&mut segment.incoming_gaps, &mut merged.incoming_gaps),
+            (&mut segment.fragments, &mut merged.fragments),
+        ] {
+            existing.append(more);
+        }
+
+        let known = |sequence: &str| sequence != "*" && !sequence.is_empty();
+
+        if segment.is_ghost() && !merged.is_ghost() {
+            segment.sequence = std::mem::take(&mut merged.sequence);
+            segment.length = merged.length;
+            segment.tags = std::mem::take(&mut merged.tags);
+            segment.set_ln_tag(merged.ln_length());
+        } else if known(&segment.sequence)
+            && known(&merged.sequence)
+            && segment.sequence != merged.sequence
+        {
+            self.messages.push(ParseMessage::new(
+                merged.line_no,
+                ParseMessageCode::MergedSegmentMismatch,
+                merged.name.clone(),
+            ));
+        }
+    }
+}
+
+fn clear_raw(record: &mut GfaRecord) {
+    let raw = match record {
+        GfaRecord::Header(r) => &mut r.raw,
+        GfaRecord::Segment(r) => &mut r.raw,
+        GfaRecord::Link(r) => &mut r.raw,
+        GfaRecord::Containment(r) => &mut r.raw,
+        GfaRecord::Path(r) => &mut r.raw,
+        GfaRecord::Walk(r) => &mut r.raw,
+        GfaRecord::Jump(r) => &mut r.raw,
+        GfaRecord::Fragment(r) => &mut r.raw,
+        GfaRecord::Edge(r) => &mut r.raw,
+        GfaRecord::Gap(r) => &mut r.raw,
+        GfaRecord::OrderedGroup(r) => &mut r.raw,
+        GfaRecord::UnorderedGroup(r) => &mut r.raw,
+    };
+
+    raw.clear();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::ParseMessageCode,
+        gfa::{GFAVersion, GfaParser, ParseOptions},
+    };
+
+    fn build(lines: &[&str]) -> GfaParser {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in lines {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        gfa
+    }
+
+    #[test]
+    fn merge_renames_collisions() {
+        let mut gfa = build(&["S\ta\tACGT", "S\tb\tACGT", "L\ta\t+\tb\t+\t0M"]);
+        let other = build(&[
+            "S\tb\tGG",
+            "S\tc\tTT",
+            "L\tb\t+\tc\t+\t0M",
+            "P\tp1\tb+,c+\t*",
+        ]);
+
+        let messages = gfa.merge(other, true, &ParseOptions::default());
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(gfa.segments().count(), 4);
+        assert_eq!(
+            gfa.messages
+                .iter()
+                .filter(|m| m.code == ParseMessageCode::NamespaceCollision)
+                .count(),
+            1
+        );
+
+        let merged_link = gfa.links().last().unwrap();
+        assert_eq!(gfa.resolve(merged_link.from_segment), "b_1");
+        assert_eq!(gfa.resolve(merged_link.to_segment), "c");
+        let merged_link_no = merged_link.line_no;
+
+        let renamed = gfa.find_segment_with_name("b_1").unwrap();
+        assert_eq!(renamed.sequence, "GG");
+        assert_eq!(renamed.outgoing_links, vec![merged_link_no]);
+        let renamed_no = renamed.line_no;
+
+        let path = gfa.paths().next().unwrap();
+        assert_eq!(path.steps[0].segment_id as usize, renamed_no);
+        assert!(
+            path.to_raw_line(GFAVersion::V1, &gfa)
+                .starts_with("P\tp1\tb_1+,c+")
+        );
+    }
+
+    #[test]
+    fn merge_shares_segments_by_name() {
+        let mut gfa = build(&["S\ta\tACGT", "S\tb\tACGT", "L\ta\t+\tb\t+\t0M"]);
+        let other = build(&[
+            "S\tb\tGG",
+            "S\tc\tTT",
+            "L\tb\t+\tc\t+\t0M",
+            "P\tp1\tb+,c+\t*",
+            "P\tx\tc+,d+\t*",
+        ]);
+
+        let messages = gfa.merge(other, false, &ParseOptions::default());
+
+        let codes: Vec<_> = messages.iter().map(|m| m.code.clone()).collect();
+        assert_eq!(codes, [ParseMessageCode::MergedSegmentMismatch]);
+
+        // the ghost d of `other` comes along, b is shared
+        let names: Vec<_> = gfa.segments().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["a", "b", "c", "d"]);
+
+        let b = gfa.find_segment_with_name("b").unwrap();
+        assert_eq!(b.sequence, "ACGT");
+        assert_eq!(b.incoming_links, [3]);
+        assert_eq!(b.outgoing_links.len(), 1);
+        let (b_no, merged_link_no) = (b.line_no, b.outgoing_links[0]);
+
+        assert_eq!(
+            gfa.resolve(gfa.find_link(merged_link_no).unwrap().from_segment),
+            "b"
+        );
+
+        let path = gfa.find_path_with_name("p1").unwrap();
+        assert_eq!(path.steps[0].segment_id as usize, b_no);
+        assert!(gfa.validate_path_connectivity("p1").is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use memmap2::Mmap;
+
+use crate::{
+    errors::{ParseMessage, ParseMessageCode},
+    gfa::{GfaParser, ParseOptions},
+};
+
+/// Parsing straight from a memory-mapped file.
+impl GfaParser {
+    /// Parses the GFA file at the given path like [`GfaParser::parse`], but maps the
+    /// file into memory and hands slices of the mapping to the line parser, instead of
+    /// copying every line into its own `String` first.
+    ///
+    /// If the file can't be mapped, this falls back to [`GfaParser::parse`].
+    ///
+    /// The mapping only lives for the duration of this call, and every record copies
+    /// what it keeps, so nothing borrows from the file once this returns. However, the
+    /// file must not be modified (truncated in particular) by this or any other process
+    /// while it is being parsed: the mapped bytes would change under the parser, and a
+    /// truncated file can crash it with a bus error.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// parser.parse_mmap("test/gfa_working.gfa", &ParseOptions::default()).unwrap();
+    ///
+    /// assert_eq!(parser.segments().count(), 4);
+    /// ```
+    pub fn parse_mmap(
+        &mut self,
+        path: impl Into<PathBuf>,
+        options: &ParseOptions,
+    ) -> Result<(), Vec<ParseMessage>> {
+        let path_buf = path.into();
+
+        let file = self.open_gfa_file(&path_buf)?;
+
+        // SAFETY: the mapping is dropped before returning and no record borrows from it,
+        // the caller is responsible for not modifying the file while it is parsed
+        let map = match unsafe { Mmap::map(file.get_ref()) } {
+            Ok(map) => map,
+            Err(_) => return self.parse(path_buf, options),
+        };
+
+        // same lines as BufRead::lines, so no empty line after the final newline
+        let bytes = map.strip_suffix(b"\n").unwrap_or(&map);
+        let mut raw_lines: Vec<(usize, &str)> = Vec::new();
+
+        if !bytes.is_empty() {
+            for (idx, line) in bytes.split(|&b| b == b'\n').enumerate() {
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+                match std::str::from_utf8(line) {
+                    Ok(l) => raw_lines.push((idx + 1, l)),
+                    Err(_) => self.messages.push(ParseMessage::new(
+                        idx + 1,
+                        ParseMessageCode::IOError,
+                        "(unable to read line)".into(),
+                    )),
+                }
+            }
+        }
+
+        self.parse_lines(&raw_lines, options);
+
+        if !self.stopped_early() {
+            self.check_header(&path_buf);
+            self.add_info_errors(options);
+        }
+
+        self.fatal_messages()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gfa::{GFAVersion, GfaParser, ParseOptions};
+
+    #[test]
+    fn mmap_matches_parse() {
+        let options = ParseOptions::default();
+
+        for file in ["test/gfa_working.gfa", "test/path.gfa"] {
+            let mut parsed = GfaParser::new();
+            let parsed_result = parsed.parse(file, &options);
+
+            let mut mapped = GfaParser::new();
+            let mapped_result = mapped.parse_mmap(file, &options);
+
+            assert_eq!(parsed_result.is_ok(), mapped_result.is_ok());
+            assert_eq!(mapped.records.len(), parsed.records.len());
+            assert_eq!(mapped.messages.len(), parsed.messages.len());
+
+            // tags are written in hash order, so only compare the set of columns
+            let columns = |line: String| {
+                let mut columns: Vec<String> = line.split('\t').map(str::to_string).collect();
+                columns.sort();
+                columns
+            };
+
+            for (a, b) in mapped.records.iter().zip(&parsed.records) {
+                assert_eq!(
+                    columns(a.to_raw_line(GFAVersion::V1, &mapped)),
+                    columns(b.to_raw_line(GFAVersion::V1, &parsed))
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use si_scale::scale_fn;
+
+use crate::{
+    gfa::GfaParser,
+    line::{BridgeRef, record::GfaRecord},
+};
+
+scale_fn!(base_pairs,
+    base: B1000,
+    constraint: UnitAndAbove,
+    mantissa_fmt: "{:.2}",
+    groupings: '_',
+    unit: "bp",
+    doc: "si base pairs"
+);
+
+/// Summary of a graph's size and shape, see [`GfaParser::stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GraphStats {
+    /// Sum of the lengths of every segment, see [`GfaParser::get_length`].
+    pub total_length: u64,
+    pub segments: usize,
+    pub links: usize,
+    /// Length of the shortest segment among the longest ones that together make up at
+    /// least half of the total length. 0 for a graph without segments.
+    pub n50: u64,
+    /// Number of groups of segments connected by any bridge, regardless of direction.
+    pub components: usize,
+    /// Number of segment sides with no bridges, see [`GfaParser::find_dead_ends`].
+    pub dead_ends: usize,
+    /// Number of segments with no bridges at all, see [`GfaParser::find_isolated_segments`].
+    pub isolated_segments: usize,
+}
+
+/// Whole-graph statistics.
+impl GfaParser {
+    /// Computes the [`GraphStats`] of the graph, in one pass over the segments and one
+    /// over the bridges.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// for line in ["S\ta\tACGT", "S\tb\tAC", "S\tc\tA", "L\ta\t+\tb\t+\t0M"] {
+    ///     parser.add_line(line, &ParseOptions::default()).unwrap();
+    /// }
+    ///
+    /// let stats = parser.stats();
+    /// assert_eq!((stats.total_length, stats.n50), (7, 4));
+    /// assert_eq!((stats.components, stats.isolated_segments), (2, 1));
+    /// ```
+    pub fn stats(&self) -> GraphStats {
+        let mut stats = GraphStats::default();
+        let mut lengths = vec![];
+
+        for segment in self.segments() {
+            let length = segment.get_length().max(0) as u64;
+            lengths.push(length);
+            stats.total_length += length;
+            stats.segments += 1;
+
+            let (left, right) = (segment.left_degree(self), segment.right_degree(self));
+            stats.dead_ends += usize::from(left == 0) + usize::from(right == 0);
+
+            if left == 0 && right == 0 {
+                stats.isolated_segments += 1;
+            }
+        }
+
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        let mut covered = 0;
+        for length in lengths {
+            covered += length;
+            if covered * 2 >= stats.total_length {
+                stats.n50 = length;
+                break;
+            }
+        }
+
+        stats.links = self.links().count();
+        stats.components = self.component_count();
+
+        stats
+    }
+
+    // union-find over the record indices of the segments
+    fn component_count(&self) -> usize {
+        let mut parents: Vec<usize> = (0..self.records.len()).collect();
+
+        fn root(parents: &mut [usize], mut idx: usize) -> usize {
+            while parents[idx] != idx {
+                parents[idx] = parents[parents[idx]];
+                idx = parents[idx];
+            }
+            idx
+        }
+
+        for record in &self.records {
+            let Some(bridge) = BridgeRef::from_record(record, self) else {
+                continue;
+            };
+
+            let (Some(from), Some(to)) = (
+                self.segment_record_index(bridge.from_segment),
+                self.segment_record_index(bridge.to_segment),
+            ) else {
+                continue;
+            };
+
+            let (from, to) = (root(&mut parents, from), root(&mut parents, to));
+            parents[from] = to;
+        }
+
+        (0..self.records.len())
+            .filter(|idx| matches!(self.records[*idx], GfaRecord::Segment(_)))
+            .filter(|idx| root(&mut parents, *idx) == *idx)
+            .count()
+    }
+}
+
+/// Prints one `name: value` line per statistic. Segments and links are left out, as
+/// [`crate::gfa::RecordCounts`] already prints them.
+impl std::fmt::Display for GraphStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "length: {} bp ({})",
+            self.total_length,
+            base_pairs(self.total_length as f64)
+        )?;
+        writeln!(f, "N50: {} bp", self.n50)?;
+        writeln!(f, "components: {}", self.components)?;
+        writeln!(f, "dead ends: {}", self.dead_ends)?;
+        writeln!(f, "isolated segments: {}", self.isolated_segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gfa::{GfaParser, ParseOptions};
+
+    #[test]
+    fn stats_of_walk_graph() {
+        let mut gfa = GfaParser::new();
+        let _ = gfa.parse("test/walk.gfa", &ParseOptions::default());
+
+        let stats = gfa.stats();
+
+        assert_eq!(stats.total_length, gfa.get_length());
+        assert_eq!(stats.segments, 7);
+        assert_eq!(stats.links, 8);
+        assert_eq!(stats.n50, 5);
+        assert_eq!(stats.components, 1);
+        assert_eq!(stats.dead_ends, gfa.find_dead_ends().len());
+        assert_eq!(stats.isolated_segments, 0);
+    }
+}
@@ -0,0 +1,163 @@
+use std::{io::BufRead, path::PathBuf};
+
+use crate::{
+    errors::{ParseMessage, ParseMessageCode},
+    gfa::{ErrorCap, GfaParser, MissingBridgeOptions, ParseOptions, raw_line_pass},
+    line::record::GfaRecord,
+};
+
+/// Parsing without holding the whole graph in memory.
+impl GfaParser {
+    /// Parses the GFA file at the given path like [`GfaParser::parse`], but hands every
+    /// parsed record to `callback` instead of keeping it in [`GfaParser::records`].
+    ///
+    /// The file is read once per pass rather than being loaded into memory, and only
+    /// headers and segments are retained, since every other record is validated against
+    /// them. Bridges are validated as usual because segments are parsed first.
+    ///
+    /// Bridges are not retained, so the links between consecutive path and walk steps
+    /// cannot be checked: `LinkNotFound` is not reported and no ghost links are created.
+    /// Once parsing is done the retained segments have no adjacency, as the bridges they
+    /// would point to are gone.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// let mut paths = 0;
+    ///
+    /// let result = parser.parse_streaming("test/path.gfa", &ParseOptions::default(), |record| {
+    ///     if record.as_path().is_some() {
+    ///         paths += 1;
+    ///     }
+    /// });
+    ///
+    /// assert!(result.is_ok());
+    /// assert!(paths > 0);
+    /// assert_eq!(parser.paths().count(), 0);
+    /// ```
+    pub fn parse_streaming<F: FnMut(&GfaRecord)>(
+        &mut self,
+        path: impl Into<PathBuf>,
+        options: &ParseOptions,
+        mut callback: F,
+    ) -> Result<(), Vec<ParseMessage>> {
+        let path_buf = path.into();
+
+        // links between trail steps can't be looked up, so don't try to repair them
+        let trail_options = ParseOptions {
+            handle_missing_bridge: MissingBridgeOptions::Ignore,
+            ..options.clone()
+        };
+
+        let mut cap = ErrorCap::new(self, options);
+
+        for pass in 0..4 {
+            if pass == 3 {
+                // isolated segments and dead ends can only be found while the adjacency is intact
+                self.add_info_errors(options);
+                self.rebuild_adjacency();
+            }
+
+            let file = self.open_gfa_file(&path_buf)?;
+
+            for (idx, line) in file.lines().enumerate() {
+                let line_no = idx + 1;
+
+                let line = match line {
+                    Ok(l) => l,
+                    Err(_) => {
+                        if pass == 0 {
+                            self.messages.push(ParseMessage::new(
+                                line_no,
+                                ParseMessageCode::IOError,
+                                "(unable to read line)".into(),
+                            ));
+                        }
+                        continue;
+                    }
+                };
+
+                // ghost segments need line numbers past the end of the file
+                if pass == 0 {
+                    self.max_lines = line_no;
+
+                    self.note_unparsed_line(line_no, &line, options);
+                }
+
+                if raw_line_pass(&line) != Some(pass) {
+                    continue;
+                }
+
+                let pass_options = if pass == 3 { &trail_options } else { options };
+                let (parsed_line, mut errs) =
+                    GfaRecord::parse_line((self, line.as_str(), line_no, pass_options));
+
+                if pass == 3 {
+                    errs.retain(|e| e.code != ParseMessageCode::LinkNotFound);
+                }
+
+                self.messages.extend(errs);
+
+                if cap.exceeded(&mut self.messages) {
+                    return self.fatal_messages();
+                }
+
+                let Some(record) = parsed_line else {
+                    continue;
+                };
+
+                callback(&record);
+
+                if matches!(record, GfaRecord::Header(_) | GfaRecord::Segment(_)) {
+                    self.push_record_and_update_index(Some(record));
+                }
+            }
+        }
+
+        self.check_header(&path_buf);
+
+        self.fatal_messages()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::ParseMessageCode,
+        gfa::{GfaParser, ParseOptions},
+        line::record::GfaRecord,
+    };
+
+    #[test]
+    fn streaming_matches_parse() {
+        let options = ParseOptions::default();
+
+        let mut parsed = GfaParser::new();
+        parsed.parse("test/path.gfa", &options).unwrap();
+
+        let mut streamed = GfaParser::new();
+        let mut seen = 0;
+        let mut links = 0;
+        streamed
+            .parse_streaming("test/path.gfa", &options, |record| {
+                seen += 1;
+                if matches!(record, GfaRecord::Link(_)) {
+                    links += 1;
+                }
+            })
+            .unwrap();
+
+        assert_eq!(seen, parsed.records.len());
+        assert_eq!(links, parsed.links().count());
+        assert_eq!(streamed.segments().count(), parsed.segments().count());
+        assert_eq!(streamed.links().count(), 0);
+        assert!(
+            streamed
+                .messages
+                .iter()
+                .all(|m| m.code != ParseMessageCode::LinkNotFound)
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
+
+use crate::{
+    gfa::GfaParser,
+    line::{BridgeRef, path::Step, record::GfaRecord},
+};
+
+/// Extracting part of a graph.
+impl GfaParser {
+    /// Returns a new graph with only the named segments, the bridges and fragments
+    /// between them, and the paths and walks that stay within them.
+    ///
+    /// Paths and walks that leave the set are dropped, unless `truncate_trails` is set:
+    /// then every run of consecutive steps within the set is kept as a path or walk of
+    /// its own. The first run of a path keeps its name, the others are suffixed. Walks
+    /// cut short lose their start and end positions, which no longer apply. Groups only
+    /// keep members that are still in the graph, and are dropped if none are left.
+    ///
+    /// Headers are kept, records are renumbered from line 1 in their original order,
+    /// and the new graph has no messages. Names that aren't segments are ignored.
+    ///
+    /// Example:
+    /// ```
+    /// use std::collections::HashSet;
+    /// use parfait_gfa::gfa::{GfaParser, ParseOptions};
+    ///
+    /// let mut parser = GfaParser::new();
+    /// for line in ["S\ta\tA", "S\tb\tC", "S\tc\tG", "L\ta\t+\tb\t+\t0M", "L\tb\t+\tc\t+\t0M"] {
+    ///     parser.add_line(line, &ParseOptions::default()).unwrap();
+    /// }
+    ///
+    /// let names: HashSet<String> = ["b", "c"].map(String::from).into();
+    /// let subgraph = parser.subgraph(&names, false);
+    ///
+    /// assert_eq!(subgraph.segments().count(), 2);
+    /// assert_eq!(subgraph.links().count(), 1);
+    /// assert_eq!(subgraph.records[0].line_no(), 1);
+    /// ```
+    pub fn subgraph(&self, segment_names: &HashSet<String>, truncate_trails: bool) -> GfaParser {
+        let segments: HashSet<usize> = segment_names
+            .iter()
+            .filter_map(|name| self.segment_line_no(name))
+            .collect();
+        let names: HashSet<&str> = self
+            .segments()
+            .filter(|s| segments.contains(&s.line_no))
+            .map(|s| s.name.as_str())
+            .collect();
+
+        let in_set = |step: &Step| segments.contains(&(step.segment_id as usize));
+
+        // names of all records, so split paths don't take one
+        let mut taken: HashSet<String> = self
+            .records
+            .iter()
+            .flat_map(GfaRecord::declared_names)
+            .collect();
+        let mut records: Vec<GfaRecord> = vec![];
+
+        for record in &self.records {
+            if let Some(bridge) = BridgeRef::from_record(record, self) {
+                if names.contains(bridge.from_segment) && names.contains(bridge.to_segment) {
+                    records.push(record.clone());
+                }
+                continue;
+            }
+
+            match record {
+                GfaRecord::Header(_)
+                | GfaRecord::OrderedGroup(_)
+                | GfaRecord::UnorderedGroup(_) => {
+                    records.push(record.clone());
+                }
+                GfaRecord::Segment(s) if segments.contains(&s.line_no) => {
+                    records.push(record.clone())
+                }
+                GfaRecord::Fragment(f) if names.contains(f.segment_name.as_str()) => {
+                    records.push(record.clone());
+                }
+                GfaRecord::Path(p) => {
+                    let has_overlaps = p.overlaps.len() + 1 == p.steps.len();
+
+                    for (idx, run) in runs(&p.steps, in_set, truncate_trails)
+                        .into_iter()
+                        .enumerate()
+                    {
+                        let mut path = p.clone();
+
+                        if idx > 0 {
+                            path.name = unused_name(&p.name, &mut taken);
+                        }
+                        if has_overlaps {
+                            path.overlaps = p.overlaps[run.start..run.end - 1].to_vec();
+                        }
+                        path.steps = p.steps[run].to_vec();
+
+                        records.push(GfaRecord::Path(path));
+                    }
+                }
+                GfaRecord::Walk(w) => {
+                    for run in runs(&w.walk, in_set, truncate_trails) {
+                        let mut walk = w.clone();
+
+                        if run.len() != w.walk.len() {
+                            (walk.seq_start, walk.seq_end) = (None, None);
+                        }
+                        walk.walk = w.walk[run].to_vec();
+
+                        records.push(GfaRecord::Walk(walk));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // groups can reference anything, so they are only filtered once everything else is in
+        let kept: HashSet<String> = records.iter().flat_map(GfaRecord::declared_names).collect();
+        let is_kept = |member: &String| kept.contains(member.trim_end_matches(['+', '-']));
+
+        records.retain_mut(|record| match record {
+            GfaRecord::OrderedGroup(o) => {
+                o.members.retain(is_kept);
+                !o.members.is_empty()
+            }
+            GfaRecord::UnorderedGroup(u) => {
+                u.members.retain(is_kept);
+                !u.members.is_empty()
+            }
+            _ => true,
+        });
+
+        let mut renumbered: HashMap<usize, usize> = HashMap::new();
+
+        for (idx, record) in records.iter_mut().enumerate() {
+            renumbered.insert(record.line_no(), idx + 1);
+            record.set_line_no(idx + 1);
+        }
+
+        for steps in records.iter_mut().filter_map(|r| match r {
+            GfaRecord::Path(p) => Some(&mut p.steps),
+            GfaRecord::Walk(w) => Some(&mut w.walk),
+            _ => None,
+        }) {
+            for step in steps {
+                step.segment_id = renumbered[&(step.segment_id as usize)] as u32;
+            }
+        }
+
+        let mut subgraph = GfaParser::new();
+        subgraph.version = self.version.clone();
+        subgraph.tag_names = self.tag_names.clone();
+        subgraph.symbols = self.symbols.clone();
+        subgraph.max_lines = records.len();
+        subgraph.records = records;
+
+        subgraph.reindex();
+        subgraph.rebuild_adjacency();
+
+        subgraph
+    }
+
+    /// Returns the part of the graph within `k` hops of the `seed` segment, see
+    /// [`GfaParser::subgraph`]. Hops follow links, jumps and edges in either direction,
+    /// on either strand. Every bridge between two segments of the neighbourhood is kept,
+    /// not only the ones the hops went through, and paths and walks are cut down to their
+    /// runs within the neighbourhood.
+    ///
+    /// Returns an empty graph if `seed` does not exist.
+    pub fn neighbourhood(&self, seed: &str, k: usize) -> GfaParser {
+        let Some(seed_no) = self.segment_line_no(seed) else {
+            return GfaParser::new();
+        };
+
+        let reached = self.hop_distances(&[(seed_no, true), (seed_no, false)], k, true);
+        let names: HashSet<String> = reached
+            .keys()
+            .filter_map(|(line_no, _)| self.find_segment(*line_no))
+            .map(|s| s.name.clone())
+            .collect();
+
+        self.subgraph(&names, true)
+    }
+}
+
+// the runs of steps to keep: every run within the set, or all steps if they are all in it
+fn runs(
+    steps: &[Step],
+    in_set: impl Fn(&Step) -> bool,
+    truncate_trails: bool,
+) -> Vec<Range<usize>> {
+    if !truncate_trails {
+        let all = steps.iter().all(in_set).then_some(0..steps.len());
+        return all.into_iter().collect();
+    }
+
+    let mut runs = vec![];
+    let mut start = None;
+
+    for (idx, step) in steps.iter().enumerate() {
+        match (in_set(step), start) {
+            (true, None) => start = Some(idx),
+            (false, Some(s)) => {
+                runs.push(s..idx);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(s) = start {
+        runs.push(s..steps.len());
+    }
+
+    runs
+}
+
+fn unused_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let name = (1..)
+        .map(|n| format!("{name}_{n}"))
+        .find(|candidate| !taken.contains(candidate))
+        .unwrap_or_default();
+
+    taken.insert(name.clone());
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::gfa::{GfaParser, ParseOptions};
+
+    fn build(lines: &[&str]) -> GfaParser {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in lines {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        gfa
+    }
+
+    fn names(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn subgraph_keeps_what_stays_inside() {
+        let gfa = build(&[
+            "H\tVN:Z:1.0",
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "S\tc\tACGT",
+            "S\td\tACGT",
+            "L\ta\t+\tb\t+\t1M",
+            "L\tb\t+\tc\t+\t1M",
+            "L\tc\t+\td\t+\t1M",
+            "P\tp1\ta+,b+,c+,d+\t1M,1M,1M",
+            "P\tp2\tb+,c+\t1M",
+            "W\tNA1\t1\tchr1\t0\t16\t>a>b>c>d",
+            "U\tu1\tp1 p2 a",
+        ]);
+
+        let mut subgraph = gfa.subgraph(&names(&["b", "c", "x"]), false);
+
+        let lines: Vec<usize> = subgraph.records.iter().map(|r| r.line_no()).collect();
+        assert_eq!(lines, (1..=6).collect::<Vec<_>>());
+        assert_eq!(subgraph.segments().count(), 2);
+        assert_eq!(subgraph.links().count(), 1);
+        assert_eq!(subgraph.walks().count(), 0);
+
+        let p2_first_step = subgraph.paths().next().unwrap().steps[0].segment_id as usize;
+        let b = subgraph.find_segment_with_name("b").unwrap();
+        assert_eq!(p2_first_step, b.line_no);
+        assert_eq!(b.outgoing_links.len(), 1);
+
+        assert_eq!(subgraph.unordered_groups().next().unwrap().members, ["p2"]);
+    }
+
+    #[test]
+    fn subgraph_truncates_trails_to_runs() {
+        let gfa = build(&[
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "S\tc\tACGT",
+            "L\ta\t+\tb\t+\t1M",
+            "L\tb\t+\tc\t+\t2M",
+            "P\tp1\ta+,b+,a+,c+\t1M,*,*",
+            "W\tNA1\t1\tchr1\t0\t16\t>a>b",
+        ]);
+
+        let subgraph = gfa.subgraph(&names(&["a", "b"]), true);
+
+        let paths: Vec<_> = subgraph
+            .paths()
+            .map(|p| (p.name.as_str(), p.steps.len(), p.overlaps.clone()))
+            .collect();
+        assert_eq!(paths, [("p1", 3, vec!["1M".to_string(), "*".to_string()])]);
+
+        let subgraph = gfa.subgraph(&names(&["b", "c"]), true);
+
+        let paths: Vec<_> = subgraph
+            .paths()
+            .map(|p| (p.name.as_str(), p.steps.len()))
+            .collect();
+        assert_eq!(paths, [("p1", 1), ("p1_1", 1)]);
+
+        let walk = subgraph.walks().next().unwrap();
+        assert_eq!(walk.walk.len(), 1);
+        assert_eq!(walk.seq_start, None);
+    }
+
+    #[test]
+    fn neighbourhood_within_k_hops() {
+        let gfa = build(&[
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "S\tc\tACGT",
+            "S\td\tACGT",
+            "S\te\tACGT",
+            "L\ta\t+\tb\t+\t0M",
+            "L\tc\t-\tb\t-\t0M",
+            "L\tc\t+\td\t+\t0M",
+            "L\td\t+\te\t+\t0M",
+            "L\ta\t+\tc\t+\t0M",
+        ]);
+
+        let neighbourhood = |seed: &str, k: usize| {
+            let subgraph = gfa.neighbourhood(seed, k);
+            let mut names: Vec<String> = subgraph.segments().map(|s| s.name.clone()).collect();
+            names.sort();
+            (names, subgraph.links().count())
+        };
+
+        assert_eq!(neighbourhood("b", 0), (vec!["b".to_string()], 0));
+        // a and c are one hop away on either side, and a -> c is kept without being hopped along
+        let (names, links) = neighbourhood("b", 1);
+        assert_eq!(names, ["a", "b", "c"]);
+        assert_eq!(links, 3);
+
+        let (names, links) = neighbourhood("b", 2);
+        assert_eq!(names, ["a", "b", "c", "d"]);
+        assert_eq!(links, 4);
+        assert_eq!(neighbourhood("missing", 2).0.len(), 0);
+    }
+}
@@ -3,6 +3,11 @@ use crate::errors::ParseMessageCode;
 use crate::gfa::GfaParser;
 use crate::gfa::MissingSegmentOptions;
 use crate::gfa::ParseOptions;
+use crate::line::record::GfaRecord;
+use crate::line::segment::Segment;
+use crate::line::symbol::Symbol;
+use crate::line::utils::Alignment;
+use crate::line::utils::cigar_reference_length;
 use crate::line::utils::is_valid_cigar;
 use crate::line::utils::is_valid_name;
 use crate::optional_field::OptionalFieldValue;
@@ -25,6 +30,81 @@ pub enum BridgeType {
     Gap,
 }
 
+/// Borrowed, type-independent view of a link, jump, containment, edge or gap.
+///
+/// Containments go from the container to the contained segment.
+/// `overlap` is `None` for jumps and gaps, and for edges without a CIGAR alignment.
+#[derive(Debug, Clone, Copy)]
+pub struct BridgeRef<'a> {
+    pub line_no: usize,
+    pub bridge_type: BridgeType,
+    pub from_segment: &'a str,
+    pub from_orientation: bool,
+    pub to_segment: &'a str,
+    pub to_orientation: bool,
+    pub overlap: Option<&'a str>,
+}
+
+impl<'a> BridgeRef<'a> {
+    /// Returns `None` if the record is not a bridge.
+    /// Segment symbols are resolved through `gfa`, the parser that interned them.
+    pub fn from_record(record: &'a GfaRecord, gfa: &'a GfaParser) -> Option<Self> {
+        let name = |symbol: Symbol| gfa.resolve(symbol);
+
+        match record {
+            GfaRecord::Link(l) => Some(Self {
+                line_no: l.line_no,
+                bridge_type: BridgeType::Link,
+                from_segment: name(l.from_segment),
+                from_orientation: l.from_orientation,
+                to_segment: name(l.to_segment),
+                to_orientation: l.to_orientation,
+                overlap: Some(&l.overlap),
+            }),
+            GfaRecord::Jump(j) => Some(Self {
+                line_no: j.line_no,
+                bridge_type: BridgeType::Jump,
+                from_segment: name(j.from_segment),
+                from_orientation: j.from_orientation,
+                to_segment: name(j.to_segment),
+                to_orientation: j.to_orientation,
+                overlap: None,
+            }),
+            GfaRecord::Containment(c) => Some(Self {
+                line_no: c.line_no,
+                bridge_type: BridgeType::Containment,
+                from_segment: name(c.container),
+                from_orientation: c.container_orientation,
+                to_segment: name(c.contained),
+                to_orientation: c.contained_orientation,
+                overlap: Some(&c.overlap),
+            }),
+            GfaRecord::Edge(e) => Some(Self {
+                line_no: e.line_no,
+                bridge_type: BridgeType::Edge,
+                from_segment: name(e.from_segment),
+                from_orientation: e.from_orientation,
+                to_segment: name(e.to_segment),
+                to_orientation: e.to_orientation,
+                overlap: match &e.alignment {
+                    Some(Alignment::CIGAR(cigar)) => Some(cigar),
+                    _ => None,
+                },
+            }),
+            GfaRecord::Gap(g) => Some(Self {
+                line_no: g.line_no,
+                bridge_type: BridgeType::Gap,
+                from_segment: name(g.from_segment),
+                from_orientation: g.from_orientation,
+                to_segment: name(g.to_segment),
+                to_orientation: g.to_orientation,
+                overlap: None,
+            }),
+            _ => None,
+        }
+    }
+}
+
 pub struct BridgeParts<'a> {
     pub bridge_type: BridgeType,
     pub from_segment: &'a str,
@@ -42,154 +122,300 @@ pub fn parse_generic_bridge(
     map: &mut TagMap,
     options: &ParseOptions,
 ) -> (Option<GenericBridge>, Vec<ParseMessage>) {
-        let mut errors = vec![];
+    let mut errors = vec![];
 
-        let bridge_type = parts.bridge_type;
+    let bridge_type = parts.bridge_type;
 
-        let mut from_segment = parts.from_segment.to_owned();
-        let mut to_segment = parts.to_segment.to_owned();
+    let connected = connect_bridge(
+        gfa,
+        bridge_type,
+        parts.from_segment,
+        parts.to_segment,
+        n,
+        options,
+        &mut errors,
+    );
 
-        // check if the segment exists
-        let p_from_segment_none = gfa.find_segment_with_name(&from_segment).is_none();
-        let p_to_segment_none = gfa.find_segment_with_name(&to_segment).is_none();
+    let Some(ends) = connected else {
+        return (None, errors);
+    };
 
-        if p_from_segment_none {
-            errors.push(ParseMessage::new(
-                n,
-                ParseMessageCode::SegmentNotFound,
-                from_segment.to_owned(),
-            ));
-        }
+    // check if the orientations are valid
+    if parts.from_orientation != "-" && parts.from_orientation != "+" {
+        errors.push(ParseMessage::new(
+            n,
+            ParseMessageCode::InvalidOrientation,
+            parts.from_orientation.to_owned(),
+        ));
+    }
 
-        if p_to_segment_none {
-            errors.push(ParseMessage::new(
-                n,
-                ParseMessageCode::SegmentNotFound,
-                to_segment.to_owned(),
-            ));
+    if parts.to_orientation != "-" && parts.to_orientation != "+" {
+        errors.push(ParseMessage::new(
+            n,
+            ParseMessageCode::InvalidOrientation,
+            parts.to_orientation.to_owned(),
+        ));
+    }
+
+    // default to + if orientation is not valid
+    let from_orientation = parts.from_orientation != "-";
+    let to_orientation = parts.to_orientation != "-";
+
+    // Add Link/Jump/Containment EdgeID tag to namespace
+    if !matches!(parts.bridge_type, BridgeType::Edge | BridgeType::Gap)
+        && let Some(edge_id) = map.get::<String>("ID")
+    {
+        if !is_valid_name(&edge_id) {
+            gfa.messages
+                .push(ParseMessage::new(n, ParseMessageCode::InvalidID, edge_id));
+        } else {
+            let uid = gfa.ensure_name_unique(n, edge_id);
+            map.tags
+                .insert("ID".into(), OptionalFieldValue::String(uid));
         }
+    }
 
-        
-        if p_from_segment_none || p_to_segment_none {
-            if p_from_segment_none && p_to_segment_none {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::BridgeGoesNowhere,
-                    format!("({:?}) / {} and {}", parts.bridge_type, from_segment, to_segment),
-                ));
-            }
+    errors.extend(check_bridge_ends(
+        gfa,
+        bridge_type,
+        &ends,
+        parts.overlap,
+        n,
+        raw,
+    ));
 
-            if options.handle_missing_segment != MissingSegmentOptions::Ignore {
-                // Skip upon a missing segment if we have to
-                if options.handle_missing_segment != MissingSegmentOptions::CreateGhost {
-                    return (None, errors);
-                }
+    (
+        Some(GenericBridge {
+            from_segment: ends.from_segment,
+            from_orientation,
+            to_segment: ends.to_segment,
+            to_orientation,
+        }),
+        errors,
+    )
+}
 
-                // Otherwise, create a ghost segment
+/// Checks a bridge against the segments it was connected to: that it doesn't connect a
+/// segment to itself, and that its overlap (if it has one) is a valid CIGAR that fits
+/// within them. Shared by parsed lines and by records added with [`GfaParser::add_record`].
+pub(crate) fn check_bridge_ends(
+    gfa: &GfaParser,
+    bridge_type: BridgeType,
+    ends: &BridgeEnds,
+    overlap: Option<&str>,
+    n: usize,
+    raw: &str,
+) -> Vec<ParseMessage> {
+    let mut errors = vec![];
 
-                if p_from_segment_none {
-                    let g = &gfa.create_ghost_segment(from_segment.to_owned());
-                    from_segment = g.name.clone();
-                }
+    if ends.is_self_bridge() {
+        let code = match bridge_type {
+            BridgeType::Containment => ParseMessageCode::SelfContainment,
+            _ => ParseMessageCode::SelfBridge,
+        };
 
-                if p_to_segment_none {
-                    let g = &gfa.create_ghost_segment(to_segment.to_owned());
-                    to_segment = g.name.clone();
-                }
-            }
-        }
+        errors.push(ParseMessage::new(n, code, raw.to_owned()));
+    }
 
-        if let Some(from) = gfa.find_segment_with_name(&from_segment) {
-            match bridge_type {
-                BridgeType::Link => from.outgoing_links.push(n),
-                BridgeType::Jump => from.outgoing_jumps.push(n),
-                BridgeType::Containment => from.containments.push(n),
-                BridgeType::Edge => from.outgoing_edges.push(n),
-                BridgeType::Gap => from.outgoing_gaps.push(n),
-            }
-        }
+    let Some(overlap) = overlap else {
+        return errors;
+    };
 
-        if let Some(to) = gfa.find_segment_with_name(&to_segment) {
-            match bridge_type {
-                BridgeType::Link => to.incoming_links.push(n),
-                BridgeType::Jump => to.incoming_jumps.push(n),
-                BridgeType::Containment => to.contained_by.push(n),
-                BridgeType::Edge => to.incoming_edges.push(n),
-                BridgeType::Gap => to.incoming_gaps.push(n),
-            }
-        }
+    if overlap != "*" && !is_valid_cigar(overlap) {
+        errors.push(ParseMessage::new(
+            n,
+            ParseMessageCode::InvalidCIGAR,
+            overlap.to_owned(),
+        ));
+    }
 
-        // check if the orientations are valid
-        if parts.from_orientation != "-" && parts.from_orientation != "+" {
-            errors.push(ParseMessage::new(
-                n,
-                ParseMessageCode::InvalidOrientation,
-                parts.from_orientation.to_owned(),
-            ));
-        }
+    // a link can't overlap more bases than either of its segments has
+    if bridge_type == BridgeType::Link
+        && let Some(overlap_length) = cigar_reference_length(overlap)
+    {
+        let exceeded = ends.segments(gfa).find_map(|(name, segment)| {
+            let length = segment.get_length();
 
-        if parts.to_orientation != "-" && parts.to_orientation != "+" {
+            // 0 means the length is unknown (e.g. ghost segments)
+            (length > 0 && overlap_length > length as u32)
+                .then(|| format!("{overlap} is longer than {name} ({length}bp)"))
+        });
+
+        if let Some(exceeded) = exceeded {
             errors.push(ParseMessage::new(
                 n,
-                ParseMessageCode::InvalidOrientation,
-                parts.to_orientation.to_owned(),
+                ParseMessageCode::OverlapExceedsSegment,
+                exceeded,
             ));
         }
+    }
+
+    errors
+}
+
+/// The segments a bridge was connected to, by name and by index into
+/// [`GfaParser::records`], so they are only looked up once per bridge.
+#[derive(Debug, Clone)]
+pub(crate) struct BridgeEnds {
+    bridge_type: BridgeType,
+    pub from_segment: String,
+    pub to_segment: String,
+    from_idx: Option<usize>,
+    to_idx: Option<usize>,
+}
+
+impl BridgeEnds {
+    pub(crate) fn is_self_bridge(&self) -> bool {
+        self.from_segment == self.to_segment
+    }
+
+    // the ends that exist (both unless missing segments are ignored), with their names
+    fn segments<'a>(&'a self, gfa: &'a GfaParser) -> impl Iterator<Item = (&'a str, &'a Segment)> {
+        [
+            (&self.from_segment, self.from_idx),
+            (&self.to_segment, self.to_idx),
+        ]
+        .into_iter()
+        .filter_map(|(name, idx)| {
+            let segment = gfa.records.get(idx?)?.as_segment()?;
+            Some((name.as_str(), segment))
+        })
+    }
+}
+
+/// Resolves both endpoints of a bridge and registers the bridge at line `n` in the
+/// adjacency vectors of its segments.
+///
+/// Missing segments are reported and handled according to `options.handle_missing_segment`.
+/// Returns the (possibly ghost) segments, or `None` if the bridge should be skipped.
+pub(crate) fn connect_bridge(
+    gfa: &mut GfaParser,
+    bridge_type: BridgeType,
+    from_segment: &str,
+    to_segment: &str,
+    n: usize,
+    options: &ParseOptions,
+    errors: &mut Vec<ParseMessage>,
+) -> Option<BridgeEnds> {
+    let ends = resolve_bridge_ends(
+        gfa,
+        bridge_type,
+        from_segment,
+        to_segment,
+        n,
+        options,
+        errors,
+    )?;
+    wire_bridge(gfa, &ends, n);
 
-        // default to + if orientation is not valid
-        let from_orientation = parts.from_orientation != "-";
-        let to_orientation = parts.to_orientation != "-";
+    Some(ends)
+}
+
+// like connect_bridge, but leaves the adjacency alone
+pub(crate) fn resolve_bridge_ends(
+    gfa: &mut GfaParser,
+    bridge_type: BridgeType,
+    from_segment: &str,
+    to_segment: &str,
+    n: usize,
+    options: &ParseOptions,
+    errors: &mut Vec<ParseMessage>,
+) -> Option<BridgeEnds> {
+    let mut from_segment = from_segment.to_owned();
+    let mut to_segment = to_segment.to_owned();
 
-        if from_segment == to_segment {
-            let code = match bridge_type {
-                BridgeType::Link => ParseMessageCode::SelfBridge,
-                BridgeType::Jump => ParseMessageCode::SelfBridge,
-                BridgeType::Edge => ParseMessageCode::SelfBridge,
-                BridgeType::Containment => ParseMessageCode::SelfContainment,
-                BridgeType::Gap => ParseMessageCode::SelfBridge,
-            };
+    // check if the segment exists
+    let mut from_idx = gfa.segment_record_index(&from_segment);
+    let mut to_idx = gfa.segment_record_index(&to_segment);
 
+    let p_from_segment_none = from_idx.is_none();
+    let p_to_segment_none = to_idx.is_none();
+
+    if p_from_segment_none {
+        errors.push(ParseMessage::new(
+            n,
+            ParseMessageCode::SegmentNotFound,
+            from_segment.to_owned(),
+        ));
+    }
+
+    if p_to_segment_none {
+        errors.push(ParseMessage::new(
+            n,
+            ParseMessageCode::SegmentNotFound,
+            to_segment.to_owned(),
+        ));
+    }
+
+    if p_from_segment_none || p_to_segment_none {
+        if p_from_segment_none && p_to_segment_none {
             errors.push(ParseMessage::new(
                 n,
-                code,
-                raw.to_owned(),
-            ));            
+                ParseMessageCode::BridgeGoesNowhere,
+                format!("({:?}) / {} and {}", bridge_type, from_segment, to_segment),
+            ));
         }
 
-        // Add Link/Jump/Containment EdgeID tag to namespace
-        if !matches!(parts.bridge_type, BridgeType::Edge | BridgeType::Gap) {
-            if let Some(edge_id) = map.get::<String>("ID") {
-                if !is_valid_name(&edge_id) {
-                    gfa.messages.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::InvalidID,
-                        edge_id,
-                    ));
-                } else {
-                    let uid = gfa.ensure_name_unique(n, edge_id);
-                    map.0.insert("ID".into(), OptionalFieldValue::String(uid));
-                }
+        if options.handle_missing_segment != MissingSegmentOptions::Ignore {
+            // Skip upon a missing segment if we have to
+            if options.handle_missing_segment != MissingSegmentOptions::CreateGhost {
+                return None;
             }
-        }
 
-        if let Some(overlap) = parts.overlap {    
-            if overlap != "*" && !is_valid_cigar(overlap) {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidCIGAR,
-                    overlap.to_owned(),
-                ));
+            // Otherwise, create a ghost segment
+
+            if p_from_segment_none {
+                let idx = gfa.ghost_for(&from_segment, options);
+                from_segment = gfa.records[idx]
+                    .as_segment()
+                    .map_or(from_segment, |s| s.name.clone());
+                from_idx = Some(idx);
+            }
+
+            if p_to_segment_none {
+                let idx = gfa.ghost_for(&to_segment, options);
+                to_segment = gfa.records[idx]
+                    .as_segment()
+                    .map_or(to_segment, |s| s.name.clone());
+                to_idx = Some(idx);
             }
         }
+    }
 
-        (
-            Some(GenericBridge {
-                from_segment,
-                from_orientation,
-                to_segment,
-                to_orientation,
-            }),
-            errors,
-        )
+    Some(BridgeEnds {
+        bridge_type,
+        from_segment,
+        to_segment,
+        from_idx,
+        to_idx,
+    })
+}
+
+// registers the bridge at line `n` in the adjacency vectors of the segments at its ends
+pub(crate) fn wire_bridge(gfa: &mut GfaParser, ends: &BridgeEnds, n: usize) {
+    if let Some(from) = ends
+        .from_idx
+        .and_then(|idx| gfa.records[idx].as_mut_segment())
+    {
+        match ends.bridge_type {
+            BridgeType::Link => from.outgoing_links.push(n),
+            BridgeType::Jump => from.outgoing_jumps.push(n),
+            BridgeType::Containment => from.containments.push(n),
+            BridgeType::Edge => from.outgoing_edges.push(n),
+            BridgeType::Gap => from.outgoing_gaps.push(n),
+        }
+    }
 
+    if let Some(to) = ends
+        .to_idx
+        .and_then(|idx| gfa.records[idx].as_mut_segment())
+    {
+        match ends.bridge_type {
+            BridgeType::Link => to.incoming_links.push(n),
+            BridgeType::Jump => to.incoming_jumps.push(n),
+            BridgeType::Containment => to.contained_by.push(n),
+            BridgeType::Edge => to.incoming_edges.push(n),
+            BridgeType::Gap => to.incoming_gaps.push(n),
+        }
+    }
 }
@@ -3,10 +3,13 @@ use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
-use crate::line::bridge::parse_generic_bridge;
 use crate::line::bridge::BridgeParts;
 use crate::line::bridge::BridgeType;
+use crate::line::bridge::parse_generic_bridge;
+use crate::line::symbol::Symbol;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::cigar_reference_length;
+use crate::line::utils::required_columns;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
@@ -15,9 +18,9 @@ pub struct Containment {
     pub raw: String,
     pub tags: TagMap,
 
-    pub container: String,
+    pub container: Symbol,
     pub container_orientation: bool,
-    pub contained: String,
+    pub contained: Symbol,
     pub contained_orientation: bool,
     pub position: i32,
     pub overlap: String,
@@ -36,16 +39,27 @@ impl Containment {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
+        let [
+            container,
+            container_orientation,
+            contained,
+            contained_orientation,
+            pos,
+            overlap,
+        ] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
 
         let (containment_as_bridge, errors) = parse_generic_bridge(
             gfa,
-            BridgeParts{
+            BridgeParts {
                 bridge_type: BridgeType::Containment,
-                from_segment: parts[1],
-                from_orientation: parts[2],
-                to_segment: parts[3],
-                to_orientation: parts[4],
-                overlap: Some(parts[6]),
+                from_segment: container,
+                from_orientation: container_orientation,
+                to_segment: contained,
+                to_orientation: contained_orientation,
+                overlap: Some(overlap),
             },
             raw,
             n,
@@ -62,27 +76,25 @@ impl Containment {
         let containment = containment_as_bridge.unwrap();
 
         // check if position is a valid integer
-        let position = match parts[5].parse() {
+        let position = match pos.parse() {
             Ok(p) => p,
             Err(_) => {
                 errors.push(ParseMessage::new(
                     n,
                     ParseMessageCode::InvalidPosition,
-                    parts[5].to_owned(),
+                    pos.to_owned(),
                 ));
                 0 // default to 0
             }
         };
 
-        if let Some(container_segment) = gfa.find_segment_with_name(parts[1]) {
-            if position < 0 || position > container_segment.get_length() {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidPosition,
-                    parts[5].to_owned(),
-                ));
-            }
-        }
+        errors.extend(Self::check_position(
+            gfa,
+            &containment.from_segment,
+            position,
+            overlap,
+            n,
+        ));
 
         (
             Some(Self {
@@ -90,34 +102,78 @@ impl Containment {
                 raw: raw.to_owned(),
                 tags: map.clone(),
 
-                container: containment.from_segment,
+                container: gfa.intern(&containment.from_segment),
                 container_orientation: containment.from_orientation,
-                contained: containment.to_segment,
+                contained: gfa.intern(&containment.to_segment),
                 contained_orientation: containment.to_orientation,
                 position,
-                overlap: parts[6].to_owned(),
+                overlap: overlap.to_owned(),
             }),
             errors,
         )
     }
 
-    pub fn to_raw_line(&self, _: GFAVersion) -> String {
-        self.to_raw_line_v1()
+    // the contained region has to start and end within the container. shared by parsed
+    // lines and by records added with GfaParser::add_record
+    pub(crate) fn check_position(
+        gfa: &GfaParser,
+        container: &str,
+        position: i32,
+        overlap: &str,
+        n: usize,
+    ) -> Vec<ParseMessage> {
+        let mut errors = vec![];
+
+        let Some(container_segment) = gfa
+            .segment_record_index(container)
+            .and_then(|idx| gfa.records[idx].as_segment())
+        else {
+            return errors;
+        };
+
+        let container_length = container_segment.get_length();
+
+        if position < 0 || position > container_length {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::InvalidPosition,
+                position.to_string(),
+            ));
+        } else if !container_segment.is_ghost()
+            && let Some(overlap_length) = cigar_reference_length(overlap)
+            && i64::from(position) + i64::from(overlap_length) > i64::from(container_length)
+        {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::InvalidContainmentPositionRange,
+                format!("{position} + {overlap}"),
+            ));
+        }
+
+        errors
     }
 
-    fn to_raw_line_v1(&self) -> String {
+    pub fn to_raw_line(&self, _: GFAVersion, gfa: &GfaParser) -> String {
+        self.to_raw_line_v1(gfa)
+    }
+
+    fn to_raw_line_v1(&self, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(gfa.resolve(self.container), gfa.resolve(self.contained))
+    }
+
+    // the segment names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, container: &str, contained: &str) -> String {
         build_gfa_line(
             'C',
             &[
-                self.container.as_str(),
+                container,
                 if self.container_orientation { "+" } else { "-" },
-                self.contained.as_str(),
+                contained,
                 if self.contained_orientation { "+" } else { "-" },
                 &self.position.to_string(),
                 &self.overlap,
-            ], 
-            &self.tags
+            ],
+            &self.tags,
         )
     }
 }
-
@@ -1,19 +1,25 @@
+use crate::errors::ParseMessage;
 use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
-use crate::gfa::ParseOptions;
-use crate::errors::ParseMessage;
 use crate::gfa::GfaParser;
-use crate::line::bridge::parse_generic_bridge;
+use crate::gfa::ParseOptions;
 use crate::line::bridge::BridgeParts;
 use crate::line::bridge::BridgeType;
-use crate::line::utils::build_gfa_line;
+use crate::line::bridge::parse_generic_bridge;
+use crate::line::symbol::Symbol;
 use crate::line::utils::Alignment;
 use crate::line::utils::DirectedReference;
 use crate::line::utils::Interval;
+use crate::line::utils::build_gfa_line;
+use crate::line::utils::check_trace_spacing;
+use crate::line::utils::cigar_query_length;
+use crate::line::utils::cigar_reference_length;
 use crate::line::utils::deduce_alignment;
 use crate::line::utils::is_valid_name;
 use crate::line::utils::parse_directed_reference;
 use crate::line::utils::parse_interval;
+use crate::line::utils::parse_position;
+use crate::line::utils::required_columns;
 use crate::optional_field::OptionalFieldValue;
 use crate::optional_field::TagMap;
 
@@ -24,8 +30,10 @@ pub struct Edge {
     pub tags: TagMap,
 
     pub id: Option<String>,
-    pub from: DirectedReference,
-    pub to: DirectedReference,
+    pub from_segment: Symbol,
+    pub from_orientation: bool,
+    pub to_segment: Symbol,
+    pub to_orientation: bool,
     pub from_interval: Interval,
     pub to_interval: Interval,
     pub alignment: Option<Alignment>,
@@ -33,6 +41,12 @@ pub struct Edge {
 
 pub static REQ_COLUMNS_EDGE: usize = 9;
 
+/// Tags that carry the intervals of an edge written as a v1 link, as `begin..end`
+/// (e.g. `fi:Z:6..8$`). Together with the `edge` flag and the `TS` tag for trace
+/// alignments, they let the link be turned back into the same edge.
+pub static FROM_INTERVAL_TAG: &str = "fi";
+pub static TO_INTERVAL_TAG: &str = "ti";
+
 impl Edge {
     pub fn parse_line(
         (gfa, parts, raw, n, map, options): (
@@ -44,7 +58,13 @@ impl Edge {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
-        let from = match parse_directed_reference(parts[2]) {
+        let [eid, sid1, sid2, beg1, end1, beg2, end2, alignment] = match required_columns(parts, n)
+        {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
+        let from = match parse_directed_reference(sid1) {
             Ok(f) => f,
             Err(mut e) => {
                 e.line = n;
@@ -52,7 +72,7 @@ impl Edge {
             }
         };
 
-        let to = match parse_directed_reference(parts[3]) {
+        let to = match parse_directed_reference(sid2) {
             Ok(t) => t,
             Err(mut e) => {
                 e.line = n;
@@ -97,14 +117,14 @@ impl Edge {
         let mut edge_id;
 
         if map.contains("ID") {
-            if parts[1] != "*" {
+            if eid != "*" {
                 errors.push(ParseMessage::new(
                     n,
                     ParseMessageCode::RedundantEdgeIDTag,
                     map.get::<String>("ID").unwrap().to_owned(),
                 ));
 
-                edge_id = Some(parts[1].to_owned());
+                edge_id = Some(eid.to_owned());
             } else {
                 // very silly scenario that's easy enough to handle
                 errors.push(ParseMessage::new(
@@ -115,10 +135,10 @@ impl Edge {
                 edge_id = map.get::<String>("ID");
             }
         } else {
-            edge_id = if parts[1] == "*" {
+            edge_id = if eid == "*" {
                 None
             } else {
-                Some(parts[1].to_owned())
+                Some(eid.to_owned())
             };
         }
 
@@ -127,20 +147,22 @@ impl Edge {
                 if is_valid_name(&id) {
                     return Some(id);
                 }
-                errors.push(ParseMessage::new(n, ParseMessageCode::InvalidID, id.to_owned()));
+                errors.push(ParseMessage::new(
+                    n,
+                    ParseMessageCode::InvalidID,
+                    id.to_owned(),
+                ));
                 None
             })
-            .and_then(|id| Some(gfa.ensure_name_unique(n, id.clone())))
-            .and_then(|id| Some(id.clone()));
+            .map(|id| gfa.ensure_name_unique(n, id));
 
         let from_segment = gfa.find_segment_with_name(&from.reference);
 
-        let from_interval =
-            parse_interval(n, &mut errors, from_segment.as_deref(), parts[4], parts[5]);
+        let from_interval = parse_interval(n, &mut errors, from_segment.as_deref(), beg1, end1);
 
         let to_segment = gfa.find_segment_with_name(&to.reference);
 
-        let to_interval = parse_interval(n, &mut errors, to_segment.as_deref(), parts[6], parts[7]);
+        let to_interval = parse_interval(n, &mut errors, to_segment.as_deref(), beg2, end2);
 
         if from_interval.is_err() || to_interval.is_err() {
             // same policy as fragment for now, skip the record if any of the positions were invalid
@@ -150,12 +172,20 @@ impl Edge {
         let from_interval = from_interval.unwrap();
         let to_interval = to_interval.unwrap();
 
-        let alignment = deduce_alignment(parts[8]).unwrap_or_else(|mut e| {
+        let alignment = deduce_alignment(alignment).unwrap_or_else(|mut e| {
             e.line = n;
             errors.push(e);
             None
         });
 
+        errors.extend(Self::check_alignment(
+            gfa,
+            &from_interval,
+            &to_interval,
+            alignment.as_ref(),
+            n,
+        ));
+
         (
             Some(Self {
                 line_no: n,
@@ -163,8 +193,10 @@ impl Edge {
                 tags: map.clone(),
 
                 id: edge_id,
-                from,
-                to,
+                from_segment: gfa.intern(&from.reference),
+                from_orientation: from.direction,
+                to_segment: gfa.intern(&to.reference),
+                to_orientation: to.direction,
                 from_interval,
                 to_interval,
                 alignment,
@@ -173,35 +205,49 @@ impl Edge {
         )
     }
 
-    pub fn to_raw_line(&self, version: GFAVersion) -> String {
+    /// Length of the overlap on the from segment, i.e. the length of `from_interval`.
+    /// Returns `None` if the interval ends before it begins.
+    pub fn overlap_len(&self) -> Option<i32> {
+        let length = self.from_interval.end.position - self.from_interval.begin.position;
+        (length >= 0).then_some(length)
+    }
+
+    pub fn to_raw_line(&self, version: GFAVersion, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(
+            version,
+            gfa.resolve(self.from_segment),
+            gfa.resolve(self.to_segment),
+        )
+    }
+
+    // the segment names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, version: GFAVersion, from: &str, to: &str) -> String {
         match version {
-            GFAVersion::V2 => self.to_raw_line_v2(),
-            _ => self.to_raw_line_v1(),
+            GFAVersion::V2 => self.to_raw_line_v2(from, to),
+            _ => self.to_raw_line_v1(from, to),
         }
     }
 
-    fn to_raw_line_v1(&self) -> String {
+    fn to_raw_line_v1(&self, from: &str, to: &str) -> String {
         let mut new_tags = self.tags.clone();
 
         if self.id.is_some() && !new_tags.contains("ID") {
-            new_tags.0.insert(
+            new_tags.tags.insert(
                 "ID".to_string(),
                 OptionalFieldValue::String(self.id.clone().unwrap()),
             );
         }
 
-        let overlap = if self.alignment.is_some() {
-            match self.alignment.as_ref().unwrap() {
+        let overlap = if let Some(alignment) = &self.alignment {
+            match alignment {
                 Alignment::Trace(trace) => {
-                    new_tags.0.insert(
+                    new_tags.tags.insert(
                         "TS".to_string(),
                         OptionalFieldValue::String(trace.to_string()),
                     );
                     "*".to_string()
                 }
-                Alignment::CIGAR(cigar) => {
-                    cigar.to_string()
-                }
+                Alignment::CIGAR(cigar) => cigar.to_string(),
             }
         } else {
             "*".to_string()
@@ -209,39 +255,163 @@ impl Edge {
 
         // this link was an edge in another life
         new_tags.add_flag("edge");
+        Self::stash_intervals(&self.from_interval, &self.to_interval, &mut new_tags);
 
         // jumps only exist in v1.2, use a link for v1.0
         let columns = [
-            self.from.reference.as_str(),
-            if self.from.direction { "+" } else { "-"},
-            self.to.reference.as_str(),
-            if self.to.direction { "+" } else { "-" },
+            from,
+            if self.from_orientation { "+" } else { "-" },
+            to,
+            if self.to_orientation { "+" } else { "-" },
             overlap.as_str(),
         ];
 
-        // converting to a link loses the alignment information
-        // might be worth revisiting this in the future
-        // to see if i can preserve it with containments/etc.
-
         build_gfa_line('L', &columns, &new_tags)
     }
 
-    fn to_raw_line_v2(&self) -> String {
+    // a trace needs a trace spacing, and without an alignment the intervals can only be
+    // matched base for base. shared by parsed lines and by records added with
+    // GfaParser::add_record
+    pub(crate) fn check_alignment(
+        gfa: &GfaParser,
+        from_interval: &Interval,
+        to_interval: &Interval,
+        alignment: Option<&Alignment>,
+        n: usize,
+    ) -> Vec<ParseMessage> {
+        let mut errors: Vec<_> = check_trace_spacing(gfa, alignment, n).into_iter().collect();
+
+        let lengths = [from_interval, to_interval].map(|i| i.end.position - i.begin.position);
+
+        match alignment {
+            None if lengths[0] != lengths[1] => errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::EdgeIntervalLengthMismatch,
+                format!("{} vs {}", lengths[0], lengths[1]),
+            )),
+            // the from interval is the reference of the CIGAR, the to interval the query
+            Some(Alignment::CIGAR(cigar)) => {
+                let covered = [cigar_reference_length(cigar), cigar_query_length(cigar)];
+
+                if let [Some(reference), Some(query)] = covered
+                    && [reference as i32, query as i32] != lengths
+                {
+                    errors.push(ParseMessage::new(
+                        n,
+                        ParseMessageCode::EdgeIntervalLengthMismatch,
+                        format!(
+                            "{} vs {}, {cigar} covers {reference} vs {query}",
+                            lengths[0], lengths[1]
+                        ),
+                    ));
+                }
+            }
+            _ => {}
+        }
+
+        errors
+    }
+
+    pub(crate) fn stash_intervals(
+        from_interval: &Interval,
+        to_interval: &Interval,
+        tags: &mut TagMap,
+    ) {
+        tags.add_tag(
+            FROM_INTERVAL_TAG,
+            OptionalFieldValue::String(from_interval.to_string()),
+        );
+        tags.add_tag(
+            TO_INTERVAL_TAG,
+            OptionalFieldValue::String(to_interval.to_string()),
+        );
+    }
+
+    /// Removes the interval tags written by a v1 conversion and returns the
+    /// (from, to) intervals, or `None` if either is missing or invalid.
+    pub(crate) fn take_stashed_intervals(tags: &mut TagMap) -> Option<(Interval, Interval)> {
+        let parse = |value: Option<String>| -> Option<Interval> {
+            let value = value?;
+            let (begin, end) = value.split_once("..")?;
+            let mut errors = vec![];
+
+            Some(Interval {
+                begin: parse_position(0, &mut errors, begin).ok()?,
+                end: parse_position(0, &mut errors, end).ok()?,
+            })
+        };
+
+        let from = parse(tags.get::<String>(FROM_INTERVAL_TAG));
+        let to = parse(tags.get::<String>(TO_INTERVAL_TAG));
+
+        tags.remove_tag(FROM_INTERVAL_TAG);
+        tags.remove_tag(TO_INTERVAL_TAG);
+
+        Some((from?, to?))
+    }
+
+    fn to_raw_line_v2(&self, from: &str, to: &str) -> String {
+        let from = DirectedReference {
+            reference: from.to_string(),
+            direction: self.from_orientation,
+        };
+        let to = DirectedReference {
+            reference: to.to_string(),
+            direction: self.to_orientation,
+        };
+
         build_gfa_line(
             'E',
             &[
                 self.id.as_deref().unwrap_or("*"),
-                self.from.to_string().as_str(),
-                self.to.to_string().as_str(),
+                from.to_string().as_str(),
+                to.to_string().as_str(),
                 &self.from_interval.begin.to_string(),
                 &self.from_interval.end.to_string(),
                 &self.to_interval.begin.to_string(),
                 &self.to_interval.end.to_string(),
-                &self.alignment.as_ref().map_or("*".to_string(), |a| a.to_string()),
-            ], 
-            &self.tags
+                &self
+                    .alignment
+                    .as_ref()
+                    .map_or("*".to_string(), |a| a.to_string()),
+            ],
+            &self.tags,
         )
     }
-
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::errors::ParseMessageCode;
+    use crate::gfa::{GfaParser, ParseOptions};
+
+    #[test]
+    fn interval_lengths() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        let lines = [
+            "H\tVN:Z:2.0",
+            "S\ta\t8\tACGTACGT",
+            "S\tb\t8\tACGTACGT",
+            "E\te1\ta+\tb+\t4\t8$\t0\t4\t*",
+            "E\te2\ta+\tb+\t4\t8$\t0\t3\t*",
+            "E\te3\ta+\tb+\t4\t8$\t0\t3\t3M1D",
+            "E\te4\ta+\tb+\t4\t8$\t0\t4\t2M",
+        ];
+        for line in lines {
+            let _ = gfa.add_line(line, &options);
+        }
+
+        let overlaps: Vec<_> = gfa.edges().map(|e| e.overlap_len()).collect();
+        assert_eq!(overlaps, [Some(4), Some(4), Some(4), Some(4)]);
+
+        let mismatches: Vec<_> = gfa
+            .messages
+            .iter()
+            .filter(|m| m.code == ParseMessageCode::EdgeIntervalLengthMismatch)
+            .map(|m| (m.line, m.offender.as_str()))
+            .collect();
+        assert_eq!(mismatches, [(5, "4 vs 3"), (7, "4 vs 4, 2M covers 2 vs 2")]);
+    }
+}
@@ -3,14 +3,19 @@ use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::MissingSegmentOptions;
-use crate::line::utils::build_gfa_line;
-use crate::line::utils::parse_interval;
-use crate::line::utils::Interval;
-use crate::optional_field::TagMap;
+use crate::line::containment::Containment;
+use crate::line::symbol::Symbol;
 use crate::line::utils::Alignment;
 use crate::line::utils::DirectedReference;
+use crate::line::utils::Interval;
+use crate::line::utils::build_gfa_line;
+use crate::line::utils::check_trace_spacing;
 use crate::line::utils::deduce_alignment;
 use crate::line::utils::parse_directed_reference;
+use crate::line::utils::parse_interval;
+use crate::line::utils::required_columns;
+use crate::optional_field::OptionalFieldValue;
+use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
 pub struct Fragment {
@@ -38,16 +43,30 @@ impl Fragment {
             &crate::gfa::ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
+        let [
+            segment_name,
+            external_ref,
+            sbeg,
+            send,
+            fbeg,
+            fend,
+            alignment,
+        ] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
         let mut errors = vec![];
 
-        // check if segment exists
-        let segment = gfa.find_segment_with_name(parts[1]);
-        
+        // check if segment exists, the fragment points at the ghost in its place otherwise
+        let mut segment_name = segment_name.to_owned();
+        let segment = gfa.find_segment_with_name(&segment_name);
+
         if segment.is_none() {
             errors.push(ParseMessage::new(
                 n,
                 ParseMessageCode::SegmentNotFound,
-                parts[1].to_owned(),
+                segment_name.to_owned(),
             ));
 
             match options.handle_missing_segment {
@@ -56,33 +75,36 @@ impl Fragment {
                     return (None, errors);
                 }
                 MissingSegmentOptions::CreateGhost => {
-                    // check to see if the missing name is valid
-                    let _ = &gfa.create_ghost_segment(parts[1].to_owned());
+                    let idx = gfa.ghost_for(&segment_name, options);
+                    if let Some(ghost) = gfa.records[idx].as_segment() {
+                        segment_name = ghost.name.clone();
+                    }
                 }
             }
         }
 
         // add the fragment to the segment
-        if let Some(s) = gfa.find_segment_with_name(parts[1]) {
+        if let Some(s) = gfa.find_segment_with_name(&segment_name) {
             s.fragments.push(n);
         }
 
-        let referenced_segment = gfa.find_segment_with_name(parts[1]);        
+        let referenced_segment = gfa.find_segment_with_name(&segment_name);
 
         // check if external reference is valid
-        let external = parse_directed_reference(parts[2]).unwrap_or_else(|mut e| {
+        let external = parse_directed_reference(external_ref).unwrap_or_else(|mut e| {
             e.line = n;
             errors.push(e);
 
             // default to the reference name in forward ori
             DirectedReference {
-                reference: parts[1].to_owned(),
-                direction: true
+                reference: segment_name.to_owned(),
+                direction: true,
             }
         });
-        
-        let segment_interval = parse_interval(n, &mut errors, referenced_segment.as_deref(), parts[3], parts[4]);
-        let fragment_interval = parse_interval(n, &mut errors, None, parts[5], parts[6]);
+
+        let segment_interval =
+            parse_interval(n, &mut errors, referenced_segment.as_deref(), sbeg, send);
+        let fragment_interval = parse_interval(n, &mut errors, None, fbeg, fend);
 
         if segment_interval.is_err() || fragment_interval.is_err() {
             // if any of the fragment positions were invalid, don't even
@@ -100,19 +122,21 @@ impl Fragment {
 
         // check if alignment is valid
 
-        let alignment = deduce_alignment(parts[7]).unwrap_or_else(|mut e| {
+        let alignment = deduce_alignment(alignment).unwrap_or_else(|mut e| {
             e.line = n;
             errors.push(e);
             None
         });
 
+        errors.extend(check_trace_spacing(gfa, alignment.as_ref(), n));
+
         (
             Some(Self {
                 line_no: n,
                 raw: raw.to_owned(),
                 tags: map.clone(),
 
-                segment_name: parts[1].to_owned(),
+                segment_name: segment_name.to_owned(),
                 external_name: external,
                 segment_interval,
                 fragment_interval,
@@ -129,15 +153,46 @@ impl Fragment {
         }
     }
 
+    // the external sequence isn't written as a segment here, GfaParser::to_gfa_string
+    // writes one if the graph has none, and GfaParser::convert_to adds one to the graph
     fn to_raw_line_v1(&self) -> String {
-        // fragments are a unique v2 concept, that can't be abstracted
-        // to a bridge (since fragments exist outside of the gfa file)
-        
-        // maybe this could be another segment + a containment line?
-        
-        // if you have a use case for converting fragments to v1,
-        // please open an issue about this
-        "".to_string()
+        self.to_containment(Symbol::default(), Symbol::default())
+            .to_raw_line_with(&self.segment_name, &self.external_name.reference)
+    }
+
+    /// The containment this fragment becomes in GFA 1, given the symbols of its segment
+    /// and of the external sequence.
+    ///
+    /// The external sequence is contained in the segment, in its direction, at the start
+    /// of the segment interval. A CIGAR alignment becomes the overlap, and a trace the `TS`
+    /// tag. This is lossy: the fragment interval is dropped, and the external sequence
+    /// becomes a node of the graph (see [`GfaParser::convert_to`]).
+    pub fn to_containment(&self, segment: Symbol, external: Symbol) -> Containment {
+        let mut tags = self.tags.clone();
+
+        let overlap = match &self.alignment {
+            Some(Alignment::CIGAR(cigar)) => cigar.clone(),
+            Some(Alignment::Trace(trace)) => {
+                tags.add_tag("TS", OptionalFieldValue::String(trace.clone()));
+                "*".to_string()
+            }
+            None => "*".to_string(),
+        };
+
+        // this containment was a fragment in another life
+        tags.add_flag("fragment");
+
+        Containment {
+            line_no: self.line_no,
+            raw: self.raw.clone(),
+            tags,
+            container: segment,
+            container_orientation: true,
+            contained: external,
+            contained_orientation: self.external_name.direction,
+            position: self.segment_interval.begin.position,
+            overlap,
+        }
     }
 
     fn to_raw_line_v2(&self) -> String {
@@ -150,15 +205,16 @@ impl Fragment {
                 &self.segment_interval.end.to_string(),
                 &self.fragment_interval.begin.to_string(),
                 &self.fragment_interval.end.to_string(),
-                &self.alignment.as_ref().map_or("*".to_string(), |a| a.to_string()),
-            ], 
-            &self.tags
+                &self
+                    .alignment
+                    .as_ref()
+                    .map_or("*".to_string(), |a| a.to_string()),
+            ],
+            &self.tags,
         )
     }
 }
 
-
-
 #[cfg(test)]
 mod tests {
     use crate::gfa::GfaParser;
@@ -177,4 +233,32 @@ mod tests {
         // TODO: write real tests
         assert_eq!(gfa.messages.len(), 20);
     }
+
+    #[test]
+    fn trace_alignment_needs_trace_spacing() {
+        use crate::errors::ParseMessageCode;
+
+        let missing_spacing = |header: &str| {
+            let mut gfa = GfaParser::new();
+            let options = ParseOptions::default();
+
+            for line in [header, "S\t1\t4\tACGT", "F\t1\text+\t0\t4$\t0\t4\t2,2"] {
+                let _ = gfa.add_line(line, &options);
+            }
+
+            let fragment = gfa.fragments().next().unwrap();
+            assert_eq!(
+                fragment.alignment.as_ref().unwrap().trace_points(),
+                Some(vec![2, 2])
+            );
+
+            gfa.messages
+                .iter()
+                .filter(|m| m.code == ParseMessageCode::MissingTraceSpacing)
+                .count()
+        };
+
+        assert_eq!(missing_spacing("H\tVN:Z:2.0"), 1);
+        assert_eq!(missing_spacing("H\tVN:Z:2.0\tTS:i:100"), 0);
+    }
 }
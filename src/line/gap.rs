@@ -3,13 +3,15 @@ use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
-use crate::line::bridge::parse_generic_bridge;
 use crate::line::bridge::BridgeParts;
 use crate::line::bridge::BridgeType;
+use crate::line::bridge::parse_generic_bridge;
+use crate::line::symbol::Symbol;
 use crate::line::utils::DirectedReference;
 use crate::line::utils::build_gfa_line;
 use crate::line::utils::is_valid_name;
 use crate::line::utils::parse_directed_reference;
+use crate::line::utils::required_columns;
 use crate::optional_field::OptionalFieldValue;
 use crate::optional_field::TagMap;
 
@@ -20,8 +22,10 @@ pub struct Gap {
     pub tags: TagMap,
 
     pub id: Option<String>,
-    pub from: DirectedReference,
-    pub to: DirectedReference,
+    pub from_segment: Symbol,
+    pub from_orientation: bool,
+    pub to_segment: Symbol,
+    pub to_orientation: bool,
     pub distance: i32,
     pub variance: Option<i32>,
 }
@@ -39,7 +43,12 @@ impl Gap {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
-        let from = match parse_directed_reference(parts[2]) {
+        let [gid, sid1, sid2, dist, var] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
+        let from = match parse_directed_reference(sid1) {
             Ok(f) => f,
             Err(mut e) => {
                 e.line = n;
@@ -47,7 +56,7 @@ impl Gap {
             }
         };
 
-        let to = match parse_directed_reference(parts[3]) {
+        let to = match parse_directed_reference(sid2) {
             Ok(t) => t,
             Err(mut e) => {
                 e.line = n;
@@ -82,27 +91,17 @@ impl Gap {
 
         let gap = gap_as_bridge.unwrap();
 
-        let from = DirectedReference {
-            reference: gap.from_segment,
-            direction: gap.from_orientation,
-        };
-
-        let to = DirectedReference {
-            reference: gap.to_segment,
-            direction: gap.to_orientation,
-        };
-
         let mut gap_id;
 
         if map.contains("ID") {
-            if parts[1] != "*" {
+            if gid != "*" {
                 errors.push(ParseMessage::new(
                     n,
                     ParseMessageCode::RedundantEdgeIDTag,
                     map.get::<String>("ID").unwrap().to_owned(),
                 ));
 
-                gap_id = Some(parts[1].to_owned());
+                gap_id = Some(gid.to_owned());
             } else {
                 errors.push(ParseMessage::new(
                     n,
@@ -112,10 +111,10 @@ impl Gap {
                 gap_id = map.get::<String>("ID");
             }
         } else {
-            gap_id = if parts[1] == "*" {
+            gap_id = if gid == "*" {
                 None
             } else {
-                Some(parts[1].to_owned())
+                Some(gid.to_owned())
             };
         }
 
@@ -124,24 +123,27 @@ impl Gap {
                 if is_valid_name(&id) {
                     return Some(id);
                 }
-                errors.push(ParseMessage::new(n, ParseMessageCode::InvalidID, id.to_owned()));
+                errors.push(ParseMessage::new(
+                    n,
+                    ParseMessageCode::InvalidID,
+                    id.to_owned(),
+                ));
                 None
             })
-            .and_then(|id| Some(gfa.ensure_name_unique(n, id.clone())))
-            .and_then(|id| Some(id.clone()));
+            .map(|id| gfa.ensure_name_unique(n, id));
 
-        let distance = parts[4]
+        let distance = dist
             .parse::<i32>()
             .map_err(|_| {
                 errors.push(ParseMessage::new(
                     n,
                     ParseMessageCode::InvalidGapDistance,
-                    parts[4].to_string(),
+                    dist.to_string(),
                 ));
             })
             .unwrap_or(0);
 
-        let variance = match parts[5] {
+        let variance = match var {
             "*" => None,
             s => s.parse::<i32>().map(Some).unwrap_or_else(|_| {
                 errors.push(ParseMessage::new(
@@ -160,8 +162,10 @@ impl Gap {
                 tags: map.clone(),
 
                 id: gap_id,
-                from,
-                to,
+                from_segment: gfa.intern(&gap.from_segment),
+                from_orientation: gap.from_orientation,
+                to_segment: gfa.intern(&gap.to_segment),
+                to_orientation: gap.to_orientation,
                 distance,
                 variance,
             }),
@@ -169,29 +173,39 @@ impl Gap {
         )
     }
 
-    pub fn to_raw_line(&self, version: GFAVersion) -> String {
+    pub fn to_raw_line(&self, version: GFAVersion, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(
+            version,
+            gfa.resolve(self.from_segment),
+            gfa.resolve(self.to_segment),
+        )
+    }
+
+    // the segment names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, version: GFAVersion, from: &str, to: &str) -> String {
         match version {
-            GFAVersion::V2 => self.to_raw_line_v2(),
-            GFAVersion::V1_2 => self.to_raw_line_v1(false),
-            _ => self.to_raw_line_v1(true),
+            GFAVersion::V2 => self.to_raw_line_v2(from, to),
+            GFAVersion::V1_2 => self.to_raw_line_v1(false, from, to),
+            _ => self.to_raw_line_v1(true, from, to),
         }
     }
 
-    fn to_raw_line_v1(&self, is_v1_0: bool) -> String {
+    fn to_raw_line_v1(&self, is_v1_0: bool, from: &str, to: &str) -> String {
         let mut new_tags = self.tags.clone();
 
         if self.id.is_some() && !new_tags.contains("ID") {
-            new_tags.0.insert(
+            new_tags.tags.insert(
                 "ID".to_string(),
                 OptionalFieldValue::String(self.id.clone().unwrap()),
             );
         }
 
-        if self.variance.is_some() && !new_tags.contains("VA") {
-            new_tags.0.insert(
-                "VA".to_string(),
-                OptionalFieldValue::Int(self.variance.unwrap()),
-            );
+        if let Some(variance) = self.variance
+            && !new_tags.contains("VA")
+        {
+            new_tags
+                .tags
+                .insert("VA".to_string(), OptionalFieldValue::Int(variance));
         }
 
         // this link/jump was a gap in another life
@@ -201,10 +215,9 @@ impl Gap {
         let record_type = if is_v1_0 { 'L' } else { 'J' };
         let fifth_column = if is_v1_0 {
             if !new_tags.contains("VA") {
-                new_tags.0.insert(
-                    "DI".to_string(),
-                    OptionalFieldValue::Int(self.distance),
-                );
+                new_tags
+                    .tags
+                    .insert("DI".to_string(), OptionalFieldValue::Int(self.distance));
             }
 
             "*".to_string()
@@ -213,23 +226,32 @@ impl Gap {
         };
 
         let columns = [
-            self.from.reference.as_str(),
-            if self.from.direction { "+" } else { "-" },
-            self.to.reference.as_str(),
-            if self.to.direction { "+" } else { "-" },
+            from,
+            if self.from_orientation { "+" } else { "-" },
+            to,
+            if self.to_orientation { "+" } else { "-" },
             fifth_column.as_str(),
         ];
 
         build_gfa_line(record_type, &columns, &new_tags)
     }
 
-    fn to_raw_line_v2(&self) -> String {
+    fn to_raw_line_v2(&self, from: &str, to: &str) -> String {
+        let from = DirectedReference {
+            reference: from.to_string(),
+            direction: self.from_orientation,
+        };
+        let to = DirectedReference {
+            reference: to.to_string(),
+            direction: self.to_orientation,
+        };
+
         build_gfa_line(
             'G',
             &[
                 self.id.as_deref().unwrap_or("*"),
-                self.from.to_string().as_str(),
-                self.to.to_string().as_str(),
+                from.to_string().as_str(),
+                to.to_string().as_str(),
                 self.distance.to_string().as_str(),
                 &self.variance.map_or("*".to_string(), |v| v.to_string()),
             ],
@@ -237,4 +259,3 @@ impl Gap {
         )
     }
 }
-
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+
 use crate::errors::ParseMessage;
 use crate::errors::ParseMessageCode;
 use crate::gfa::GfaParser;
 use crate::gfa::MissingSegmentOptions;
 use crate::gfa::ParseOptions;
+use crate::line::record::GfaRecord;
 
 #[derive(Debug, Clone)]
 pub struct GenericGroup {
@@ -28,57 +31,110 @@ pub fn parse_generic_group(
     n: usize,
     options: &ParseOptions,
 ) -> (Option<GenericGroup>, Vec<ParseMessage>) {
-        let mut errors = vec![];
+    let mut errors = vec![];
 
-        let group_type = parts.group_type;
+    let group_type = parts.group_type;
 
-        let name = if parts.name != "*" {
-            &gfa.ensure_name_unique(n, parts.name.to_string())
+    let name = if parts.name != "*" {
+        &gfa.ensure_name_unique(n, parts.name.to_string())
+    } else {
+        let group_type_str = if matches!(group_type, GroupType::OrderedGroup) {
+            "O"
         } else {
-            let group_type_str = if matches!(group_type, GroupType::OrderedGroup) {
-                "O"
-            } else {
-                "U"
-            };
-
-            let new_name = format!("anon_{group_type_str}_{n}");
-            &gfa.ensure_name_unique(n, new_name)
+            "U"
         };
 
-        let mut members = vec![];
-        let members_str = parts.members.split(" ").collect::<Vec<&str>>();
+        let new_name = format!("{}{group_type_str}_{n}", options.anon_group_prefix);
+        &gfa.ensure_name_unique(n, new_name)
+    };
+
+    let mut members = vec![];
+    let members_str = parts.members.split(" ").collect::<Vec<&str>>();
 
-        // check if every group member exists in the GFA file
-        for member in members_str {
-            let member_name = member.trim_end_matches(['+', '-']);
-            let record_exists = gfa.is_name_in_namespace(member_name);
+    // check if every group member exists in the GFA file
+    for member in members_str {
+        let member_name = member.trim_end_matches(['+', '-']);
+        let record_exists = gfa.is_name_in_namespace(member_name);
 
-            if !record_exists {
+        if !record_exists {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::GroupMemberNotFound,
+                member.to_owned(),
+            ));
+
+            if options.handle_missing_segment == MissingSegmentOptions::HardSkip {
                 errors.push(ParseMessage::new(
                     n,
-                    ParseMessageCode::GroupMemberNotFound,
+                    ParseMessageCode::InvalidGroup,
                     member.to_owned(),
                 ));
 
-                if options.handle_missing_segment == MissingSegmentOptions::HardSkip {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::InvalidGroup,
-                        member.to_owned(),
-                    ));
+                return (None, errors);
+            }
+        }
+
+        members.push(member.to_owned());
+    }
+
+    (
+        Some(GenericGroup {
+            name: name.to_string(),
+            members,
+        }),
+        errors,
+    )
+}
+
+/// Resolves group members to the segments they stand for. Segments are kept as they
+/// are, paths contribute the segments of their steps and groups are expanded
+/// recursively. Every segment appears once, in the order it is first reached.
+///
+/// `visited` holds the names of the groups that were already expanded, so a group
+/// that (indirectly) contains itself is only expanded once. Members that are neither
+/// segments, paths nor groups (edges, or names that don't exist) are skipped.
+pub(crate) fn expand_members(
+    gfa: &GfaParser,
+    members: &[String],
+    visited: &mut HashSet<String>,
+    segments: &mut Vec<String>,
+) {
+    for member in members {
+        let name = member.trim_end_matches(['+', '-']);
 
-                    return (None, errors);
+        match gfa.find_record_with_name(name) {
+            Some(GfaRecord::Segment(s)) => push_unique(segments, &s.name),
+            Some(GfaRecord::Path(p)) => {
+                for step in &p.steps {
+                    if let Some(s) = gfa.find_segment(step.segment_id as usize) {
+                        push_unique(segments, &s.name);
+                    }
                 }
             }
-
-            members.push(member.to_owned());
+            Some(GfaRecord::UnorderedGroup(u)) if visited.insert(u.name.clone()) => {
+                expand_members(gfa, &u.members, visited, segments);
+            }
+            Some(GfaRecord::OrderedGroup(o)) if visited.insert(o.name.clone()) => {
+                expand_members(gfa, &o.members, visited, segments);
+            }
+            _ => {}
         }
+    }
+}
+
+fn push_unique(segments: &mut Vec<String>, name: &str) {
+    if !segments.iter().any(|s| s == name) {
+        segments.push(name.to_string());
+    }
+}
+
+/// Renames a group member in place, keeping its orientation suffix (if any).
+///
+/// `rename` maps the bare member name to its new name, or `None` to leave it as is.
+pub(crate) fn rename_member<'a>(member: &mut String, rename: impl Fn(&str) -> Option<&'a str>) {
+    let name = member.trim_end_matches(['+', '-']);
 
-        (
-            Some(GenericGroup {
-                name: name.to_string(),
-                members,
-            }),
-            errors,
-        )
+    if let Some(new_name) = rename(name) {
+        *member = format!("{new_name}{}", &member[name.len()..]);
+    }
 }
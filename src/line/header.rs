@@ -1,11 +1,11 @@
+use crate::errors::ParseMessage;
+use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
+use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
 use crate::line::record::GfaRecord;
 use crate::line::utils::build_gfa_line;
 use crate::optional_field::OptionalFieldValue;
-use crate::errors::ParseMessageCode;
-use crate::errors::ParseMessage;
-use crate::gfa::GfaParser;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
@@ -30,7 +30,14 @@ impl Header {
     }
 
     pub fn parse_line(
-        (gfa, _, raw, n, map , _): (&mut GfaParser, &[&str], &str, usize, &mut TagMap, &ParseOptions),
+        (gfa, _, raw, n, map, _): (
+            &mut GfaParser,
+            &[&str],
+            &str,
+            usize,
+            &mut TagMap,
+            &ParseOptions,
+        ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
         let mut errors = vec![];
 
@@ -53,7 +60,7 @@ impl Header {
         if map.contains("VN")
             && !map
                 .get::<String>("VN")
-                .map_or(false, |v| valid_versions.contains(&v.as_str()))
+                .is_some_and(|v| valid_versions.contains(&v.as_str()))
         {
             errors.push(ParseMessage::new(
                 n,
@@ -62,7 +69,7 @@ impl Header {
             ));
 
             // default VN to 1.0
-            map.0.insert(
+            map.tags.insert(
                 "VN".to_string(),
                 OptionalFieldValue::String("1.0".to_string()),
             );
@@ -79,7 +86,7 @@ impl Header {
 
             // default to 1.0 if VN is missing
             // TODO: infer version from file instead of defaulting
-            map.0.insert(
+            map.tags.insert(
                 "VN".to_string(),
                 OptionalFieldValue::String("1.0".to_string()),
             );
@@ -91,8 +98,9 @@ impl Header {
                 .expect("already ensured VN tag")
                 .into();
 
-            if map.contains("TS") {
-                gfa.trace = map.get::<String>("TS");
+            // TS is an integer, but keep whatever was written
+            if let Some(ts) = map.tags.get("TS") {
+                gfa.trace = Some(ts.to_string());
             }
         }
 
@@ -113,17 +121,12 @@ impl Header {
 
     fn to_raw_line_v1(&self, version: GFAVersion) -> String {
         let mut tag_clone: TagMap = self.tags.clone();
-        
-        tag_clone.0.insert(
+
+        tag_clone.tags.insert(
             "VN".to_string(),
             OptionalFieldValue::String(version.to_string()),
         );
 
-        build_gfa_line(
-            'H',
-            &[],
-            &tag_clone,
-        )
+        build_gfa_line('H', &[], &tag_clone)
     }
 }
-
@@ -3,10 +3,12 @@ use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
-use crate::line::bridge::parse_generic_bridge;
 use crate::line::bridge::BridgeParts;
 use crate::line::bridge::BridgeType;
+use crate::line::bridge::parse_generic_bridge;
+use crate::line::symbol::Symbol;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::required_columns;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
@@ -15,9 +17,9 @@ pub struct Jump {
     pub raw: String,
     pub tags: TagMap,
 
-    pub from_segment: String,
+    pub from_segment: Symbol,
     pub from_orientation: bool,
-    pub to_segment: String,
+    pub to_segment: Symbol,
     pub to_orientation: bool,
     pub distance: Option<i32>,
 }
@@ -35,15 +37,32 @@ impl Jump {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
-        let (jump_as_bridge, errors) =
-            parse_generic_bridge(gfa, BridgeParts{
+        let [
+            from_segment,
+            from_orientation,
+            to_segment,
+            to_orientation,
+            distance,
+        ] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
+        let (jump_as_bridge, errors) = parse_generic_bridge(
+            gfa,
+            BridgeParts {
                 bridge_type: BridgeType::Jump,
-                from_segment: parts[1],
-                from_orientation: parts[2],
-                to_segment: parts[3],
-                to_orientation: parts[4],
+                from_segment,
+                from_orientation,
+                to_segment,
+                to_orientation,
                 overlap: None,
-         }, raw, n, map, options);
+            },
+            raw,
+            n,
+            map,
+            options,
+        );
 
         if jump_as_bridge.is_none() {
             return (None, errors);
@@ -53,40 +72,29 @@ impl Jump {
 
         let jump = jump_as_bridge.unwrap();
 
-        if let Some(sc) = map.get::<i32>("SC") {
-            if sc != 0 && sc != 1 {
+        let distance = match distance {
+            "*" => None,
+            s => s.parse::<i32>().map(Some).unwrap_or_else(|_| {
                 errors.push(ParseMessage::new(
                     n,
-                    ParseMessageCode::InvalidShortcut,
-                    sc.to_string(),
+                    ParseMessageCode::InvalidJumpDistance,
+                    s.to_string(),
                 ));
-            }
-        }
-
-        let distance = match parts[5] {
-            "*" => None,
-            s => s
-                .parse::<i32>()
-                .map(Some)
-                .unwrap_or_else(|_| {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::InvalidJumpDistance,
-                        s.to_string(),
-                    ));
-                    None
-                }),
+                None
+            }),
         };
 
+        errors.extend(Self::check_shortcut(map, distance, n));
+
         (
             Some(Self {
                 line_no: n,
                 raw: raw.to_owned(),
                 tags: map.clone(),
 
-                from_segment: jump.from_segment,
+                from_segment: gfa.intern(&jump.from_segment),
                 from_orientation: jump.from_orientation,
-                to_segment: jump.to_segment,
+                to_segment: gfa.intern(&jump.to_segment),
                 to_orientation: jump.to_orientation,
                 distance,
             }),
@@ -94,26 +102,129 @@ impl Jump {
         )
     }
 
-    pub fn to_raw_line(&self, _: GFAVersion) -> String {
-        self.to_raw_line_v1()
+    // the SC tag must be 0 or 1, and a shortcut has no distance. shared by parsed lines
+    // and by records added with GfaParser::add_record
+    pub(crate) fn check_shortcut(
+        tags: &TagMap,
+        distance: Option<i32>,
+        n: usize,
+    ) -> Vec<ParseMessage> {
+        let mut errors = vec![];
+        let shortcut = tags.get::<i32>("SC");
+
+        if let Some(sc) = shortcut
+            && sc != 0
+            && sc != 1
+        {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::InvalidShortcut,
+                sc.to_string(),
+            ));
+        }
+
+        if shortcut == Some(1)
+            && let Some(d) = distance
+        {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::ShortcutJumpHasDistance,
+                d.to_string(),
+            ));
+        }
+
+        errors
+    }
+
+    /// True if the distance is `*`, i.e. the gap between the segments is not known.
+    pub fn is_unknown_distance(&self) -> bool {
+        self.distance.is_none()
+    }
+
+    /// True if the distance is exactly 0: the segments are known to be adjacent, but
+    /// (unlike a link) without an overlap. An unknown (`*`) distance is not adjacent.
+    pub fn is_adjacent(&self) -> bool {
+        self.distance == Some(0)
+    }
+
+    /// True if the jump has `SC:i:1`, marking it as a shortcut over segments of a path.
+    pub fn is_shortcut(&self) -> bool {
+        self.tags.get::<i32>("SC") == Some(1)
+    }
+
+    pub fn to_raw_line(&self, _: GFAVersion, gfa: &GfaParser) -> String {
+        self.to_raw_line_v1(gfa)
+    }
+
+    fn to_raw_line_v1(&self, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(gfa.resolve(self.from_segment), gfa.resolve(self.to_segment))
     }
 
-    fn to_raw_line_v1(&self) -> String {
+    // the segment names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, from_segment: &str, to_segment: &str) -> String {
         let columns = [
-            self.from_segment.as_str(),
+            from_segment,
             if self.from_orientation { "+" } else { "-" },
-            self.to_segment.as_str(),
+            to_segment,
             if self.to_orientation { "+" } else { "-" },
             &self.distance.map_or("*".to_string(), |d| d.to_string()),
         ];
 
         // build the GFA line
-        build_gfa_line(
-            'J',
-            &columns,
-            &self.tags,
-        )
+        build_gfa_line('J', &columns, &self.tags)
     }
-
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::errors::ParseMessageCode;
+    use crate::gfa::{GfaParser, ParseOptions};
+
+    #[test]
+    fn distance_semantics() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        let lines = [
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "J\ta\t+\tb\t+\t0",
+            "J\ta\t+\tb\t-\t*",
+        ];
+        for line in lines {
+            gfa.add_line(line, &options).unwrap();
+        }
+
+        let jumps: Vec<_> = gfa
+            .jumps()
+            .map(|j| (j.is_adjacent(), j.is_unknown_distance()))
+            .collect();
+        assert_eq!(jumps, [(true, false), (false, true)]);
+    }
+
+    #[test]
+    fn shortcut_with_distance() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        let lines = [
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "J\ta\t+\tb\t+\t*\tSC:i:1",
+            "J\ta\t-\tb\t+\t12\tSC:i:1",
+        ];
+        for line in lines {
+            let _ = gfa.add_line(line, &options);
+        }
+
+        assert!(gfa.jumps().all(|j| j.is_shortcut()));
+
+        let shortcuts: Vec<_> = gfa
+            .messages
+            .iter()
+            .filter(|m| m.code == ParseMessageCode::ShortcutJumpHasDistance)
+            .map(|m| (m.line, m.offender.as_str()))
+            .collect();
+        assert_eq!(shortcuts, [(4, "12")]);
+    }
+}
@@ -1,11 +1,18 @@
+use std::borrow::Cow;
+use std::cmp::Ordering;
+
 use crate::errors::ParseMessage;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
-use crate::line::bridge::parse_generic_bridge;
 use crate::line::bridge::BridgeParts;
 use crate::line::bridge::BridgeType;
+use crate::line::bridge::parse_generic_bridge;
+use crate::line::symbol::Symbol;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::parse_cigar;
+use crate::line::utils::required_columns;
+use crate::optional_field::OptionalFieldValue;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone)]
@@ -14,14 +21,13 @@ pub struct Link {
     pub raw: String,
     pub tags: TagMap,
 
-    pub from_segment: String,
+    pub from_segment: Symbol,
     pub from_orientation: bool,
-    pub to_segment: String,
+    pub to_segment: Symbol,
     pub to_orientation: bool,
     pub overlap: String,
 }
 
-
 impl Default for Link {
     fn default() -> Self {
         Self {
@@ -29,9 +35,9 @@ impl Default for Link {
             raw: "".to_string(),
             tags: TagMap::new(),
 
-            from_segment: "".to_string(),
+            from_segment: Symbol::default(),
             from_orientation: true,
-            to_segment: "".to_string(),
+            to_segment: Symbol::default(),
             to_orientation: true,
             overlap: "*".to_string(),
         }
@@ -40,7 +46,130 @@ impl Default for Link {
 
 pub static REQ_COLUMNS_LINK: usize = 6;
 
+/// Builder for [`Link`], see [`Link::builder`].
+#[derive(Debug, Clone)]
+pub struct LinkBuilder {
+    link: Link,
+}
+
+impl LinkBuilder {
+    pub fn from_orientation(mut self, orientation: bool) -> Self {
+        self.link.from_orientation = orientation;
+        self
+    }
+
+    pub fn to_orientation(mut self, orientation: bool) -> Self {
+        self.link.to_orientation = orientation;
+        self
+    }
+
+    pub fn overlap(mut self, overlap: &str) -> Self {
+        self.link.overlap = overlap.to_string();
+        self
+    }
+
+    pub fn tag(mut self, tag: &str, value: OptionalFieldValue) -> Self {
+        self.link.tags.add_tag(tag, value);
+        self
+    }
+
+    pub fn build(self) -> Link {
+        self.link
+    }
+}
+
 impl Link {
+    /// Starts building a `+/+` link between two segments with an unknown (`*`) overlap.
+    /// The segments are symbols interned by the parser the link is meant for.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::{gfa::GfaParser, line::link::Link};
+    ///
+    /// let mut gfa = GfaParser::new();
+    /// let (s1, s2) = (gfa.intern("s1"), gfa.intern("s2"));
+    ///
+    /// let link = Link::builder(s1, s2).to_orientation(false).overlap("2M").build();
+    /// assert_eq!(link.overlap, "2M");
+    /// assert_eq!(gfa.resolve(link.to_segment), "s2");
+    /// ```
+    pub fn builder(from_segment: Symbol, to_segment: Symbol) -> LinkBuilder {
+        LinkBuilder {
+            link: Link {
+                from_segment,
+                to_segment,
+                ..Link::default()
+            },
+        }
+    }
+
+    /// True if the parser created this link to join two consecutive path steps that had
+    /// no link, see [`crate::gfa::MissingBridgeOptions::CreateGhostLink`].
+    pub fn is_ghost(&self) -> bool {
+        self.tags.has_flag("ghost")
+    }
+
+    /// Returns the link as (from, from orientation, to, to orientation, overlap), written
+    /// the same way for both strands: `A+ -> B+` and `B- -> A-` are the same link, and both
+    /// give the one of the two that sorts first (by segment name, then orientation).
+    ///
+    /// Seen from the other strand, the overlap's operations come in reverse order, with
+    /// insertions and deletions swapped. An overlap that isn't a CIGAR (e.g. `*`) is kept as is.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::{gfa::GfaParser, line::link::Link};
+    ///
+    /// let mut gfa = GfaParser::new();
+    /// let (a, b) = (gfa.intern("a"), gfa.intern("b"));
+    ///
+    /// let forward = Link::builder(a, b).overlap("2M1I").build();
+    /// let reverse = Link::builder(b, a)
+    ///     .from_orientation(false)
+    ///     .to_orientation(false)
+    ///     .overlap("1D2M")
+    ///     .build();
+    ///
+    /// let canonical = ("a".to_string(), true, "b".to_string(), true, "2M1I".to_string());
+    /// assert_eq!(forward.canonical(&gfa), canonical);
+    /// assert_eq!(reverse.canonical(&gfa), canonical);
+    /// ```
+    pub fn canonical(&self, gfa: &GfaParser) -> (String, bool, String, bool, String) {
+        let (from, from_orientation, to, to_orientation, overlap) = self.canonical_key(gfa);
+        (
+            from.to_string(),
+            from_orientation,
+            to.to_string(),
+            to_orientation,
+            overlap.into_owned(),
+        )
+    }
+
+    // Link::canonical without the copies, the overlap is only reversed when the
+    // other strand sorts first
+    pub(crate) fn canonical_key<'a>(
+        &'a self,
+        gfa: &'a GfaParser,
+    ) -> (&'a str, bool, &'a str, bool, Cow<'a, str>) {
+        let (from, to) = (gfa.resolve(self.from_segment), gfa.resolve(self.to_segment));
+
+        let forward = (from, self.from_orientation, to, self.to_orientation);
+        let reverse = (to, !self.to_orientation, from, !self.from_orientation);
+
+        let overlap = Cow::Borrowed(self.overlap.as_str());
+        let ((from, from_orientation, to, to_orientation), overlap) = match forward.cmp(&reverse) {
+            Ordering::Less => (forward, overlap),
+            Ordering::Greater => (reverse, Cow::Owned(reverse_overlap(&self.overlap))),
+            // a link to the other strand of its own segment end, only the overlap differs
+            Ordering::Equal => (
+                forward,
+                overlap.min(Cow::Owned(reverse_overlap(&self.overlap))),
+            ),
+        };
+
+        (from, from_orientation, to, to_orientation, overlap)
+    }
+
     pub fn parse_line(
         (gfa, parts, raw, n, map, options): (
             &mut GfaParser,
@@ -51,15 +180,32 @@ impl Link {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
-        let (link_as_bridge, errors) =
-            parse_generic_bridge(gfa, BridgeParts{
+        let [
+            from_segment,
+            from_orientation,
+            to_segment,
+            to_orientation,
+            overlap,
+        ] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
+        let (link_as_bridge, errors) = parse_generic_bridge(
+            gfa,
+            BridgeParts {
                 bridge_type: BridgeType::Link,
-                from_segment: parts[1],
-                from_orientation: parts[2],
-                to_segment: parts[3],
-                to_orientation: parts[4],
-                overlap: Some(parts[5]),
-        }, raw, n, map, options);
+                from_segment,
+                from_orientation,
+                to_segment,
+                to_orientation,
+                overlap: Some(overlap),
+            },
+            raw,
+            n,
+            map,
+            options,
+        );
 
         if link_as_bridge.is_none() {
             return (None, errors);
@@ -73,35 +219,52 @@ impl Link {
                 raw: raw.to_owned(),
                 tags: map.clone(),
 
-                from_segment: link.from_segment,
+                from_segment: gfa.intern(&link.from_segment),
                 from_orientation: link.from_orientation,
-                to_segment: link.to_segment,
+                to_segment: gfa.intern(&link.to_segment),
                 to_orientation: link.to_orientation,
-                overlap: parts[5].to_owned(),
+                overlap: overlap.to_owned(),
             }),
             errors,
         )
     }
 
-    pub fn to_raw_line(&self, _: GFAVersion) -> String {
-        self.to_raw_line_v1()
+    pub fn to_raw_line(&self, _: GFAVersion, gfa: &GfaParser) -> String {
+        self.to_raw_line_v1(gfa)
     }
 
-    fn to_raw_line_v1(&self) -> String {
+    fn to_raw_line_v1(&self, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(gfa.resolve(self.from_segment), gfa.resolve(self.to_segment))
+    }
+
+    // the segment names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, from_segment: &str, to_segment: &str) -> String {
         let columns = [
-            self.from_segment.as_str(),
+            from_segment,
             if self.from_orientation { "+" } else { "-" },
-            self.to_segment.as_str(),
+            to_segment,
             if self.to_orientation { "+" } else { "-" },
             self.overlap.as_str(),
         ];
 
         // build the GFA line
-        build_gfa_line(
-            'L',
-            &columns,
-            &self.tags,
-        )
+        build_gfa_line('L', &columns, &self.tags)
     }
 }
 
+// the same overlap read from the other strand
+fn reverse_overlap(overlap: &str) -> String {
+    let Ok(operations) = parse_cigar(overlap) else {
+        return overlap.to_string();
+    };
+
+    operations
+        .iter()
+        .rev()
+        .map(|(count, op)| match op {
+            'I' => format!("{count}D"),
+            'D' => format!("{count}I"),
+            op => format!("{count}{op}"),
+        })
+        .collect()
+}
@@ -1,16 +1,21 @@
 mod bridge;
 pub mod containment;
+pub mod edge;
 pub mod fragment;
+pub mod gap;
+mod group;
 pub mod header;
 pub mod jump;
 pub mod link;
+pub mod ordered;
 pub mod path;
 pub mod record;
 pub mod segment;
+pub mod symbol;
+pub mod unordered;
 pub mod utils;
 pub mod walk;
-pub mod edge;
-pub mod gap;
-mod group;
-pub mod unordered;
-pub mod ordered;
\ No newline at end of file
+
+pub(crate) use bridge::{BridgeEnds, check_bridge_ends, resolve_bridge_ends, wire_bridge};
+pub use bridge::{BridgeRef, BridgeType};
+pub(crate) use group::rename_member;
@@ -1,11 +1,18 @@
 use crate::errors::ParseMessage;
+use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
-use crate::line::group::parse_generic_group;
 use crate::line::group::GroupParts;
 use crate::line::group::GroupType;
+use crate::line::group::parse_generic_group;
+use crate::line::path::Path;
+use crate::line::path::Step;
+use crate::line::record::GfaRecord;
+use crate::line::utils::DirectedReference;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::parse_directed_reference;
+use crate::line::utils::required_columns;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
@@ -31,12 +38,17 @@ impl OrderedGroup {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
+        let [name, members] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
         let (ordered_as_group, errors) = parse_generic_group(
             gfa,
             GroupParts {
                 group_type: GroupType::OrderedGroup,
-                name: parts[1],
-                members: parts[2],
+                name,
+                members,
             },
             n,
             options,
@@ -61,21 +73,208 @@ impl OrderedGroup {
         )
     }
 
-    pub fn to_raw_line(&self, version: GFAVersion) -> String {
+    /// The members with their orientation, in group order, so the group can be followed
+    /// as an oriented walk. Members without a `+` or `-` are skipped.
+    pub fn directed_members(&self) -> Vec<DirectedReference> {
+        self.members
+            .iter()
+            .filter_map(|m| parse_directed_reference(m).ok())
+            .collect()
+    }
+
+    pub fn to_raw_line(&self, version: GFAVersion, gfa: &GfaParser) -> String {
         match version {
             GFAVersion::V2 => self.to_raw_line_v2(),
-            _ => self.to_raw_line_v1(),
+            _ => self.to_raw_line_v1(gfa),
         }
     }
 
-    fn to_raw_line_v1(&self) -> String {
-        // TODO: convert ordered groups to v1 paths
-        "".to_string()
+    fn to_raw_line_v1(&self, gfa: &GfaParser) -> String {
+        let (line, _) = self.to_path_line(gfa);
+        line
+    }
+
+    /// Builds a v1 P-line from the group, with `*` for every overlap,
+    /// see [`OrderedGroup::to_path`].
+    pub fn to_path_line(&self, gfa: &GfaParser) -> (String, Vec<ParseMessage>) {
+        let (path, messages) = self.to_path(gfa);
+
+        let line = path.map_or_else(String::new, |p| p.to_raw_line(GFAVersion::V1, gfa));
+
+        (line, messages)
+    }
+
+    /// Turns the group into a path over its members, with `*` for every overlap.
+    ///
+    /// Segment members become steps in the same orientation. Edge members are expanded
+    /// to the segments they connect, in the direction the edge is traversed (`e-` goes
+    /// from the reverse of the second segment to the reverse of the first), skipping a
+    /// segment that is already the previous step. Other members (groups, or unknown names)
+    /// are skipped and reported as info messages. Returns `None` if no member references
+    /// a segment.
+    pub fn to_path(&self, gfa: &GfaParser) -> (Option<Path>, Vec<ParseMessage>) {
+        let mut messages = vec![];
+        let mut steps: Vec<Step> = vec![];
+
+        for member in &self.members {
+            let name = member.trim_end_matches(['+', '-']);
+            let orientation = !member.ends_with('-');
+
+            match gfa.find_record_with_name(name) {
+                Some(GfaRecord::Segment(s)) => steps.push(Step {
+                    segment_id: s.line_no as u32,
+                    orientation,
+                }),
+                Some(GfaRecord::Edge(e)) => {
+                    let (first, second) = if orientation {
+                        (
+                            (e.from_segment, e.from_orientation),
+                            (e.to_segment, e.to_orientation),
+                        )
+                    } else {
+                        (
+                            (e.to_segment, !e.to_orientation),
+                            (e.from_segment, !e.from_orientation),
+                        )
+                    };
+
+                    for (name, orientation) in [first, second] {
+                        push_edge_end(&mut steps, gfa, gfa.resolve(name), orientation);
+                    }
+                }
+                _ => messages.push(ParseMessage::new(
+                    self.line_no,
+                    ParseMessageCode::LossyConversion,
+                    format!("ordered group member {member} is not a segment or edge; skipping"),
+                )),
+            }
+        }
+
+        if steps.is_empty() {
+            return (None, messages);
+        }
+
+        // a single step has no overlaps, but the column is still required
+        let overlaps = vec!["*".to_string(); (steps.len() - 1).max(1)];
+
+        let path = Path {
+            line_no: self.line_no,
+            raw: self.raw.clone(),
+            tags: self.tags.clone(),
+
+            name: self.name.clone(),
+            steps,
+            overlaps,
+            missing_segments: vec![],
+        };
+
+        (Some(path), messages)
     }
 
-    fn to_raw_line_v2(&self) -> String {
+    pub(crate) fn to_raw_line_v2(&self) -> String {
         let members_str = self.members.join(" ");
         let parts = vec![self.name.as_str(), members_str.as_str()];
-        build_gfa_line('U', &parts, &self.tags)
+        build_gfa_line('O', &parts, &self.tags)
+    }
+}
+
+// an edge end that is already the previous step (listed before the edge, or shared
+// with the previous edge) is not repeated
+fn push_edge_end(steps: &mut Vec<Step>, gfa: &GfaParser, name: &str, orientation: bool) {
+    let Some(segment_id) = gfa.segment_line_no(name) else {
+        return;
+    };
+    let segment_id = segment_id as u32;
+
+    let repeated = steps
+        .last()
+        .is_some_and(|s| s.segment_id == segment_id && s.orientation == orientation);
+
+    if !repeated {
+        steps.push(Step {
+            segment_id,
+            orientation,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::ParseMessageCode,
+        gfa::{GFAVersion, GfaParser, ParseOptions, WriteOptions},
+    };
+
+    #[test]
+    fn ordered_group_to_path() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "H\tVN:Z:2.0",
+            "S\ta\t4\tACGT",
+            "S\tb\t4\tACGT",
+            "S\tc\t4\tACGT",
+            "E\te1\ta+\tb+\t3\t4$\t0\t1\t1M",
+            "E\te2\tb+\tc-\t3\t4$\t3\t4$\t1M",
+            "O\to1\ta+ e1+ b- o2",
+            "O\to2\te1+ e2+",
+            "O\to3\te2-",
+        ] {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let groups: Vec<_> = gfa.ordered_groups().collect();
+        let (line, messages) = groups[0].to_path_line(&gfa);
+
+        assert_eq!(line, "P\to1\ta+,b+,b-\t*,*");
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].code, ParseMessageCode::LossyConversion);
+        assert_eq!(groups[0].to_raw_line(GFAVersion::V1, &gfa), line);
+
+        assert_eq!(groups[1].to_path_line(&gfa).0, "P\to2\ta+,b+,c-\t*,*");
+        assert_eq!(groups[2].to_path_line(&gfa).0, "P\to3\tc+,b-\t*");
+    }
+
+    #[test]
+    fn ordered_group_round_trip() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "H\tVN:Z:2.0",
+            "S\ta\t4\tACGT",
+            "S\tb\t4\tACGT",
+            "O\to1\tb- a+ b+",
+        ] {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let written = gfa.to_gfa_string(GFAVersion::V2, &WriteOptions::default());
+        assert!(written.contains("O\to1\tb- a+ b+"));
+
+        let mut reparsed = GfaParser::new();
+        for line in written.lines() {
+            reparsed
+                .add_line(line, &options)
+                .expect("written line should parse");
+        }
+
+        assert_eq!(reparsed.unordered_groups().count(), 0);
+        let directed: Vec<String> = reparsed
+            .ordered_groups()
+            .next()
+            .unwrap()
+            .directed_members()
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(directed, ["b-", "a+", "b+"]);
+        assert_eq!(
+            reparsed.ordered_groups().next().unwrap().members,
+            ["b-", "a+", "b+"]
+        );
     }
 }
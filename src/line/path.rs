@@ -1,8 +1,11 @@
 use crate::gfa::GFAVersion;
+use crate::gfa::MissingBridgeOptions;
 use crate::gfa::MissingSegmentOptions;
 use crate::gfa::ParseOptions;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::column_of;
 use crate::line::utils::is_valid_cigar;
+use crate::line::walk::Walk;
 
 use crate::errors::ParseMessageCode;
 
@@ -10,6 +13,7 @@ use crate::errors::ParseMessage;
 
 use crate::gfa::GfaParser;
 use crate::line::utils::is_valid_name;
+use crate::line::utils::required_columns;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
@@ -21,6 +25,9 @@ pub struct Path {
     pub name: String,
     pub steps: Vec<Step>,
     pub overlaps: Vec<String>,
+    /// Steps whose segment doesn't exist, as `(step index, segment name)`. These steps
+    /// have a `segment_id` of 0, see [`MissingSegmentOptions::Ignore`].
+    pub missing_segments: Vec<(usize, String)>,
 }
 
 #[derive(Debug, Clone)]
@@ -29,6 +36,14 @@ pub struct Step {
     pub orientation: bool, // true for +, false for -
 }
 
+// name of the step at `idx`, if its segment doesn't exist
+pub(crate) fn missing_segment(missing: &[(usize, String)], idx: usize) -> Option<&str> {
+    missing
+        .iter()
+        .find(|(i, _)| *i == idx)
+        .map(|(_, name)| name.as_str())
+}
+
 pub static REQ_COLUMNS_PATH: usize = 4;
 
 impl Path {
@@ -40,29 +55,34 @@ impl Path {
             usize,
             &mut TagMap,
             &ParseOptions,
-        )
+        ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
+        let [path_name, segment_names, overlaps_column] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
         let mut errors = vec![];
 
-        if !is_valid_name(parts[1]) {
+        if !is_valid_name(path_name) {
             errors.push(ParseMessage::new(
                 n,
                 ParseMessageCode::InvalidID,
-                parts[1].to_owned(),
+                path_name.to_owned(),
             ));
-            
+
             return (None, errors);
         }
 
-        let name = &gfa.ensure_name_unique(n, parts[1].to_string());
+        let name = &gfa.ensure_name_unique(n, path_name.to_string());
 
-        let steps_str = parts[2].split([',', ';']).collect::<Vec<&str>>();
-        let mut overlaps_str = parts[3].split(",").collect::<Vec<&str>>();
+        let steps_str = segment_names.split([',', ';']).collect::<Vec<&str>>();
+        let mut overlaps_str = overlaps_column.split(",").collect::<Vec<&str>>();
 
         let mut steps: Vec<Step> = Vec::with_capacity(steps_str.len());
         let mut overlaps: Vec<String> = Vec::with_capacity(steps_str.len());
 
-        let use_overlaps = if parts[3] == "*" {    
+        let use_overlaps = if overlaps_column == "*" {
             // no overlaps provided
             false
         } else if steps_str.len() != (overlaps_str.len() + 1) {
@@ -78,20 +98,24 @@ impl Path {
             true
         };
 
+        let mut missing_segments = vec![];
         let mut prev_step = None::<Step>;
         let mut step_idx: isize = -1;
+        let mut step_column = column_of(parts, 2);
 
         // parse each step in the path
         for path_step in &steps_str {
             step_idx += 1;
 
+            let column = step_column;
+            step_column += path_step.len() + 1;
+
             // shortest path step is 2 characters (A+)
             if path_step.len() < 2 {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidPathStep,
-                    path_step.to_string(),
-                ));
+                errors.push(
+                    ParseMessage::new(n, ParseMessageCode::InvalidPathStep, path_step.to_string())
+                        .with_column(column),
+                );
                 continue;
             }
 
@@ -100,11 +124,14 @@ impl Path {
             } else if let Some(s) = path_step.strip_suffix('-') {
                 (s.to_string(), false)
             } else {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidPathStepOrientation,
-                    path_step.to_string(),
-                ));
+                errors.push(
+                    ParseMessage::new(
+                        n,
+                        ParseMessageCode::InvalidPathStepOrientation,
+                        path_step.to_string(),
+                    )
+                    .with_column(column + path_step.len() - 1),
+                );
                 (path_step.to_string(), true)
             };
 
@@ -125,7 +152,7 @@ impl Path {
             }
 
             let mut graph_segment_opt = gfa.find_segment_with_name(&segment);
-            
+
             if graph_segment_opt.is_none() {
                 errors.push(ParseMessage::new(
                     n,
@@ -143,8 +170,8 @@ impl Path {
                 }
 
                 if options.handle_missing_segment == MissingSegmentOptions::CreateGhost {
-                    gfa.create_ghost_segment(segment.to_string());
-                    graph_segment_opt = gfa.find_segment_with_name(&segment);
+                    let idx = gfa.ghost_for(&segment, options);
+                    graph_segment_opt = gfa.records[idx].as_mut_segment();
                 }
 
                 if options.handle_missing_segment == MissingSegmentOptions::SoftSkip {
@@ -152,9 +179,24 @@ impl Path {
                 }
             }
 
-            let graph_segment = graph_segment_opt.unwrap();
+            // only MissingSegmentOptions::Ignore gets here without a segment. like walks,
+            // the step is kept but points at no segment, so there is no link to check
+            let Some(graph_segment) = graph_segment_opt else {
+                missing_segments.push((steps.len(), segment));
+                steps.push(Step {
+                    segment_id: 0,
+                    orientation,
+                });
+                prev_step = None;
+                continue;
+            };
             let segment_line_no = graph_segment.line_no as u32;
-            let curr_step_incoming_links = graph_segment.incoming_links.clone();
+            let curr_step_links = graph_segment
+                .incoming_links
+                .iter()
+                .chain(graph_segment.outgoing_links.iter())
+                .copied()
+                .collect::<Vec<usize>>();
 
             let curr_step = Step {
                 segment_id: segment_line_no,
@@ -178,16 +220,19 @@ impl Path {
 
                 let prev_step_segment_name = prev_step_segment.unwrap();
 
+                let prev_symbol = gfa.intern(&prev_step_segment_name);
+                let curr_symbol = gfa.intern(&curr_step_segment_name);
+
                 let mut found_link_between_segments = false;
                 let mut found_implicit_link_between_segments = false;
 
-                for link_no in curr_step_incoming_links.iter() {
+                for link_no in curr_step_links.iter() {
                     let link = gfa
                         .find_link_mut(*link_no)
-                        .expect("incoming_links is managed by segment.rs");
+                        .expect("incoming_links and outgoing_links are managed by segment.rs");
 
-                    if link.from_segment == prev_step_segment_name
-                        && link.to_segment == curr_step_segment_name
+                    if link.from_segment == prev_symbol
+                        && link.to_segment == curr_symbol
                         && prev_step_orientation == link.from_orientation
                         && curr_step.orientation == link.to_orientation
                     {
@@ -195,17 +240,20 @@ impl Path {
                         found_link_between_segments = true;
                     }
 
-                    if link.from_segment == prev_step_segment_name
-                        && link.to_segment == curr_step_segment_name
-                        && prev_step_orientation == !(link.to_orientation)
-                        && curr_step.orientation == !(link.from_orientation)
+                    // A+ -> B+ also implies B- -> A-
+                    if link.from_segment == curr_symbol
+                        && link.to_segment == prev_symbol
+                        && prev_step_orientation != link.to_orientation
+                        && curr_step.orientation != link.from_orientation
                     {
                         // check if the user cares about these
                         found_implicit_link_between_segments = true;
                     }
                 }
 
-                if !found_link_between_segments && (found_implicit_link_between_segments && !options.allow_implicit_links) {
+                if !found_link_between_segments
+                    && (!found_implicit_link_between_segments || !options.allow_implicit_links)
+                {
                     errors.push(ParseMessage::new(
                         n,
                         ParseMessageCode::LinkNotFound,
@@ -215,8 +263,28 @@ impl Path {
                             if prev_step_orientation { "+" } else { "-" },
                             curr_step_segment_name,
                             if curr_step.orientation { "+" } else { "-" }
-                        ).to_string(),
+                        )
+                        .to_string(),
                     ));
+
+                    if options.handle_missing_bridge == MissingBridgeOptions::HardSkip {
+                        errors.push(ParseMessage::new(
+                            n,
+                            ParseMessageCode::InvalidPath,
+                            raw.chars().take(100).collect(),
+                        ));
+                        return (None, errors);
+                    }
+
+                    if options.handle_missing_bridge == MissingBridgeOptions::CreateGhostLink {
+                        gfa.create_ghost_link(
+                            prev_step_segment_name.clone(),
+                            prev_step_orientation,
+                            curr_step_segment_name.clone(),
+                            curr_step.orientation,
+                            "*".to_string(),
+                        );
+                    }
                 }
 
                 prev_step = Some(curr_step.clone());
@@ -279,13 +347,14 @@ impl Path {
                         .trim_end_matches(['+', '-']);
 
                     let outgoing_links = step_segment_current.outgoing_links.clone();
+                    let next_symbol = gfa.intern(step_segment_next_str);
 
                     let candidate_link_no = outgoing_links.iter().copied().find(|&link_no| {
                         let link = &gfa
                             .find_link_mut(link_no)
                             .expect("outgoing_links is managed by segment.rs");
 
-                        link.to_segment == step_segment_next_str
+                        link.to_segment == next_symbol
                             && link.from_orientation == steps_str[step_index].ends_with("+")
                             && link.to_orientation == steps_str[step_index + 1].ends_with("+")
                     });
@@ -318,55 +387,305 @@ impl Path {
                 name: name.to_string(),
                 steps,
                 overlaps,
+                missing_segments,
             }),
             errors,
         )
     }
 
+    /// Splits a PanSN name (`sample#hap#contig`) into the sample, haplotype and contig,
+    /// the same fields a walk has. Returns `None` if the name doesn't follow PanSN.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::line::path::Path;
+    ///
+    /// let path = Path { name: "HG002#1#chr1".to_string(), ..Path::default() };
+    /// assert_eq!(path.pansn_fields(), Some(("HG002".to_string(), 1, "chr1".to_string())));
+    /// ```
+    pub fn pansn_fields(&self) -> Option<(String, u32, String)> {
+        let mut fields = self.name.splitn(3, '#');
+
+        let sample_id = fields.next().filter(|s| !s.is_empty())?;
+        let hap_index = fields.next()?.parse::<u32>().ok()?;
+        let seq_id = fields.next().filter(|s| !s.is_empty())?;
+
+        Some((sample_id.to_string(), hap_index, seq_id.to_string()))
+    }
+
+    /// Builds the walk following the same steps, with the sample, haplotype and
+    /// sequence taken from the PanSN name (see [`Path::pansn_fields`]) and no sequence range.
+    ///
+    /// Returns `None` if the name doesn't follow PanSN, or if any overlap is known,
+    /// since walks can't carry overlaps.
+    pub fn to_walk(&self) -> Option<Walk> {
+        if self.overlaps.iter().any(|o| o != "*") {
+            return None;
+        }
+
+        let (sample_id, hap_index, seq_id) = self.pansn_fields()?;
+
+        Some(Walk {
+            line_no: self.line_no,
+            raw: self.raw.clone(),
+            tags: self.tags.clone(),
+
+            sample_id,
+            hap_index,
+            seq_id,
+            seq_start: None,
+            seq_end: None,
+            walk: self.steps.clone(),
+            missing_segments: self.missing_segments.clone(),
+        })
+    }
+
     pub fn to_raw_line(&self, _: GFAVersion, gfa: &GfaParser) -> String {
         self.to_raw_line_v1(gfa)
     }
 
     fn to_raw_line_v1(&self, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(|segment_id| {
+            gfa.find_segment(segment_id as usize)
+                .map_or_else(|| segment_id.to_string(), |s| s.name.clone())
+        })
+    }
+
+    // the step names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, segment_name: impl Fn(u32) -> String) -> String {
         let name = self.name.as_str();
         let steps = self
             .steps
             .iter()
-            .map(|s| {
-                let seg_name = gfa
-                    .find_segment(s.segment_id as usize)
-                    .map(|s| s.name.clone())
-                    .unwrap_or_default();
+            .enumerate()
+            .map(|(idx, s)| {
+                let seg_name = missing_segment(&self.missing_segments, idx)
+                    .map_or_else(|| segment_name(s.segment_id), str::to_string);
                 format!("{}{}", seg_name, if s.orientation { "+" } else { "-" })
             })
             .collect::<Vec<String>>()
             .join(",");
 
-            let overlaps = self.overlaps.join(",");
+        // paths parsed without overlaps have none, but the column is still required
+        let overlaps = if self.overlaps.is_empty() {
+            "*".to_string()
+        } else {
+            self.overlaps.join(",")
+        };
 
         build_gfa_line('P', &[name, &steps, &overlaps], &self.tags)
     }
-}
 
+    /// Builds a single GAF record describing the geometry of the path.
+    ///
+    /// The path name is used as the query name and every coordinate column spans
+    /// the summed length of the path's segments, so the record reads as a perfect
+    /// end-to-end alignment of the path against itself. Mapping quality is 255 (missing).
+    pub fn to_gaf(&self, gfa: &GfaParser) -> String {
+        let mut path_string = String::new();
+        let mut length: i64 = 0;
+
+        for step in &self.steps {
+            let Some(segment) = gfa.find_segment(step.segment_id as usize) else {
+                continue;
+            };
+
+            path_string.push(if step.orientation { '>' } else { '<' });
+            path_string.push_str(&segment.name);
+            length += segment.get_length() as i64;
+        }
+
+        if path_string.is_empty() {
+            path_string.push('*');
+        }
 
+        let length = length.to_string();
+
+        [
+            self.name.as_str(),
+            &length,
+            "0",
+            &length,
+            "+",
+            &path_string,
+            &length,
+            "0",
+            &length,
+            &length,
+            &length,
+            "255",
+        ]
+        .join("\t")
+    }
+}
 
 #[cfg(test)]
 mod tests {
-    use crate::{errors::ParseMessageSeverity, gfa::{GfaParser, ParseOptions}};
+    use super::Path;
+    use crate::{
+        errors::ParseMessageSeverity,
+        gfa::{GFAVersion, GfaParser, ParseOptions, WriteOptions},
+        line::walk::Walk,
+    };
 
     #[test]
     fn test_working_path() {
         let mut gfa = GfaParser::new();
 
         let _ = gfa.parse("test/path.gfa", &ParseOptions::default());
-    
+
         gfa.messages.iter().for_each(|e| {
             e.print_formatted_error();
         });
 
-        let has_errors = gfa.messages.iter().any(|e| e.severity() != ParseMessageSeverity::Warn && e.severity() != ParseMessageSeverity::Info);
-        
+        let has_errors = gfa.messages.iter().any(|e| {
+            e.severity() != ParseMessageSeverity::Warn && e.severity() != ParseMessageSeverity::Info
+        });
+
         // TODO: write real test for path
         assert!(!has_errors);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn pansn_paths_become_walks() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "H\tVN:Z:1.1",
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t-\t0M",
+            "P\tHG002#1#chr1\ta+,b-\t*",
+            "P\tHG002#2#chr1\ta+,b-\t0M",
+            "P\tplain\ta+,b-\t*",
+        ] {
+            gfa.add_line(line, &options).unwrap();
+        }
+
+        let paths: Vec<_> = gfa.paths().collect();
+        let walk = paths[0].to_walk().unwrap();
+        assert_eq!(
+            (
+                walk.sample_id.as_str(),
+                walk.hap_index,
+                walk.seq_id.as_str()
+            ),
+            ("HG002", 1, "chr1")
+        );
+        assert_eq!(walk.walk.len(), 2);
+        assert!(paths[1].to_walk().is_none());
+        assert!(paths[2].to_walk().is_none());
+
+        let written = gfa.to_gfa_string(GFAVersion::V1_1, &WriteOptions::default());
+        assert!(written.contains("W\tHG002\t1\tchr1\t*\t*\t>a<b"));
+        assert!(written.contains("P\tHG002#2#chr1\t"));
+        assert!(written.contains("P\tplain\t"));
+    }
+
+    #[test]
+    fn pansn_names() {
+        let path = |name: &str| Path {
+            name: name.to_string(),
+            ..Path::default()
+        };
+
+        assert_eq!(
+            path("HG002#2#chr1#alt").pansn_fields(),
+            Some(("HG002".to_string(), 2, "chr1#alt".to_string()))
+        );
+        assert_eq!(path("HG002#x#chr1").pansn_fields(), None);
+        assert_eq!(path("HG002#1#").pansn_fields(), None);
+        assert_eq!(path("chr1").pansn_fields(), None);
+
+        let walk = Walk {
+            sample_id: "HG002".to_string(),
+            hap_index: 1,
+            seq_id: "chr1".to_string(),
+            ..Walk::default()
+        };
+
+        let name = walk.to_pansn_path_name();
+        assert_eq!(name, "HG002#1#chr1");
+        assert_eq!(
+            path(&name).pansn_fields(),
+            Some((walk.sample_id, walk.hap_index, walk.seq_id))
+        );
+    }
+
+    #[test]
+    fn path_to_gaf() {
+        let mut gfa = GfaParser::new();
+        let _ = gfa.parse("test/path.gfa", &ParseOptions::default());
+
+        let path = gfa
+            .paths()
+            .find(|p| p.name == "04_fwithmiddle")
+            .expect("path exists in test file");
+
+        assert_eq!(
+            path.to_gaf(&gfa),
+            "04_fwithmiddle\t60\t0\t60\t+\t>A4<B4>C4\t60\t0\t60\t60\t60\t255"
+        );
+    }
+
+    #[test]
+    fn path_creates_ghost_links() {
+        use crate::gfa::MissingBridgeOptions;
+
+        let lines = ["S\ta\tACGT", "S\tb\tACGT", "P\tp1\ta+,b+,a+,b+\t*"];
+
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+        for line in lines {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        // the second a+ -> b+ step reuses the ghost link made for the first
+        let ghosts: Vec<_> = gfa.links().filter(|l| l.is_ghost()).collect();
+        assert_eq!(ghosts.len(), 2);
+        let ghost_no = ghosts[0].line_no;
+        assert_eq!(
+            gfa.find_segment_with_name("a").unwrap().outgoing_links,
+            vec![ghost_no]
+        );
+        assert_eq!(
+            gfa.find_segment_with_name("b").unwrap().incoming_links,
+            vec![ghost_no]
+        );
+
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions {
+            handle_missing_bridge: MissingBridgeOptions::HardSkip,
+            ..ParseOptions::default()
+        };
+        gfa.add_line(lines[0], &options).unwrap();
+        gfa.add_line(lines[1], &options).unwrap();
+        assert!(gfa.add_line(lines[2], &options).is_err());
+    }
+
+    #[test]
+    fn messages_point_at_columns() {
+        use crate::errors::ParseMessageCode;
+
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        gfa.add_line("S\ta\tAC GT", &options).unwrap();
+        gfa.add_line("P\tp\ta+,ax\t*", &options).unwrap();
+
+        let column = |code: ParseMessageCode| {
+            gfa.messages
+                .iter()
+                .find(|m| m.code == code)
+                .and_then(|m| m.column)
+        };
+
+        assert_eq!(column(ParseMessageCode::InvalidSequence), Some(7));
+        assert_eq!(
+            column(ParseMessageCode::InvalidPathStepOrientation),
+            Some(9)
+        );
+    }
+}
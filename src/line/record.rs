@@ -1,4 +1,10 @@
+use std::fmt;
+
+use crate::errors::ParseMessage;
+use crate::errors::ParseMessageCode;
+use crate::errors::ParseMessageSeverity;
 use crate::gfa::GFAVersion;
+use crate::gfa::GfaParser;
 use crate::line::containment::Containment;
 use crate::line::edge::Edge;
 use crate::line::fragment::Fragment;
@@ -10,15 +16,18 @@ use crate::line::ordered::OrderedGroup;
 use crate::line::path::Path;
 use crate::line::segment::Segment;
 use crate::line::unordered::UnorderedGroup;
+use crate::line::utils::build_gfa_line;
 use crate::line::walk::Walk;
+use crate::optional_field::OptionalField;
 use crate::optional_field::TagMap;
-use crate::errors::ParseMessageCode;
-use crate::errors::ParseMessage;
-use crate::gfa::GfaParser;
 use crate::optional_field::collect_optional_fields;
+use crate::optional_field::collect_optional_fields_until_comment;
 use crate::parse_case;
 use crate::record_accessors;
 
+// segments are both the largest and the most common record, boxing them would only
+// add an allocation per segment
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum GfaRecord {
     Header(Header),
@@ -37,80 +46,37 @@ pub enum GfaRecord {
 
 impl GfaRecord {
     pub fn parse_line(
-        (gfa, line, n, options): (&mut GfaParser, &str, usize, &crate::gfa::ParseOptions),
+        args: (&mut GfaParser, &str, usize, &crate::gfa::ParseOptions),
     ) -> (Option<Self>, Vec<ParseMessage>) {
-        let parts: Vec<&str> = line.split('\t').collect();
-        let record_type = parts.first().cloned();
-
-        // keeping the raw lines is really only useful for debugging
-        let raw = if options.store_raw_lines {
-            line.to_owned()
-        } else {
-            "".to_string()
-        };
-
-        // get required columns based on the record type
-        let required_columns = match record_type {
-            Some("H") => crate::line::header::REQ_COLUMNS_HEADER,
-            Some("S") => {
-                if gfa.version == GFAVersion::V2 {
-                    4
-                } else {
-                    3
-                }
-            }
-            Some("L") => crate::line::link::REQ_COLUMNS_LINK,
-            Some("C") => crate::line::containment::REQ_COLUMNS_CONTAIN,
-            Some("P") => crate::line::path::REQ_COLUMNS_PATH,
-            Some("W") => crate::line::walk::REQ_COLUMNS_WALK,
-            Some("J") => crate::line::jump::REQ_COLUMNS_JUMP,
-            Some("F") => crate::line::fragment::REQ_COLUMNS_FRAGMENT,
-            Some("E") => crate::line::edge::REQ_COLUMNS_EDGE,
-            Some("G") => crate::line::gap::REQ_COLUMNS_GAP,
-            Some("O") => crate::line::ordered::REQ_COLUMNS_ORDERED,
-            Some("U") => crate::line::unordered::REQ_COLUMNS_UNORDERED,
-            _ => panic!("unreachable")
-        };
+        Self::parse_line_with_buffer(args, &mut Vec::new())
+    }
 
-        // check if there are enough columns
-        if parts.len() < required_columns {
-            return (
-                None,
-                vec![ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidLine,
-                    raw.to_owned(),
-                )],
-            );
+    /// Same as [`GfaRecord::parse_line`], but splits the line into `parts`, so the
+    /// buffer can be reused across lines instead of allocating one per line.
+    pub(crate) fn parse_line_with_buffer<'a>(
+        (gfa, line, n, options): (&mut GfaParser, &'a str, usize, &crate::gfa::ParseOptions),
+        parts: &mut Vec<&'a str>,
+    ) -> (Option<Self>, Vec<ParseMessage>) {
+        let (raw, tags, errors) = match tokenize(line, parts, n, options, &gfa.version) {
+            Ok(tokens) => tokens,
+            Err(errors) => return (None, errors),
         };
 
-        let mut errors = vec![];
-
-        // collect optional fields
-        let (tags, tag_errs) = collect_optional_fields(
-            n,
-            record_type.expect("should have already skipped line if unknown record type"),
-            &parts[required_columns..],
-        );
-
-        if let Some(err) = tag_errs.into_iter().next() {
-            errors.push(err);
-        }
-
         // keep note of all tag names encountered
         for tag in tags.iter() {
             gfa.tag_names.insert(tag.tag.clone());
         }
 
+        let record_type = parts.first().cloned();
         let mut tag_map: TagMap = TagMap::from_vec(tags);
 
         let args = (
-            gfa, 
-            parts.as_slice(), 
-            raw.as_str(), 
-            n, 
-            &mut tag_map, 
-            options
+            gfa,
+            parts.as_slice(),
+            raw.as_str(),
+            n,
+            &mut tag_map,
+            options,
         );
 
         let (record, mut record_errors) = match record_type {
@@ -126,7 +92,7 @@ impl GfaRecord {
             Some("G") => parse_case!(Gap, Gap, args),
             Some("O") => parse_case!(OrderedGroup, OrderedGroup, args),
             Some("U") => parse_case!(UnorderedGroup, UnorderedGroup, args),
-            _ => panic!("unreachable"),
+            _ => (None, vec![unknown_line(n, line, options)]),
         };
 
         // add optional field errors
@@ -135,6 +101,33 @@ impl GfaRecord {
         (record, record_errors)
     }
 
+    /// Parses a segment line without touching the parser, see [`Segment::pre_parse`].
+    /// Also returns the names of the tags on the line, which still have to be added
+    /// to [`GfaParser::tag_names`].
+    pub(crate) fn pre_parse_segment(
+        line: &str,
+        n: usize,
+        options: &crate::gfa::ParseOptions,
+        version: &GFAVersion,
+    ) -> (Option<Segment>, Vec<ParseMessage>, Vec<String>) {
+        let mut parts = Vec::new();
+        let (raw, tags, errors) = match tokenize(line, &mut parts, n, options, version) {
+            Ok(tokens) => tokens,
+            Err(errors) => return (None, errors, vec![]),
+        };
+
+        let tag_names = tags.iter().map(|t| t.tag.clone()).collect();
+        let mut tag_map: TagMap = TagMap::from_vec(tags);
+
+        let (segment, mut segment_errors) =
+            Segment::pre_parse(&parts, &raw, n, &mut tag_map, options, version);
+
+        // add optional field errors
+        segment_errors.extend(errors);
+
+        (segment, segment_errors, tag_names)
+    }
+
     // TODO: make this a macro if it gets too unwieldy
 
     pub fn line_no(&self) -> usize {
@@ -154,24 +147,164 @@ impl GfaRecord {
         }
     }
 
+    pub(crate) fn set_line_no(&mut self, line_no: usize) {
+        match self {
+            GfaRecord::Header(r) => r.line_no = line_no,
+            GfaRecord::Segment(r) => r.line_no = line_no,
+            GfaRecord::Link(r) => r.line_no = line_no,
+            GfaRecord::Containment(r) => r.line_no = line_no,
+            GfaRecord::Path(r) => r.line_no = line_no,
+            GfaRecord::Walk(r) => r.line_no = line_no,
+            GfaRecord::Jump(r) => r.line_no = line_no,
+            GfaRecord::Fragment(r) => r.line_no = line_no,
+            GfaRecord::Edge(r) => r.line_no = line_no,
+            GfaRecord::Gap(r) => r.line_no = line_no,
+            GfaRecord::OrderedGroup(r) => r.line_no = line_no,
+            GfaRecord::UnorderedGroup(r) => r.line_no = line_no,
+        }
+    }
+
+    pub fn tags(&self) -> &TagMap {
+        match self {
+            GfaRecord::Header(r) => &r.tags,
+            GfaRecord::Segment(r) => &r.tags,
+            GfaRecord::Link(r) => &r.tags,
+            GfaRecord::Containment(r) => &r.tags,
+            GfaRecord::Path(r) => &r.tags,
+            GfaRecord::Walk(r) => &r.tags,
+            GfaRecord::Jump(r) => &r.tags,
+            GfaRecord::Fragment(r) => &r.tags,
+            GfaRecord::Edge(r) => &r.tags,
+            GfaRecord::Gap(r) => &r.tags,
+            GfaRecord::OrderedGroup(r) => &r.tags,
+            GfaRecord::UnorderedGroup(r) => &r.tags,
+        }
+    }
+
+    /// True for segments and links the parser created to patch a missing reference,
+    /// see [`GfaParser::ghost_segments`].
+    pub fn is_ghost(&self) -> bool {
+        match self {
+            GfaRecord::Segment(s) => s.is_ghost(),
+            GfaRecord::Link(l) => l.is_ghost(),
+            _ => false,
+        }
+    }
+
+    /// The pass in which the record is parsed (and written): headers, then
+    /// segments, then bridges, then paths, walks and groups.
+    pub(crate) fn pass(&self) -> usize {
+        match self {
+            GfaRecord::Header(_) => 0,
+            GfaRecord::Segment(_) => 1,
+            GfaRecord::Link(_)
+            | GfaRecord::Jump(_)
+            | GfaRecord::Containment(_)
+            | GfaRecord::Fragment(_)
+            | GfaRecord::Edge(_)
+            | GfaRecord::Gap(_) => 2,
+            GfaRecord::Path(_)
+            | GfaRecord::Walk(_)
+            | GfaRecord::OrderedGroup(_)
+            | GfaRecord::UnorderedGroup(_) => 3,
+        }
+    }
+
+    /// Names that the record adds to the namespace.
+    pub(crate) fn declared_names(&self) -> Vec<String> {
+        match self {
+            GfaRecord::Segment(s) => vec![s.name.clone()],
+            GfaRecord::Path(p) => vec![p.name.clone()],
+            GfaRecord::OrderedGroup(o) => vec![o.name.clone()],
+            GfaRecord::UnorderedGroup(u) => vec![u.name.clone()],
+            GfaRecord::Edge(e) => e.id.clone().into_iter().collect(),
+            GfaRecord::Gap(g) => g.id.clone().into_iter().collect(),
+            GfaRecord::Link(l) => l.tags.get::<String>("ID").into_iter().collect(),
+            GfaRecord::Jump(j) => j.tags.get::<String>("ID").into_iter().collect(),
+            GfaRecord::Containment(c) => c.tags.get::<String>("ID").into_iter().collect(),
+            GfaRecord::Header(_) | GfaRecord::Fragment(_) | GfaRecord::Walk(_) => vec![],
+        }
+    }
+
+    /// The columns of the line [`GfaRecord::to_raw_line`] writes in the parser's version,
+    /// starting with the record type but without any tags. Unlike `raw`, this doesn't
+    /// depend on [`crate::gfa::ParseOptions::store_raw_lines`].
+    ///
+    /// Records that can't be written in the parser's version have no columns.
+    pub fn reconstruct_columns(&self, gfa: &GfaParser) -> Vec<String> {
+        let line = self.to_raw_line(gfa.version.clone(), gfa);
+        let columns: Vec<&str> = line.split('\t').collect();
+
+        // tags always come after the required columns
+        let count = required_column_count(columns.first().copied(), &gfa.version).unwrap_or(0);
+
+        columns
+            .into_iter()
+            .take(count)
+            .map(str::to_string)
+            .collect()
+    }
+
     pub fn to_raw_line(&self, version: GFAVersion, gfa: &GfaParser) -> String {
         match self {
             GfaRecord::Header(r) => r.to_raw_line(version),
             GfaRecord::Segment(r) => r.to_raw_line(version),
-            GfaRecord::Link(r) => r.to_raw_line(version),
-            GfaRecord::Containment(r) => r.to_raw_line(version),
+            GfaRecord::Link(r) => r.to_raw_line(version, gfa),
+            GfaRecord::Containment(r) => r.to_raw_line(version, gfa),
             GfaRecord::Path(r) => r.to_raw_line(version, gfa),
             GfaRecord::Walk(r) => r.to_raw_line(version, gfa),
-            GfaRecord::Jump(r) => r.to_raw_line(version),
+            GfaRecord::Jump(r) => r.to_raw_line(version, gfa),
             GfaRecord::Fragment(r) => r.to_raw_line(version),
-            GfaRecord::Edge(r) => r.to_raw_line(version),
-            GfaRecord::Gap(r) => r.to_raw_line(version),
-            GfaRecord::OrderedGroup(r) => r.to_raw_line(version),
+            GfaRecord::Edge(r) => r.to_raw_line(version, gfa),
+            GfaRecord::Gap(r) => r.to_raw_line(version, gfa),
+            GfaRecord::OrderedGroup(r) => r.to_raw_line(version, gfa),
             GfaRecord::UnorderedGroup(r) => r.to_raw_line(version),
         }
     }
 }
 
+/// Writes the record as a GFA line without a parser to resolve segment names.
+///
+/// Path and walk steps, and the segments of links, jumps, containments, edges and gaps,
+/// are written as their numeric segment line number or [`crate::line::symbol::Symbol`]
+/// instead. Segments with a length, edges, gaps, fragments and groups are written as
+/// GFA 2. Use [`GfaRecord::to_raw_line`] to write a line that can be read back.
+impl fmt::Display for GfaRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let line = match self {
+            GfaRecord::Header(r) => build_gfa_line('H', &[], &r.tags),
+            GfaRecord::Segment(r) if r.length.is_some() => r.to_raw_line(GFAVersion::V2),
+            GfaRecord::Segment(r) => r.to_raw_line(GFAVersion::V1),
+            GfaRecord::Link(r) => {
+                r.to_raw_line_with(&r.from_segment.to_string(), &r.to_segment.to_string())
+            }
+            GfaRecord::Jump(r) => {
+                r.to_raw_line_with(&r.from_segment.to_string(), &r.to_segment.to_string())
+            }
+            GfaRecord::Containment(r) => {
+                r.to_raw_line_with(&r.container.to_string(), &r.contained.to_string())
+            }
+            GfaRecord::Path(r) => r.to_raw_line_with(|id| id.to_string()),
+            GfaRecord::Walk(r) => r.to_raw_line_with(|id| id.to_string()),
+            GfaRecord::Fragment(r) => r.to_raw_line(GFAVersion::V2),
+            GfaRecord::Edge(r) => r.to_raw_line_with(
+                GFAVersion::V2,
+                &r.from_segment.to_string(),
+                &r.to_segment.to_string(),
+            ),
+            GfaRecord::Gap(r) => r.to_raw_line_with(
+                GFAVersion::V2,
+                &r.from_segment.to_string(),
+                &r.to_segment.to_string(),
+            ),
+            GfaRecord::OrderedGroup(r) => r.to_raw_line_v2(),
+            GfaRecord::UnorderedGroup(r) => r.to_raw_line(GFAVersion::V2),
+        };
+
+        f.write_str(&line)
+    }
+}
+
 record_accessors! {
    impl GfaRecord {
         Header(Header) => (as_header, as_mut_header);
@@ -187,4 +320,276 @@ record_accessors! {
         OrderedGroup(OrderedGroup) => (as_ordered_group, as_mut_ordered_group);
         UnorderedGroup(UnorderedGroup) => (as_unordered_group, as_mut_unordered_group);
     }
-}
\ No newline at end of file
+}
+
+/// Reports a line that isn't a known record type, which is fatal with
+/// [`crate::gfa::ParseOptions::strict_line_types`].
+pub(crate) fn unknown_line(
+    n: usize,
+    line: &str,
+    options: &crate::gfa::ParseOptions,
+) -> ParseMessage {
+    let message = ParseMessage::new(n, ParseMessageCode::UnknownLine, line.to_owned());
+
+    if options.strict_line_types {
+        message.with_severity(ParseMessageSeverity::Fatal)
+    } else {
+        message
+    }
+}
+
+// number of columns before the optional fields, including the record type
+fn required_column_count(record_type: Option<&str>, version: &GFAVersion) -> Option<usize> {
+    let count = match record_type? {
+        "H" => crate::line::header::REQ_COLUMNS_HEADER,
+        "S" => {
+            if *version == GFAVersion::V2 {
+                4
+            } else {
+                3
+            }
+        }
+        "L" => crate::line::link::REQ_COLUMNS_LINK,
+        "C" => crate::line::containment::REQ_COLUMNS_CONTAIN,
+        "P" => crate::line::path::REQ_COLUMNS_PATH,
+        "W" => crate::line::walk::REQ_COLUMNS_WALK,
+        "J" => crate::line::jump::REQ_COLUMNS_JUMP,
+        "F" => crate::line::fragment::REQ_COLUMNS_FRAGMENT,
+        "E" => crate::line::edge::REQ_COLUMNS_EDGE,
+        "G" => crate::line::gap::REQ_COLUMNS_GAP,
+        "O" => crate::line::ordered::REQ_COLUMNS_ORDERED,
+        "U" => crate::line::unordered::REQ_COLUMNS_UNORDERED,
+        _ => return None,
+    };
+
+    Some(count)
+}
+
+type Tokens = (String, Vec<OptionalField>, Vec<ParseMessage>);
+
+// splits the line into columns (reusing the `parts` buffer) and collects its optional fields
+fn tokenize<'a>(
+    line: &'a str,
+    parts: &mut Vec<&'a str>,
+    n: usize,
+    options: &crate::gfa::ParseOptions,
+    version: &GFAVersion,
+) -> Result<Tokens, Vec<ParseMessage>> {
+    // lines from BufRead::lines are already stripped, but lines handed over directly
+    // (or split on '\n' only) can still end in the '\r' of a CRLF line ending
+    let line = line.strip_suffix('\r').unwrap_or(line);
+
+    parts.clear();
+    parts.extend(line.split('\t'));
+    let record_type = parts.first().cloned();
+
+    // keeping the raw lines is really only useful for debugging
+    let raw = if options.store_raw_lines {
+        line.to_owned()
+    } else {
+        "".to_string()
+    };
+
+    let Some(required_columns) = required_column_count(record_type, version) else {
+        return Err(vec![unknown_line(n, line, options)]);
+    };
+
+    // check if there are enough columns
+    if parts.len() < required_columns {
+        return Err(vec![ParseMessage::new(
+            n,
+            ParseMessageCode::InvalidLine,
+            raw.to_owned(),
+        )]);
+    };
+
+    let mut errors = vec![];
+
+    // collect optional fields
+    let collect = if options.allow_inline_comments {
+        collect_optional_fields_until_comment
+    } else {
+        collect_optional_fields
+    };
+
+    let (tags, tag_errs) = collect(
+        n,
+        record_type.expect("should have already skipped line if unknown record type"),
+        &parts[required_columns..],
+    );
+
+    if let Some(err) = tag_errs.into_iter().next() {
+        errors.push(err);
+    }
+
+    Ok((raw, tags, errors))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::{ParseMessage, ParseMessageCode},
+        gfa::{GFAVersion, GfaParser, ParseOptions},
+        line::{
+            containment::Containment, edge::Edge, fragment::Fragment, gap::Gap, jump::Jump,
+            link::Link, ordered::OrderedGroup, path::Path, segment::Segment,
+            unordered::UnorderedGroup, walk::Walk,
+        },
+        optional_field::TagMap,
+    };
+
+    // calls the record parser directly, skipping the column count check in `tokenize`
+    fn parse_columns(gfa: &mut GfaParser, parts: &[&str]) -> Vec<ParseMessage> {
+        let options = ParseOptions::default();
+        let map = &mut TagMap::default();
+        let args = (gfa, parts, "", 1, map, &options);
+
+        match parts[0] {
+            "S" => Segment::parse_line(args).1,
+            "L" => Link::parse_line(args).1,
+            "C" => Containment::parse_line(args).1,
+            "P" => Path::parse_line(args).1,
+            "W" => Walk::parse_line(args).1,
+            "J" => Jump::parse_line(args).1,
+            "F" => Fragment::parse_line(args).1,
+            "E" => Edge::parse_line(args).1,
+            "G" => Gap::parse_line(args).1,
+            "O" => OrderedGroup::parse_line(args).1,
+            "U" => UnorderedGroup::parse_line(args).1,
+            _ => unreachable!("only known record types are tested"),
+        }
+    }
+
+    #[test]
+    fn columns_without_raw_lines() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "S\ta\tACGT\tRC:i:4",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t-\t2M\tFC:i:1",
+            "P\tp1\ta+,b-\t2M",
+        ] {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let columns: Vec<Vec<String>> = gfa
+            .records
+            .iter()
+            .map(|r| r.reconstruct_columns(&gfa))
+            .collect();
+
+        assert_eq!(columns.len(), 4);
+        assert_eq!(columns[0], ["S", "a", "ACGT"]);
+        assert_eq!(columns[2], ["L", "a", "+", "b", "-", "2M"]);
+        assert_eq!(columns[3], ["P", "p1", "a+,b-", "2M"]);
+    }
+
+    #[test]
+    fn truncated_lines_do_not_panic() {
+        let v1 = [
+            "S\ta\tACGT",
+            "L\ta\t+\tb\t+\t0M",
+            "C\ta\t+\tb\t+\t1\t1M",
+            "P\tp1\ta+,b+\t0M",
+            "W\tsample\t1\tchr1\t0\t8\t>a>b",
+            "J\ta\t+\tb\t+\t10",
+        ];
+        let v2 = [
+            "S\ta\t4\tACGT",
+            "F\ta\tread1+\t0\t4$\t0\t4\t4M",
+            "E\te1\ta+\tb+\t3\t4$\t0\t1\t1M",
+            "G\tg1\ta+\tb+\t10\t*",
+            "O\to1\ta+ b+",
+            "U\tu1\ta b",
+        ];
+
+        for (version, lines) in [(GFAVersion::V1, v1), (GFAVersion::V2, v2)] {
+            for line in lines {
+                let columns: Vec<&str> = line.split('\t').collect();
+
+                for len in 1..columns.len() {
+                    let mut gfa = GfaParser::new();
+                    gfa.version = version.clone();
+
+                    let messages = parse_columns(&mut gfa, &columns[..len]);
+                    assert!(
+                        messages
+                            .iter()
+                            .any(|m| m.code == ParseMessageCode::InvalidLine),
+                        "{line} truncated to {len} columns"
+                    );
+
+                    let truncated = columns[..len].join("\t");
+                    assert!(gfa.add_line(&truncated, &ParseOptions::default()).is_err());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn display_every_record() {
+        let options = ParseOptions::default();
+
+        let mut v1 = GfaParser::new();
+        for line in [
+            "S\ta\tACGT",
+            "S\tb\tACGT",
+            "L\ta\t+\tb\t-\t2M",
+            "C\ta\t+\tb\t+\t0\t4M",
+            "P\tp1\ta+,b-\t2M",
+            "W\tsample\t1\tchr1\t0\t8\t>a<b",
+            "J\ta\t+\tb\t-\t10",
+        ] {
+            v1.add_line(line, &options).expect("test line should parse");
+        }
+
+        let mut v2 = GfaParser::new();
+        for line in [
+            "H\tVN:Z:2.0",
+            "S\ta\t4\tACGT",
+            "S\tb\t4\tACGT",
+            "F\ta\tread+\t0\t4$\t0\t4\t4M",
+            "E\te1\ta+\tb-\t2\t4$\t0\t2\t2M",
+            "G\tg1\ta+\tb-\t10\t*",
+            "O\to1\ta+ b-",
+            "U\tu1\ta b",
+        ] {
+            v2.add_line(line, &options).expect("test line should parse");
+        }
+
+        // bridges print the symbols of their segments, steps the segment line numbers
+        let (a, b) = (v1.intern("a"), v1.intern("b"));
+        let lines: Vec<String> = v1.records.iter().map(|r| r.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "S\ta\tACGT".to_string(),
+                "S\tb\tACGT".to_string(),
+                format!("L\t{a}\t+\t{b}\t-\t2M"),
+                format!("C\t{a}\t+\t{b}\t+\t0\t4M"),
+                "P\tp1\t1+,2-\t2M".to_string(),
+                "W\tsample\t1\tchr1\t0\t8\t>1<2".to_string(),
+                format!("J\t{a}\t+\t{b}\t-\t10"),
+            ]
+        );
+
+        let (a, b) = (v2.intern("a"), v2.intern("b"));
+        let lines: Vec<String> = v2.records.iter().map(|r| r.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "H\tVN:Z:2.0".to_string(),
+                "S\ta\t4\tACGT".to_string(),
+                "S\tb\t4\tACGT".to_string(),
+                "F\ta\tread+\t0\t4$\t0\t4\t4M".to_string(),
+                format!("E\te1\t{a}+\t{b}-\t2\t4$\t0\t2\t2M"),
+                format!("G\tg1\t{a}+\t{b}-\t10\t*"),
+                "O\to1\ta+ b-".to_string(),
+                "U\tu1\ta b".to_string(),
+            ]
+        );
+    }
+}
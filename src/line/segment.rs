@@ -2,8 +2,11 @@ use crate::errors::ParseMessage;
 use crate::errors::ParseMessageCode;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
+use crate::line::bridge::BridgeRef;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::column_of;
 use crate::line::utils::is_valid_name;
+use crate::line::utils::required_columns;
 use crate::optional_field::OptionalFieldValue;
 use crate::optional_field::TagMap;
 
@@ -17,6 +20,9 @@ pub struct Segment {
     pub sequence: String,
 
     pub length: Option<i32>,
+    // the LN tag, read once when the segment is parsed or built so that get_length
+    // doesn't have to look it up. set_ln_tag keeps the two in sync
+    ln_length: Option<i32>,
 
     pub outgoing_links: Vec<usize>,
     pub incoming_links: Vec<usize>,
@@ -31,6 +37,36 @@ pub struct Segment {
     pub fragments: Vec<usize>,
 }
 
+/// One of the two ends of a segment. The left side is where the forward
+/// strand begins, the right side is where it ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SegmentSide {
+    Left,
+    Right,
+}
+
+impl std::fmt::Display for SegmentSide {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SegmentSide::Left => write!(f, "left"),
+            SegmentSide::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// Where [`Segment::get_length`] takes the length of a segment from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthSource {
+    /// The length column of a v2 segment.
+    V2Column,
+    /// The `LN` tag.
+    LnTag,
+    /// The length of the sequence.
+    Sequence,
+    /// None of the above, so the length is only a default.
+    Defaulted,
+}
+
 impl Default for Segment {
     fn default() -> Self {
         Self {
@@ -41,6 +77,7 @@ impl Default for Segment {
             name: "Segment".to_string(),
             sequence: "*".to_string(),
             length: None,
+            ln_length: None,
 
             outgoing_links: vec![],
             incoming_links: vec![],
@@ -56,8 +93,62 @@ impl Default for Segment {
         }
     }
 }
-    
+
+/// Builder for [`Segment`], see [`Segment::builder`].
+#[derive(Debug, Clone)]
+pub struct SegmentBuilder {
+    segment: Segment,
+}
+
+impl SegmentBuilder {
+    pub fn sequence(mut self, sequence: &str) -> Self {
+        self.segment.sequence = sequence.to_string();
+        self
+    }
+
+    /// Sets the v2 length column.
+    pub fn length(mut self, length: i32) -> Self {
+        self.segment.length = Some(length);
+        self
+    }
+
+    pub fn tag(mut self, tag: &str, value: OptionalFieldValue) -> Self {
+        self.segment.tags.add_tag(tag, value);
+        self.segment.ln_length = self.segment.tags.get::<i32>("LN");
+        self
+    }
+
+    pub fn build(self) -> Segment {
+        self.segment
+    }
+}
+
 impl Segment {
+    /// Starts building a segment with no sequence (`*`) and no bridges.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::line::segment::Segment;
+    ///
+    /// let segment = Segment::builder("s1").sequence("ATCG").length(4).build();
+    /// assert_eq!(segment.get_length(), 4);
+    /// ```
+    pub fn builder(name: &str) -> SegmentBuilder {
+        SegmentBuilder {
+            segment: Segment {
+                name: name.to_string(),
+                sequence: "*".to_string(),
+                ..Segment::default()
+            },
+        }
+    }
+
+    /// True if the parser created this segment in place of a missing one, see
+    /// [`crate::gfa::MissingSegmentOptions::CreateGhost`].
+    pub fn is_ghost(&self) -> bool {
+        self.tags.has_flag("ghost")
+    }
+
     pub fn parse_line(
         (gfa, parts, raw, n, map, options): (
             &mut GfaParser,
@@ -67,32 +158,64 @@ impl Segment {
             &mut TagMap,
             &crate::gfa::ParseOptions,
         ),
+    ) -> (Option<Self>, Vec<ParseMessage>) {
+        let (segment, errors) = Self::pre_parse(parts, raw, n, map, options, &gfa.version);
+
+        let segment = segment.map(|mut s| {
+            s.name = gfa.ensure_name_unique(n, s.name);
+            s
+        });
+
+        (segment, errors)
+    }
+
+    /// Parses and validates everything except the name, which is kept as written
+    /// and still has to be run through [`GfaParser::ensure_name_unique`].
+    /// This does not touch the parser, so segment lines can be pre-parsed in parallel.
+    pub(crate) fn pre_parse(
+        parts: &[&str],
+        raw: &str,
+        n: usize,
+        map: &mut TagMap,
+        options: &crate::gfa::ParseOptions,
+        version: &GFAVersion,
     ) -> (Option<Self>, Vec<ParseMessage>) {
         let mut errors = vec![];
 
-        if !is_valid_name(parts[1]) {
+        // v2 has a length column before the sequence
+        let columns = if *version == GFAVersion::V2 {
+            required_columns(parts, n).map(|[name, length, sequence]| (name, length, sequence))
+        } else {
+            required_columns(parts, n).map(|[name, sequence]| (name, "", sequence))
+        };
+
+        let (name, length_column, sequence_column) = match columns {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
+        if !is_valid_name(name) {
             errors.push(ParseMessage::new(
                 n,
                 ParseMessageCode::InvalidID,
-                parts[1].to_owned(),
+                name.to_owned(),
             ));
-            
+
             return (None, errors);
         }
 
-        let name = &gfa.ensure_name_unique(n, parts[1].to_owned());
         let ln_tag = map.get::<i32>("LN");
 
         let mut length = None;
         let sequence;
 
-        let version: GFAVersion = gfa.version.clone();
-        
+        let version: GFAVersion = version.clone();
+
         if version == GFAVersion::V2 {
-            sequence = parts[3].to_owned(); // col 3 in v2
+            sequence = sequence_column.to_owned(); // col 3 in v2
 
             // attempt to parse the length from the second column
-            length = Some(parts[2].parse::<i32>().unwrap_or_else(|_| {
+            length = Some(length_column.parse::<i32>().unwrap_or_else(|_| {
                 errors.push(ParseMessage::new(
                     n,
                     ParseMessageCode::InvalidSequenceLength,
@@ -102,100 +225,138 @@ impl Segment {
                 // fallback to the length of the sequence
                 sequence.len() as i32
             }));
+        } else {
+            // GFAVersion::V1
+            sequence = sequence_column.to_owned(); // col 2 in v1
+        }
 
+        let sequence_idx = if version == GFAVersion::V2 { 3 } else { 2 };
+
+        let mut segment = Self {
+            line_no: n,
+            raw: raw.to_owned(),
+            tags: map.clone(),
+
+            name: name.to_string(),
+            sequence,
+            length,
+            ln_length: ln_tag,
+
+            outgoing_links: vec![],
+            incoming_links: vec![],
+            containments: vec![],
+            contained_by: vec![],
+            outgoing_jumps: vec![],
+            incoming_jumps: vec![],
+            outgoing_edges: vec![],
+            incoming_edges: vec![],
+            outgoing_gaps: vec![],
+            incoming_gaps: vec![],
+            fragments: vec![],
+        };
+
+        errors.extend(segment.check(n, raw, Some(column_of(parts, sequence_idx)), options));
+
+        if !options.store_sequences {
+            // if we're not storing sequences and there's no LN tag,
+            // then create one from the sequence length
+            if ln_tag.is_none() && version != GFAVersion::V2 && segment.sequence != "*" {
+                segment.set_ln_tag(Some(segment.sequence.len() as i32));
+            }
+
+            segment.sequence = "*".to_string();
+        }
+
+        (Some(segment), errors)
+    }
+
+    /// Checks the length and sequence of a segment that has not been added yet: a length
+    /// column makes the LN tag redundant, otherwise the LN tag should match the sequence.
+    /// `sequence_column` is where the sequence starts on the line, if there is one.
+    /// Shared by parsed lines and by records added with [`GfaParser::add_record`].
+    pub(crate) fn check(
+        &self,
+        n: usize,
+        raw: &str,
+        sequence_column: Option<usize>,
+        options: &crate::gfa::ParseOptions,
+    ) -> Vec<ParseMessage> {
+        let mut errors = vec![];
+
+        let ln_tag = self.ln_length;
+        let sequence = self.sequence.as_str();
+
+        if let Some(length) = self.length {
             // you don't need an LN tag in a gfa v2 file
-            if ln_tag.is_some() {
-                if Some(ln_tag) != Some(length) {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::RedundantSegmentLengthTagMismatch,
-                        raw.to_owned(),
-                    ));
+            if let Some(ln_tag) = ln_tag {
+                let code = if ln_tag != length {
+                    ParseMessageCode::RedundantSegmentLengthTagMismatch
                 } else {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::RedundantSegmentLengthTag,
-                        raw.to_owned(),
-                    ));
-                }
+                    ParseMessageCode::RedundantSegmentLengthTag
+                };
+
+                errors.push(ParseMessage::new(n, code, raw.to_owned()));
             }
-        } else { // GFAVersion::V1
-            sequence = parts[2].to_owned(); // col 2 in v1
-
-            if ln_tag.is_some() {
-                // v1 LN tag should probably match sequence length (when not *)
-                if sequence != "*" && ln_tag != Some(sequence.len() as i32) {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::SegmentLengthMismatch,
-                        raw.to_owned(),
-                    ));
-                }
-            } else if sequence == "*" || sequence.is_empty() {
+        } else if ln_tag.is_some() {
+            // v1 LN tag should probably match sequence length (when not *)
+            if sequence != "*" && ln_tag != Some(sequence.len() as i32) {
                 errors.push(ParseMessage::new(
                     n,
-                    ParseMessageCode::IndeterminateSegmentLength,
+                    ParseMessageCode::SegmentLengthMismatch,
                     raw.to_owned(),
                 ));
-            }            
+            }
+        } else if sequence == "*" || sequence.is_empty() {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::IndeterminateSegmentLength,
+                raw.to_owned(),
+            ));
+        }
+
+        // rGFA tags only make sense together
+        let rgfa_tags = ["SN", "SO", "SR"]
+            .iter()
+            .filter(|tag| self.tags.contains(tag))
+            .count();
+
+        if rgfa_tags != 0 && rgfa_tags != 3 {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::IncompleteRGFATags,
+                raw.to_owned(),
+            ));
         }
 
         // check if sequence is valid, this can take a while for large sequences
         // there's probably a faster way to do this
-        if !options.skip_invalid_sequence_test {
-            let bytes = sequence.as_bytes();
-            
+        let bytes = sequence.as_bytes();
+
+        let invalid = if !options.skip_invalid_sequence_test
+            && sequence != "*"
+            && let Some(pos) = bytes.iter().position(|b| !(b'!'..=b'~').contains(b))
+        {
             // the sequence must match * or [!-~]+
-            if !(bytes.len() == 1 && bytes[0] == b'*')
-                && bytes.iter().any(|&b| b < b'!' || b > b'~')
-            {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidSequence,
-                    raw.to_owned(),
-                ));
-            }
-        }
+            Some((ParseMessageCode::InvalidSequence, pos))
+        } else if options.strict_nucleotide_alphabet
+            && sequence != "*"
+            && let Some(pos) = bytes.iter().position(|b| !is_nucleotide_code(*b))
+        {
+            Some((ParseMessageCode::NonNucleotideSequence, pos))
+        } else {
+            None
+        };
 
-        if !options.store_sequences {
-            // if we're not storing sequences and there's no LN tag,
-            // then create one from the sequence length
-            if ln_tag.is_none() && version != GFAVersion::V2 && sequence != "*" {
-                map.0.insert(
-                    "LN".to_string(),
-                    OptionalFieldValue::Int(sequence.len() as i32),
-                );
-            }
-        }
+        if let Some((code, pos)) = invalid {
+            let message = ParseMessage::new(n, code, raw.to_owned());
 
-        (
-            Some(Self {
-                line_no: n,
-                raw: raw.to_owned(),
-                tags: map.clone(),
+            errors.push(match sequence_column {
+                Some(column) => message.with_column(column + pos),
+                None => message,
+            });
+        }
 
-                name: name.to_string(),
-                sequence: if options.store_sequences {
-                    sequence.to_owned()
-                } else {
-                    "*".to_string()
-                },
-                length,
-
-                outgoing_links: vec![],
-                incoming_links: vec![],
-                containments: vec![],
-                contained_by: vec![],
-                outgoing_jumps: vec![],
-                incoming_jumps: vec![],
-                outgoing_edges: vec![],
-                incoming_edges: vec![],
-                outgoing_gaps: vec![],
-                incoming_gaps: vec![],
-                fragments: vec![],
-            }),
-            errors,
-        )
+        errors
     }
 
     pub fn get_length(&self) -> i32 {
@@ -205,10 +366,10 @@ impl Segment {
         // 3. sequence length (if not *)
         // otherwise, 0
 
-        if self.length.is_some() {
-            self.length.unwrap()
-        } else if self.tags.get::<i32>("LN").is_some() {
-            self.tags.get::<i32>("LN").unwrap()
+        if let Some(length) = self.length {
+            length
+        } else if let Some(length) = self.ln_length {
+            length
         } else if self.sequence != "*" && !self.sequence.is_empty() {
             // if this overflows it will be the funniest thing ever
             self.sequence.len() as i32
@@ -217,13 +378,56 @@ impl Segment {
         }
     }
 
+    /// Tells where [`Segment::get_length`] takes the length from, in the same order of
+    /// priority. A [`LengthSource::Defaulted`] length is a guess, not a real length.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::line::segment::{LengthSource, Segment};
+    ///
+    /// let segment = Segment::builder("s1").build();
+    /// assert_eq!(segment.length_source(), LengthSource::Defaulted);
+    ///
+    /// let segment = Segment::builder("s1").sequence("ATCG").build();
+    /// assert_eq!(segment.length_source(), LengthSource::Sequence);
+    /// ```
+    pub fn length_source(&self) -> LengthSource {
+        if self.length.is_some() {
+            LengthSource::V2Column
+        } else if self.ln_length.is_some() {
+            LengthSource::LnTag
+        } else if self.sequence != "*" && !self.sequence.is_empty() {
+            LengthSource::Sequence
+        } else {
+            LengthSource::Defaulted
+        }
+    }
+
+    /// The length in the `LN` tag, as of when the segment was parsed or built, or the tag
+    /// was last set with [`Segment::set_ln_tag`].
+    pub fn ln_length(&self) -> Option<i32> {
+        self.ln_length
+    }
+
+    /// Sets the `LN` tag, or removes it with `None`, along with [`Segment::ln_length`].
+    pub fn set_ln_tag(&mut self, length: Option<i32>) {
+        match length {
+            Some(length) => self.tags.add_tag("LN", OptionalFieldValue::Int(length)),
+            None => {
+                self.tags.tags.remove("LN");
+            }
+        }
+
+        self.ln_length = length;
+    }
+
     pub fn get_outgoing_bridges(&self) -> Vec<usize> {
         let mut bridges = vec![];
         bridges.extend(self.outgoing_links.iter());
         bridges.extend(self.outgoing_jumps.iter());
         bridges.extend(self.outgoing_edges.iter());
         bridges.extend(self.outgoing_gaps.iter());
-        
+
         // i say we include containments since v2 edges
         // basically generalise them anyway
         bridges.extend(self.containments.iter());
@@ -241,7 +445,80 @@ impl Segment {
         bridges.extend(self.contained_by.iter());
         bridges
     }
-    
+
+    /// rGFA reference coordinate of the segment as (stable name, offset, rank),
+    /// if all of the SN, SO and SR tags are present.
+    pub fn rgfa_coordinate(&self) -> Option<(String, i32, i32)> {
+        Some((
+            self.tags.get::<String>("SN")?,
+            self.tags.get::<i32>("SO")?,
+            self.tags.get::<i32>("SR")?,
+        ))
+    }
+
+    /// Number of outgoing links, jumps and edges.
+    pub fn out_degree(&self) -> usize {
+        self.outgoing_links.len() + self.outgoing_jumps.len() + self.outgoing_edges.len()
+    }
+
+    /// Number of incoming links, jumps and edges.
+    pub fn in_degree(&self) -> usize {
+        self.incoming_links.len() + self.incoming_jumps.len() + self.incoming_edges.len()
+    }
+
+    /// Total degree of the segment. Gaps and containments are only counted
+    /// when `include_gaps_and_containments` is set, since neither of them is
+    /// an actual adjacency in the graph.
+    pub fn degree(&self, include_gaps_and_containments: bool) -> usize {
+        let mut degree = self.out_degree() + self.in_degree();
+
+        if include_gaps_and_containments {
+            degree += self.outgoing_gaps.len() + self.incoming_gaps.len();
+            degree += self.containments.len() + self.contained_by.len();
+        }
+
+        degree
+    }
+
+    /// Number of bridges attached to the left side of the segment: outgoing bridges
+    /// that leave it in reverse, and incoming bridges that enter it forwards.
+    ///
+    /// Counts the same bridges as [`Segment::get_outgoing_bridges`] and
+    /// [`Segment::get_incoming_bridges`].
+    pub fn left_degree(&self, gfa: &GfaParser) -> usize {
+        self.side_degree(gfa, SegmentSide::Left)
+    }
+
+    /// Number of bridges attached to the right side of the segment: outgoing bridges
+    /// that leave it forwards, and incoming bridges that enter it in reverse.
+    pub fn right_degree(&self, gfa: &GfaParser) -> usize {
+        self.side_degree(gfa, SegmentSide::Right)
+    }
+
+    fn side_degree(&self, gfa: &GfaParser, side: SegmentSide) -> usize {
+        let right = side == SegmentSide::Right;
+        let bridge = |n: &usize| {
+            gfa.find_record(*n)
+                .and_then(|r| BridgeRef::from_record(r, gfa))
+        };
+
+        let outgoing = self
+            .get_outgoing_bridges()
+            .iter()
+            .filter_map(bridge)
+            .filter(|b| b.from_orientation == right)
+            .count();
+
+        let incoming = self
+            .get_incoming_bridges()
+            .iter()
+            .filter_map(bridge)
+            .filter(|b| b.to_orientation != right)
+            .count();
+
+        outgoing + incoming
+    }
+
     pub fn to_raw_line(&self, version: GFAVersion) -> String {
         match version {
             GFAVersion::V2 => self.to_raw_line_v2(),
@@ -252,12 +529,8 @@ impl Segment {
     fn to_raw_line_v1(&self) -> String {
         let name = self.name.as_str();
         let sequence = self.sequence.as_str();
-        
-        build_gfa_line(
-            'S',
-            &[name, sequence],
-            &self.tags,
-        )
+
+        build_gfa_line('S', &[name, sequence], &self.tags)
     }
 
     fn to_raw_line_v2(&self) -> String {
@@ -267,11 +540,30 @@ impl Segment {
         // use get_length over self.length for v1 -> v2 conversions
         let length = self.get_length().to_string();
 
-        build_gfa_line(
-            'S',
-            &[name, &length, sequence],
-            &self.tags,
-        )
+        build_gfa_line('S', &[name, &length, sequence], &self.tags)
     }
 }
 
+// IUPAC nucleotide codes, plus the gap characters
+fn is_nucleotide_code(base: u8) -> bool {
+    matches!(
+        base.to_ascii_uppercase(),
+        b'A' | b'C'
+            | b'G'
+            | b'T'
+            | b'U'
+            | b'R'
+            | b'Y'
+            | b'K'
+            | b'M'
+            | b'S'
+            | b'W'
+            | b'B'
+            | b'D'
+            | b'H'
+            | b'V'
+            | b'N'
+            | b'.'
+            | b'-'
+    )
+}
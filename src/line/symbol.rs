@@ -0,0 +1,65 @@
+use std::{collections::HashMap, fmt};
+
+/// A segment name interned by a [`crate::gfa::GfaParser`].
+///
+/// Links, jumps, containments, edges and gaps refer to their segments by symbol, so a
+/// name that is referenced thousands of times is only stored once. Use
+/// [`crate::gfa::GfaParser::resolve`] to get the name back, and
+/// [`crate::gfa::GfaParser::intern`] to get the symbol of a name.
+///
+/// Symbols are only meaningful to the parser that handed them out, which is why they
+/// can't be built from a raw id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// Writes the raw number, for when there's no parser at hand to resolve the symbol.
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Append-only table of interned names. Names are never removed, so a symbol stays
+/// valid even after the record it was taken from is gone.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct SymbolTable {
+    names: Vec<String>,
+    symbols: HashMap<String, Symbol>,
+}
+
+impl SymbolTable {
+    pub(crate) fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(symbol) = self.symbols.get(name) {
+            return *symbol;
+        }
+
+        let symbol = Symbol(self.names.len() as u32);
+        self.names.push(name.to_string());
+        self.symbols.insert(name.to_string(), symbol);
+
+        symbol
+    }
+
+    // symbols from another table resolve to an empty name rather than panicking
+    pub(crate) fn resolve(&self, symbol: Symbol) -> &str {
+        self.names.get(symbol.0 as usize).map_or("", String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Symbol, SymbolTable};
+
+    #[test]
+    fn intern_once() {
+        let mut table = SymbolTable::default();
+
+        let a = table.intern("a");
+        let b = table.intern("b");
+
+        assert_ne!(a, b);
+        assert_eq!(table.intern("a"), a);
+        assert_eq!(table.resolve(b), "b");
+        assert_eq!(table.resolve(Symbol(7)), "");
+    }
+}
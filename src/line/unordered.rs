@@ -1,11 +1,15 @@
+use std::collections::{BTreeSet, HashSet};
+
 use crate::errors::ParseMessage;
 use crate::gfa::GFAVersion;
 use crate::gfa::GfaParser;
 use crate::gfa::ParseOptions;
-use crate::line::group::parse_generic_group;
 use crate::line::group::GroupParts;
 use crate::line::group::GroupType;
+use crate::line::group::expand_members;
+use crate::line::group::parse_generic_group;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::required_columns;
 use crate::optional_field::TagMap;
 
 #[derive(Debug, Clone, Default)]
@@ -31,12 +35,17 @@ impl UnorderedGroup {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
+        let [name, members] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
         let (unordered_as_group, errors) = parse_generic_group(
             gfa,
             GroupParts {
                 group_type: GroupType::UnorderedGroup,
-                name: parts[1],
-                members: parts[2],
+                name,
+                members,
             },
             n,
             options,
@@ -76,22 +85,158 @@ impl UnorderedGroup {
     fn to_raw_line_v2(&self) -> String {
         let members_str = self.members.join(" ");
         let parts = vec![self.name.as_str(), members_str.as_str()];
-        
+
         build_gfa_line('U', &parts, &self.tags)
     }
 
-    pub fn derive_group(&self, _: &GfaParser) -> Vec<String> {
-        let members = vec![];
+    /// The member names without any orientation, which has no meaning in an unordered group.
+    pub fn members_as_names(&self) -> Vec<&str> {
+        self.members
+            .iter()
+            .map(|m| m.trim_end_matches(['+', '-']))
+            .collect()
+    }
+
+    /// Expands the group to the segments its members stand for: segments are kept, paths
+    /// contribute the segments of their steps, and nested ordered and unordered groups are
+    /// expanded transitively. The result is deduplicated and in the order the segments
+    /// are first reached.
+    ///
+    /// A group that contains itself, directly or through other groups, is only expanded
+    /// once. Unlike [`UnorderedGroup::derive_group`], this doesn't add any segments that
+    /// aren't referenced by the group.
+    pub fn expand_group(&self, gfa: &GfaParser) -> Vec<String> {
+        let mut visited = HashSet::from([self.name.clone()]);
+        let mut segments = vec![];
+
+        expand_members(gfa, &self.members, &mut visited, &mut segments);
+
+        segments
+    }
+
+    /// Expands the group to every segment that lies on a path between two of its members,
+    /// following links, jumps and edges in either orientation.
+    ///
+    /// Only paths of at most `max_hops` bridges are considered, which keeps the search
+    /// bounded on large graphs. For k member segments this runs a bounded BFS forwards
+    /// and backwards from each member, O(k * (n + m)), then pairs up the distances of
+    /// every visited node, O(k^2) per node.
+    ///
+    /// The original members (without orientation) come first, followed by the derived
+    /// segments in line order. Members that are not segments are kept but not expanded.
+    pub fn derive_group(&self, gfa: &GfaParser, max_hops: usize) -> Vec<String> {
+        let mut members: Vec<String> = vec![];
+
+        for member in &self.members {
+            let name = member.trim_end_matches(['+', '-']);
+
+            if !members.iter().any(|m| m == name) {
+                members.push(name.to_string());
+            }
+        }
 
-        // TODO: implement unordered group expansion
-        // logic is basically just:
-        // - store all members in a set
-        // - iterate over the set and for each member, check against all the other members
-        //   - find everything in between the two members
-        //   - i.e. two segments is everything in between them
+        let member_nos: Vec<usize> = members
+            .iter()
+            .filter_map(|m| gfa.segment_line_no(m))
+            .collect();
 
-        // my naive implementation would be O(forever) so i'm not going to bother until someone requests it
+        let forward: Vec<_> = member_nos
+            .iter()
+            .map(|n| gfa.hop_distances(&[(*n, true), (*n, false)], max_hops, true))
+            .collect();
+        let backward: Vec<_> = member_nos
+            .iter()
+            .map(|n| gfa.hop_distances(&[(*n, true), (*n, false)], max_hops, false))
+            .collect();
+
+        let mut derived: BTreeSet<usize> = BTreeSet::new();
+
+        for (a, from_a) in forward.iter().enumerate() {
+            for (node, to_node) in from_a {
+                if member_nos.contains(&node.0) || derived.contains(&node.0) {
+                    continue;
+                }
+
+                let between = backward.iter().enumerate().any(|(b, to_b)| {
+                    a != b && to_b.get(node).is_some_and(|d| to_node + d <= max_hops)
+                });
+
+                if between {
+                    derived.insert(node.0);
+                }
+            }
+        }
+
+        members.extend(
+            derived
+                .into_iter()
+                .filter_map(|n| gfa.find_segment(n).map(|s| s.name.clone())),
+        );
 
         members
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::gfa::{GfaParser, ParseOptions};
+
+    #[test]
+    fn derive_group_between_members() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "S\ta\tA",
+            "S\tb\tA",
+            "S\tc\tA",
+            "S\td\tA",
+            "S\te\tA",
+            "L\ta\t+\tb\t+\t0M",
+            "L\tb\t+\tc\t+\t0M",
+            "L\td\t-\tc\t-\t0M",
+            "L\td\t+\te\t+\t0M",
+            "U\tu1\ta d",
+        ] {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let group = gfa.unordered_groups().next().unwrap();
+
+        // a+ b+ c+ then c+ -> d+ through the reverse of d- -> c-
+        assert_eq!(group.derive_group(&gfa, 3), ["a", "d", "b", "c"]);
+        assert_eq!(group.derive_group(&gfa, 2), ["a", "d"]);
+    }
+
+    #[test]
+    fn expand_nested_groups() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "S\ta\tA",
+            "S\tb\tA",
+            "S\tc\tA",
+            "S\td\tA",
+            "L\ta\t+\tb\t+\t0M",
+            "P\tp1\tc+,d-\t*",
+            "O\to1\tb+ a-",
+            "O\to2\tp1+ a+",
+            "U\tu1\to1 o2 u2",
+            "U\tu2\tu1 d+",
+        ] {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let mut groups = gfa.unordered_groups();
+        let u1 = groups.next().unwrap();
+        let u2 = groups.next().unwrap();
+
+        assert_eq!(u1.members_as_names(), ["o1", "o2", "u2"]);
+        assert_eq!(u1.expand_group(&gfa), ["b", "a", "c", "d"]);
+        assert_eq!(u2.members_as_names(), ["u1", "d"]);
+        assert_eq!(u2.expand_group(&gfa), ["b", "a", "c", "d"]);
+    }
+}
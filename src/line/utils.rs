@@ -1,5 +1,6 @@
 use crate::{
     errors::{ParseMessage, ParseMessageCode},
+    gfa::GfaParser,
     line::segment::Segment,
     optional_field::TagMap,
 };
@@ -81,6 +82,22 @@ pub fn deduce_alignment(alignment: &str) -> Result<Option<Alignment>, ParseMessa
     }
 }
 
+// trace points can't be placed without the spacing from the header
+pub(crate) fn check_trace_spacing(
+    gfa: &GfaParser,
+    alignment: Option<&Alignment>,
+    n: usize,
+) -> Option<ParseMessage> {
+    match alignment {
+        Some(Alignment::Trace(trace)) if gfa.trace.is_none() => Some(ParseMessage::new(
+            n,
+            ParseMessageCode::MissingTraceSpacing,
+            trace.to_owned(),
+        )),
+        _ => None,
+    }
+}
+
 impl std::fmt::Display for Alignment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -96,6 +113,26 @@ pub enum Alignment {
     Trace(String),
 }
 
+impl Alignment {
+    /// The trace points of a trace alignment, i.e. the number of bases of the other
+    /// sequence covered by each trace spacing (`TS`) interval. `None` for a CIGAR.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::line::utils::Alignment;
+    ///
+    /// let trace = Alignment::Trace("12,-3,14".to_string());
+    /// assert_eq!(trace.trace_points(), Some(vec![12, -3, 14]));
+    /// assert_eq!(Alignment::CIGAR("4M".to_string()).trace_points(), None);
+    /// ```
+    pub fn trace_points(&self) -> Option<Vec<i32>> {
+        match self {
+            Alignment::Trace(trace) => trace.split(',').map(|p| p.parse().ok()).collect(),
+            Alignment::CIGAR(_) => None,
+        }
+    }
+}
+
 pub fn parse_directed_reference(reference: &str) -> Result<DirectedReference, ParseMessage> {
     // check if last char is + or -
     let last_char = reference.chars().last().ok_or_else(|| {
@@ -133,6 +170,7 @@ pub fn parse_directed_reference(reference: &str) -> Result<DirectedReference, Pa
     })
 }
 
+#[allow(clippy::result_unit_err)]
 pub fn parse_interval(
     n: usize,
     errors: &mut Vec<ParseMessage>,
@@ -161,7 +199,7 @@ pub fn parse_interval(
     Ok(interval)
 }
 
-fn check_interval(
+pub(crate) fn check_interval(
     n: usize,
     errors: &mut Vec<ParseMessage>,
     interval: &Interval,
@@ -240,6 +278,7 @@ fn check_interval(
 }
 
 #[inline]
+#[allow(clippy::result_unit_err)]
 pub fn parse_position(
     n: usize,
     errors: &mut Vec<ParseMessage>,
@@ -317,6 +356,98 @@ pub fn is_valid_cigar(cigar: &str) -> bool {
     true
 }
 
+/// Splits a CIGAR string into its operations, as `(length, operation)` pairs in order.
+///
+/// Rejects the same strings as [`is_valid_cigar`] (including `*`), and lengths that don't
+/// fit in a `u32`, with an `InvalidCIGAR` message on line 0. An empty string has no operations.
+///
+/// Example:
+/// ```
+/// use parfait_gfa::line::utils::parse_cigar;
+///
+/// assert_eq!(parse_cigar("10M2I").unwrap(), [(10, 'M'), (2, 'I')]);
+/// assert!(parse_cigar("*").is_err());
+/// ```
+pub fn parse_cigar(cigar: &str) -> Result<Vec<(u32, char)>, ParseMessage> {
+    let invalid = || ParseMessage::new(0, ParseMessageCode::InvalidCIGAR, cigar.to_string());
+
+    if !is_valid_cigar(cigar) {
+        return Err(invalid());
+    }
+
+    let mut operations = Vec::new();
+    let mut count: u32 = 0;
+
+    for &b in cigar.as_bytes() {
+        if b.is_ascii_digit() {
+            count = count
+                .checked_mul(10)
+                .and_then(|c| c.checked_add((b - b'0') as u32))
+                .ok_or_else(invalid)?;
+            continue;
+        }
+
+        operations.push((count, b as char));
+        count = 0;
+    }
+
+    Ok(operations)
+}
+
+/// Sums the lengths of the CIGAR operations that consume the reference (`M`, `D`, `N`, `=`, `X`).
+/// Returns `None` if the CIGAR is invalid (including `*`).
+pub fn cigar_reference_length(cigar: &str) -> Option<u32> {
+    cigar_length(cigar, b"MDN=X")
+}
+
+/// Sums the lengths of the CIGAR operations that consume the query (`M`, `I`, `S`, `=`, `X`).
+/// Returns `None` if the CIGAR is invalid (including `*`).
+pub fn cigar_query_length(cigar: &str) -> Option<u32> {
+    cigar_length(cigar, b"MIS=X")
+}
+
+fn cigar_length(cigar: &str, consuming: &[u8]) -> Option<u32> {
+    if !is_valid_cigar(cigar) {
+        return None;
+    }
+
+    let mut length: u32 = 0;
+    let mut count: u32 = 0;
+
+    for &b in cigar.as_bytes() {
+        if b.is_ascii_digit() {
+            count = count.checked_mul(10)?.checked_add((b - b'0') as u32)?;
+            continue;
+        }
+
+        if consuming.contains(&b) {
+            length = length.checked_add(count)?;
+        }
+
+        count = 0;
+    }
+
+    Some(length)
+}
+
+/// 1-based byte column at which `parts[idx]` starts in a tab-separated line.
+pub fn column_of(parts: &[&str], idx: usize) -> usize {
+    parts.iter().take(idx).map(|p| p.len() + 1).sum::<usize>() + 1
+}
+
+/// The `N` columns that follow the record type, or an `InvalidLine` message if the line
+/// is too short. Record parsers take their required columns through this instead of
+/// indexing `parts`, so a truncated line can't cause a panic.
+pub fn required_columns<'a, const N: usize>(
+    parts: &[&'a str],
+    n: usize,
+) -> Result<[&'a str; N], ParseMessage> {
+    parts
+        .get(1..=N)
+        .and_then(|columns| columns.try_into().ok())
+        .ok_or_else(|| ParseMessage::new(n, ParseMessageCode::InvalidLine, parts.join("\t")))
+}
+
 // TODO: profile these inlines
 #[inline]
 pub fn build_gfa_line(record_type: char, columns: &[&str], tags: &TagMap) -> String {
@@ -326,7 +457,7 @@ pub fn build_gfa_line(record_type: char, columns: &[&str], tags: &TagMap) -> Str
         line.push('\t');
         line.push_str(col);
     }
-    tags.0.iter().for_each(|(tag, value)| {
+    tags.tags.iter().for_each(|(tag, value)| {
         line.push('\t');
         line.push_str(tag);
         line.push(':');
@@ -336,3 +467,34 @@ pub fn build_gfa_line(record_type: char, columns: &[&str], tags: &TagMap) -> Str
     });
     line
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{cigar_query_length, cigar_reference_length, parse_cigar};
+    use crate::errors::ParseMessageCode;
+
+    #[test]
+    fn cigar_lengths() {
+        assert_eq!(cigar_reference_length("10M2I3D1="), Some(14));
+        assert_eq!(cigar_query_length("10M2I3D1="), Some(13));
+        assert_eq!(cigar_reference_length("5S4M"), Some(4));
+        assert_eq!(cigar_query_length("5S4M"), Some(9));
+        assert_eq!(cigar_reference_length("*"), None);
+        assert_eq!(cigar_query_length("4Q"), None);
+    }
+
+    #[test]
+    fn cigar_operations() {
+        assert_eq!(
+            parse_cigar("10M2I3D1=").unwrap(),
+            [(10, 'M'), (2, 'I'), (3, 'D'), (1, '=')]
+        );
+        assert!(parse_cigar("").unwrap().is_empty());
+
+        for invalid in ["*", "M", "4Q", "10", "99999999999M"] {
+            let err = parse_cigar(invalid).unwrap_err();
+            assert_eq!(err.code, ParseMessageCode::InvalidCIGAR);
+            assert_eq!(err.offender, invalid);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use crate::gfa::GFAVersion;
 use crate::gfa::MissingBridgeOptions;
 use crate::gfa::ParseOptions;
+use crate::line::path::Path;
 use crate::line::path::Step;
+use crate::line::path::missing_segment;
 
 use crate::errors::ParseMessageCode;
 
@@ -9,6 +11,7 @@ use crate::errors::ParseMessage;
 
 use crate::gfa::GfaParser;
 use crate::line::utils::build_gfa_line;
+use crate::line::utils::required_columns;
 use crate::optional_field::TagMap;
 
 use crate::gfa::MissingSegmentOptions;
@@ -25,6 +28,9 @@ pub struct Walk {
     pub seq_start: Option<u32>,
     pub seq_end: Option<u32>,
     pub walk: Vec<Step>,
+    /// Steps whose segment doesn't exist, as `(step index, segment name)`. These steps
+    /// have a `segment_id` of 0, see [`MissingSegmentOptions::Ignore`].
+    pub missing_segments: Vec<(usize, String)>,
 }
 
 pub static REQ_COLUMNS_WALK: usize = 7;
@@ -40,55 +46,51 @@ impl Walk {
             &ParseOptions,
         ),
     ) -> (Option<Self>, Vec<ParseMessage>) {
+        let [sample, hap, seq, start, end, walk_str] = match required_columns(parts, n) {
+            Ok(columns) => columns,
+            Err(e) => return (None, vec![e]),
+        };
+
         let mut errors = vec![];
 
-        let sample_id = parts.get(1).unwrap_or(&"").to_string();
+        let sample_id = sample.to_string();
 
-        let hap_index = parts
-            .get(2)
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or_else(|| {
-                errors.push(ParseMessage::new(
-                    n,
-                    ParseMessageCode::InvalidHaplotypeIndex,
-                    parts.get(2).unwrap_or(&"").to_string(),
-                ));
-                0
-            });
+        let hap_index = hap.parse::<u32>().unwrap_or_else(|_| {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::InvalidHaplotypeIndex,
+                hap.to_string(),
+            ));
+            0
+        });
 
-        let seq_id: String = parts.get(3).unwrap_or(&"").to_string();
+        let seq_id: String = seq.to_string();
 
         let mut seq_start_is_asterisk = false;
-        let seq_start = parts
-            .get(4)
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or_else(|| {
-                if parts.get(4).expect("col count checked in record.rs") != &"*" {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::InvalidSequenceStart,
-                        parts.get(4).unwrap_or(&"").to_string(),
-                    ));
-                }
-                seq_start_is_asterisk = true;
-                0
-            });
+        let seq_start = start.parse::<u32>().unwrap_or_else(|_| {
+            if start != "*" {
+                errors.push(ParseMessage::new(
+                    n,
+                    ParseMessageCode::InvalidSequenceStart,
+                    start.to_string(),
+                ));
+            }
+            seq_start_is_asterisk = true;
+            0
+        });
 
         let mut seq_end_is_asterisk = false;
-        let seq_end = parts
-            .get(5)
-            .and_then(|s| s.parse::<u32>().ok())
-            .unwrap_or_else(|| {
-                if parts.get(5).expect("col count checked in record.rs") != &"*" {
-                    errors.push(ParseMessage::new(
-                        n,
-                        ParseMessageCode::InvalidSequenceEnd,
-                        parts.get(5).unwrap_or(&"").to_string(),
-                    ));
-                }
-                seq_end_is_asterisk = true;
-                0
-            });
+        let seq_end = end.parse::<u32>().unwrap_or_else(|_| {
+            if end != "*" {
+                errors.push(ParseMessage::new(
+                    n,
+                    ParseMessageCode::InvalidSequenceEnd,
+                    end.to_string(),
+                ));
+            }
+            seq_end_is_asterisk = true;
+            0
+        });
 
         if seq_start > seq_end {
             errors.push(ParseMessage::new(
@@ -100,34 +102,28 @@ impl Walk {
 
         // records with the same sample_id, hap_index, and seq_id are allowed
         // but their seq_start and seq_end must not overlap
-        gfa.walks().for_each(|walk| {
-            if walk.sample_id == sample_id
-                && walk.hap_index == hap_index
-                && walk.seq_id == seq_id
-            {
-                if let Some(existing_start) = walk.seq_start {
-                    if let Some(existing_end) = walk.seq_end {
-                        if (seq_start <= existing_end && seq_end >= existing_start) ||
-                            (existing_start <= seq_end && existing_end >= seq_start) {
-                            errors.push(ParseMessage::new(
-                                n,
-                                ParseMessageCode::OverlappingWalkRange,
-                                format!(
-                                    "{}/{}/{} with range {}..{} overlaps with {}..{} on line {}",
-                                    sample_id, hap_index, seq_id,
-                                    seq_start, seq_end,
-                                    existing_start, existing_end,
-                                    walk.line_no
-                                ),
-                            ));
-                        }
-                    }
-                }
-            }
-        });
+        for (existing_start, existing_end, line_no) in
+            gfa.overlapping_walk_ranges((&sample_id, hap_index, &seq_id), seq_start, seq_end)
+        {
+            errors.push(ParseMessage::new(
+                n,
+                ParseMessageCode::OverlappingWalkRange,
+                format!(
+                    "{}/{}/{} with range {}..{} overlaps with {}..{} on line {}",
+                    sample_id,
+                    hap_index,
+                    seq_id,
+                    seq_start,
+                    seq_end,
+                    existing_start,
+                    existing_end,
+                    line_no
+                ),
+            ));
+        }
 
-        let walk_str = parts.get(6).unwrap_or(&"");
         let mut walk_steps: Vec<Step> = vec![];
+        let mut missing_segments = vec![];
         let mut current_segment_name = vec![];
         let mut curr_step_direction = false;
 
@@ -163,10 +159,10 @@ impl Walk {
                 if !current_segment_name.is_empty() {
                     let segment_name = current_segment_name.iter().collect::<String>();
                     let segment = gfa.find_segment_with_name(&segment_name.clone());
-                    let mut segment_id = 0; // this should always be mutated, i just dont want to use a match block 
+                    let mut segment_id = 0; // this should always be mutated, i just dont want to use a match block
 
-                    if segment.is_some() {
-                        segment_id = segment.unwrap().line_no as u32;
+                    if let Some(segment) = segment {
+                        segment_id = segment.line_no as u32;
                     } else {
                         errors.push(ParseMessage::new(
                             n,
@@ -184,21 +180,34 @@ impl Walk {
                         }
 
                         if options.handle_missing_segment == MissingSegmentOptions::CreateGhost {
-                            let ghost = &gfa.create_ghost_segment(segment_name.clone());
+                            let idx = gfa.ghost_for(&segment_name, options);
 
-                            segment_id = ghost.line_no as u32;
+                            segment_id = gfa.records[idx].line_no() as u32;
                         }
                     }
 
-                    if segment_id != 0 || (segment_id == 0 && options.handle_missing_segment == MissingSegmentOptions::Ignore) {
+                    if segment_id == 0
+                        && options.handle_missing_segment == MissingSegmentOptions::Ignore
+                    {
+                        missing_segments.push((walk_steps.len(), segment_name.clone()));
+                    }
+
+                    if segment_id != 0
+                        || options.handle_missing_segment == MissingSegmentOptions::Ignore
+                    {
                         walk_steps.push(Step {
                             segment_id,
                             orientation: curr_step_direction,
                         });
                     }
 
-                    // compare the last two walk steps and see if a link exists between them         
-                    if walk_steps.len() >= 2 {
+                    // compare the last two walk steps and see if a link exists between them,
+                    // unless one of them has no segment to link
+                    if walk_steps.len() >= 2
+                        && walk_steps[walk_steps.len() - 2..]
+                            .iter()
+                            .all(|s| s.segment_id != 0)
+                    {
                         let this_step = &walk_steps[walk_steps.len() - 1];
                         let last_step = &walk_steps[walk_steps.len() - 2];
 
@@ -232,7 +241,7 @@ impl Walk {
                                     if this_step.orientation { "+" } else { "-" }
                                 ),
                             ));
-                            
+
                             if options.handle_missing_bridge == MissingBridgeOptions::HardSkip {
                                 errors.push(ParseMessage::new(
                                     n,
@@ -242,8 +251,10 @@ impl Walk {
                                 return (None, errors);
                             }
 
-                            if options.handle_missing_bridge == MissingBridgeOptions::CreateGhostLink {
-                                let _ = &gfa.create_ghost_link(
+                            if options.handle_missing_bridge
+                                == MissingBridgeOptions::CreateGhostLink
+                            {
+                                gfa.create_ghost_link(
                                     last_step_name.clone(),
                                     last_step.orientation,
                                     segment_name.clone(),
@@ -290,16 +301,57 @@ impl Walk {
                     Some(seq_end)
                 },
                 walk: walk_steps,
+                missing_segments,
             }),
             errors,
         )
     }
 
+    /// The PanSN name (`sample#hap#contig`) of the haplotype this walk belongs to,
+    /// which is what a path following the same haplotype would be called.
+    /// See [`crate::line::path::Path::pansn_fields`] for the reverse.
+    pub fn to_pansn_path_name(&self) -> String {
+        format!("{}#{}#{}", self.sample_id, self.hap_index, self.seq_id)
+    }
+
+    /// Builds the path following the same steps, named after the haplotype
+    /// (see [`Walk::to_pansn_path_name`]) and with unknown (`*`) overlaps.
+    ///
+    /// The sequence range has no place in a path and is dropped, as are steps that
+    /// don't point at a segment of `gfa`.
+    pub fn to_path(&self, gfa: &GfaParser) -> Path {
+        let steps: Vec<Step> = self
+            .walk
+            .iter()
+            .filter(|s| gfa.find_segment(s.segment_id as usize).is_some())
+            .cloned()
+            .collect();
+
+        Path {
+            line_no: self.line_no,
+            raw: self.raw.clone(),
+            tags: self.tags.clone(),
+
+            name: self.to_pansn_path_name(),
+            overlaps: vec!["*".to_string(); steps.len().saturating_sub(1).max(1)],
+            steps,
+            missing_segments: vec![],
+        }
+    }
+
     pub fn to_raw_line(&self, _: GFAVersion, gfa: &GfaParser) -> String {
         self.to_raw_line_v1(gfa)
     }
 
     fn to_raw_line_v1(&self, gfa: &GfaParser) -> String {
+        self.to_raw_line_with(|segment_id| {
+            gfa.find_segment(segment_id as usize)
+                .map_or_else(|| segment_id.to_string(), |s| s.name.clone())
+        })
+    }
+
+    // the step names are passed in, so that the line can be written without a parser
+    pub(crate) fn to_raw_line_with(&self, segment_name: impl Fn(u32) -> String) -> String {
         let sample_id = &self.sample_id;
         let hap_index = self.hap_index.to_string();
         let seq_id = &self.seq_id;
@@ -314,29 +366,25 @@ impl Walk {
             None => "*".to_string(),
         };
 
-        let walk_str = self.walk
-        .iter()
-        .map(|step| {
-            let step_id = step.segment_id as usize;
-            format!(
-                "{}{}",
-                if step.orientation { '>' } else { '<' },
-                gfa.find_segment(step_id).as_ref().map_or_else(
-                    || step_id.to_string(),
-                    |s| s.name.clone()
+        let walk_str = self
+            .walk
+            .iter()
+            .enumerate()
+            .map(|(idx, step)| {
+                format!(
+                    "{}{}",
+                    if step.orientation { '>' } else { '<' },
+                    missing_segment(&self.missing_segments, idx)
+                        .map_or_else(|| segment_name(step.segment_id), str::to_string)
                 )
-            )
-        }).collect::<Vec<String>>().join("");
+            })
+            .collect::<Vec<String>>()
+            .join("");
 
         build_gfa_line(
             'W',
             &[
-                sample_id,
-                &hap_index,
-                seq_id,
-                &seq_start,
-                &seq_end,
-                &walk_str,
+                sample_id, &hap_index, seq_id, &seq_start, &seq_end, &walk_str,
             ],
             &self.tags,
         )
@@ -345,21 +393,112 @@ impl Walk {
 
 #[cfg(test)]
 mod tests {
-    use crate::{errors::ParseMessageSeverity, gfa::{GfaParser, ParseOptions}};
+    use crate::{
+        errors::{ParseMessageCode, ParseMessageSeverity},
+        gfa::{GFAVersion, GfaParser, ParseOptions, WriteOptions},
+    };
+
+    #[test]
+    fn walks_written_as_paths_in_v1() {
+        let mut gfa = GfaParser::new();
+        let _ = gfa.parse("test/walk.gfa", &ParseOptions::default());
+
+        let walk = gfa.walks().next().unwrap();
+        let path = walk.to_path(&gfa);
+        assert_eq!(path.name, "walk1#1#w1#chr1");
+        assert_eq!(path.steps.len(), walk.walk.len());
+        assert_eq!(path.overlaps.len(), walk.walk.len() - 1);
+
+        let out = std::env::temp_dir().join("parfait_gfa_walks_as_paths.gfa");
+        gfa.write_to_file(
+            out.to_str().unwrap(),
+            GFAVersion::V1,
+            &WriteOptions::default(),
+        )
+        .unwrap();
+        let written = std::fs::read_to_string(&out).unwrap();
+        let _ = std::fs::remove_file(&out);
+
+        assert!(!written.lines().any(|l| l.starts_with('W')));
+        assert!(written.contains("P\twalk1#1#w1#chr1\ts1+,s2+,s5+,s6+,s4+\t*,*,*,*"));
+    }
 
     #[test]
     fn test_working_walks() {
         let mut gfa = GfaParser::new();
 
         let _ = gfa.parse("test/walk.gfa", &ParseOptions::default());
-    
+
         gfa.messages.iter().for_each(|e| {
             e.print_formatted_error();
         });
 
-        let has_errors = gfa.messages.iter().any(|e| e.severity() != ParseMessageSeverity::Warn && e.severity() != ParseMessageSeverity::Info);
-        
+        let has_errors = gfa.messages.iter().any(|e| {
+            e.severity() != ParseMessageSeverity::Warn && e.severity() != ParseMessageSeverity::Info
+        });
+
         // TODO: write real test for walk
         assert!(!has_errors);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn overlapping_walk_ranges() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "H\tVN:Z:1.1",
+            "S\ta\tACGT",
+            "W\tx\t1\tchr1\t0\t10\t>a",
+            "W\tx\t1\tchr1\t20\t30\t>a",
+            "W\tx\t2\tchr1\t0\t30\t>a",
+            "W\tx\t1\tchr1\t5\t25\t>a",
+        ] {
+            let _ = gfa.add_line(line, &options);
+        }
+
+        let overlaps: Vec<_> = gfa
+            .messages
+            .iter()
+            .filter(|m| m.code == ParseMessageCode::OverlappingWalkRange)
+            .collect();
+
+        // the last walk touches both ranges of haplotype 1, and only those
+        assert_eq!(overlaps.len(), 2);
+        assert!(overlaps.iter().all(|m| m.line == 6));
+        assert!(overlaps[0].offender.ends_with("0..10 on line 3"));
+        assert!(overlaps[1].offender.ends_with("20..30 on line 4"));
+    }
+
+    #[test]
+    fn walks_grouped_by_sample() {
+        let mut gfa = GfaParser::new();
+        let options = ParseOptions::default();
+
+        for line in [
+            "S\ta\tA",
+            "W\tHG1\t2\tchr1\t*\t*\t>a",
+            "W\tHG2\t1\tchr1\t*\t*\t>a",
+            "W\tHG1\t1\tchr1\t*\t*\t>a",
+            "W\tHG1\t2\tchr2\t*\t*\t>a",
+        ] {
+            gfa.add_line(line, &options)
+                .expect("test line should parse");
+        }
+
+        let samples = gfa.walks_by_sample();
+        let lines = |sample: &str| {
+            samples[sample]
+                .iter()
+                .map(|w| w.line_no)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(samples.len(), 2);
+        assert_eq!(lines("HG1"), [2, 4, 5]);
+        assert_eq!(lines("HG2"), [3]);
+
+        assert_eq!(gfa.haplotypes_for("HG1"), [1, 2]);
+        assert!(gfa.haplotypes_for("HG3").is_empty());
+    }
+}
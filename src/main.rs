@@ -1,14 +1,15 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use owo_colors::{OwoColorize, Stream, Style};
+use parfait_gfa::gfa::{GfaParser, MissingBridgeOptions, MissingSegmentOptions, ParseOptions};
+use std::fmt::Write;
 use std::io::{self};
-use owo_colors::OwoColorize;
-use parfait_gfa::{errors::ParseMessageSeverity, gfa::{GfaParser, MissingBridgeOptions, MissingSegmentOptions, ParseOptions}};
 
 /// A simple GFA parser application
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
 struct Args {
     /// path to the GFA file
-    #[arg(required = true, index=1)]
+    #[arg(required = true, index = 1)]
     path: String,
 
     /// when the path overlaps field is omitted, don't attempt to derive it from the link overlap
@@ -30,9 +31,8 @@ struct Args {
     #[arg(long, default_value_t = MissingBridgeOptions::HardSkip, verbatim_doc_comment)]
     missing_bridges: MissingBridgeOptions,
 
-    
     /// filter errors by severity (i: info, w: warn, s: severe, e: error, f: fatal)
-    /// 
+    ///
     /// example: `-f iw` will stop info and warnings from being printed
     #[arg(short, long, default_value_t = String::from(""))]
     filter_severity: String,
@@ -42,27 +42,58 @@ struct Args {
     #[arg(long, default_value_t = true)]
     allow_implicit_links: bool,
 
+    /// report every cycle in the graph as an info message
+    #[arg(long, default_value_t = false)]
+    report_cycles: bool,
+
+    /// threads to pre-parse segments on, 0 uses every core
+    #[arg(long, default_value_t = 1)]
+    threads: usize,
+
+    /// fail on any line that isn't a comment or a known record type
+    #[arg(long, default_value_t = false)]
+    strict_line_types: bool,
+
+    /// only allow IUPAC nucleotide codes in sequences
+    #[arg(long, default_value_t = false)]
+    strict_nucleotide_alphabet: bool,
+
+    /// stop parsing after this many messages above warning level
+    #[arg(long)]
+    max_errors: Option<usize>,
+
+    /// give up if fewer than this fraction of the first lines are GFA records, 0 to never give up
+    #[arg(long, default_value_t = 0.5)]
+    min_record_line_fraction: f64,
+
+    /// treat an optional field starting with # as a comment to the end of the line
+    #[arg(long, default_value_t = false)]
+    allow_inline_comments: bool,
+
+    /// prepended to the names of ghost segments
+    #[arg(long, default_value = "")]
+    ghost_name_prefix: String,
+
+    /// prepended to the names of groups without a name
+    #[arg(long, default_value = "anon_")]
+    anon_group_prefix: String,
+
     /// don't print any messages, only the final summary
     #[arg(short, long, default_value_t = false)]
     quiet: bool,
-}
 
-fn print_record_count<T>(name: &str, records: impl Iterator<Item = T>) {
-    let count = records.count();
-    if count > 0 {
-        println!("{name}: {count}");
-    }
+    /// how messages are printed
+    ///     text: coloured blocks for reading
+    ///     json: one JSON object per line for tools
+    #[arg(long, value_enum, default_value_t = MessageFormat::Text, verbatim_doc_comment)]
+    format: MessageFormat,
 }
 
-use si_scale::scale_fn;
-scale_fn!(base_pairs,
-    base: B1000,
-    constraint: UnitAndAbove,
-    mantissa_fmt: "{:.2}",
-    groupings: '_',
-    unit: "bp",
-    doc: "si base pairs"
-);
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum MessageFormat {
+    Text,
+    Json,
+}
 
 fn main() -> io::Result<()> {
     let args = Args::parse();
@@ -78,72 +109,102 @@ fn main() -> io::Result<()> {
         handle_missing_segment: args.missing_segments,
         handle_missing_bridge: args.missing_bridges,
         allow_implicit_links: args.allow_implicit_links,
+        report_cycles: args.report_cycles,
+        threads: args.threads,
+        strict_line_types: args.strict_line_types,
+        capture_comments: false,
+        strict_nucleotide_alphabet: args.strict_nucleotide_alphabet,
+        max_errors: args.max_errors,
+        min_record_line_fraction: args.min_record_line_fraction,
+        allow_inline_comments: args.allow_inline_comments,
+        ghost_name_prefix: args.ghost_name_prefix,
+        anon_group_prefix: args.anon_group_prefix,
+        ..ParseOptions::default()
     };
 
-    let result = gfa.parse(path, &options);
-    
+    let report = gfa.parse_with_report(path, &options);
+
     if !args.quiet {
-        for error in &gfa.messages {
+        for error in report.messages {
             if args.filter_severity.contains(error.severity().to_char()) {
                 continue;
             }
-            error.print_formatted_error();
+            match args.format {
+                MessageFormat::Text => error.print_formatted_error(),
+                MessageFormat::Json => println!("{}", error.to_json()),
+            }
         }
     }
 
-    match result {
-        Ok(_) => {
-            println!(
-                "{}",
-                "[*] [parfait-gfa] Successfully parsed GFA file".to_string()
-                    .on_green()
-                    .bold()
-            );
-        }
+    // the summary is collected first, so it can go to stderr with JSON messages
+    let mut summary = String::new();
 
-        Err(_) => {
-            println!(
-                "{}",
-                "[!] [parfait-gfa] Failed to parse GFA file".to_string()
-                    .on_red()
-                    .bold()
-            );
-        }
+    let stream = match args.format {
+        MessageFormat::Text => Stream::Stdout,
+        MessageFormat::Json => Stream::Stderr,
+    };
+
+    // colours are dropped when the stream isn't a terminal or NO_COLOR is set
+    let paint = |text: String, style: Style| {
+        text.if_supports_color(stream, |t| t.style(style))
+            .to_string()
+    };
+
+    let (status, style) = if report.is_fatal() {
+        (
+            "[!] [parfait-gfa] Failed to parse GFA file",
+            Style::new().on_red().bold(),
+        )
+    } else {
+        (
+            "[*] [parfait-gfa] Successfully parsed GFA file",
+            Style::new().on_green().bold(),
+        )
+    };
+
+    writeln!(summary, "{}", paint(status.to_string(), style)).unwrap();
+
+    for (label, count, style) in [
+        ("[X] fatal", report.fatal, Style::new().magenta()),
+        ("[!] error", report.error, Style::new().bright_red()),
+        ("[#] severe", report.severe, Style::new().red()),
+        ("[?] warning", report.warn, Style::new().yellow()),
+        ("[*] info", report.info, Style::new().blue()),
+    ] {
+        writeln!(summary, "{}", paint(format!("{label}: {count}"), style)).unwrap();
+    }
+
+    writeln!(summary).unwrap();
+
+    write!(summary, "{}", gfa.record_counts()).unwrap();
+
+    // anything the parser made up to patch missing references, rather than read
+    let (ghost_segments, ghost_links) = gfa.ghost_counts();
+    if ghost_segments > 0 || ghost_links > 0 {
+        let style = Style::new().yellow();
+        writeln!(
+            summary,
+            "{}",
+            paint(format!("ghost segments: {ghost_segments}"), style)
+        )
+        .unwrap();
+        writeln!(
+            summary,
+            "{}",
+            paint(format!("ghost links: {ghost_links}"), style)
+        )
+        .unwrap();
     }
 
-    let err_counts = gfa.messages.iter().fold(
-        (0, 0, 0, 0, 0),
-        |(fatal, error, severe, warning, info), e| match e.severity() {
-            ParseMessageSeverity::Fatal => (fatal + 1, error, severe, warning, info),
-            ParseMessageSeverity::Error => (fatal, error + 1, severe, warning, info),
-            ParseMessageSeverity::Severe => (fatal, error, severe + 1, warning, info),
-            ParseMessageSeverity::Warn => (fatal, error, severe, warning + 1, info),
-            ParseMessageSeverity::Info => (fatal, error, severe, warning, info + 1),
-        },
-    );
-
-    println!("{}", format!("[X] fatal: {}", err_counts.0).magenta());
-    println!("{}", format!("[!] error: {}", err_counts.1).bright_red());
-    println!("{}", format!("[#] severe: {}", err_counts.2).red());
-    println!("{}", format!("[?] warning: {}", err_counts.3).yellow());
-    println!("{}", format!("[*] info: {}", err_counts.4).blue());
-
-    println!();
-
-    print_record_count("headers", gfa.headers());
-    print_record_count("segments", gfa.segments());
-    print_record_count("links", gfa.links());
-    print_record_count("jumps", gfa.jumps());
-    print_record_count("containments", gfa.containments());
-    print_record_count("paths", gfa.paths());
-    print_record_count("walks", gfa.walks());
-    print_record_count("edges", gfa.edges());
-    print_record_count("fragments", gfa.fragments());
-    print_record_count("gaps", gfa.gaps());
-
-    println!();    
-
-    println!("length: {} bp ({})", gfa.get_length(), base_pairs(gfa.get_length() as f64));
+    writeln!(summary).unwrap();
+
+    write!(summary, "{}", gfa.stats()).unwrap();
+
+    // keep stdout to the JSON lines so it can be piped into other tools
+    match args.format {
+        MessageFormat::Text => print!("{summary}"),
+        MessageFormat::Json => eprint!("{summary}"),
+    }
 
     Ok(())
 }
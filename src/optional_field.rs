@@ -1,5 +1,8 @@
 use crate::errors::{ParseMessage, ParseMessageCode};
-use std::{collections::HashMap, convert::TryFrom};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryFrom,
+};
 
 #[derive(Debug, Clone)]
 pub enum OptionalFieldValue {
@@ -204,6 +207,19 @@ fn get_reserved_field(tag: &str) -> Option<&'static ReservedField> {
             type_: FieldType::String,
             allowed_records: &[&'S'],
         }),
+        // rGFA reference coordinates
+        "SN" => Some(&ReservedField {
+            type_: FieldType::String,
+            allowed_records: &[&'S'],
+        }),
+        "SO" => Some(&ReservedField {
+            type_: FieldType::Int,
+            allowed_records: &[&'S'],
+        }),
+        "SR" => Some(&ReservedField {
+            type_: FieldType::Int,
+            allowed_records: &[&'S'],
+        }),
         "MQ" => Some(&ReservedField {
             type_: FieldType::Int,
             allowed_records: &[&'L'],
@@ -236,6 +252,8 @@ fn check_optional_field_tag_context(
                 line,
                 code: ParseMessageCode::InvalidOptionalFieldReservedTagType,
                 offender: tag.to_string(),
+                column: None,
+                severity: None,
             });
         }
         if !reserved.allowed_records.contains(&record_type) {
@@ -243,6 +261,8 @@ fn check_optional_field_tag_context(
                 line,
                 code: ParseMessageCode::UnexpectedReservedTagType,
                 offender: tag.to_string(),
+                column: None,
+                severity: None,
             });
         }
         Ok(())
@@ -263,6 +283,8 @@ pub fn parse_optional_field_value(
             line,
             code: ParseMessageCode::OptionalFieldValueEmpty,
             offender: "".to_string(),
+            column: None,
+            severity: None,
         });
         return (None, errors);
     }
@@ -276,6 +298,8 @@ pub fn parse_optional_field_value(
                     line,
                     code: ParseMessageCode::OptionalFieldValueTypeMismatch,
                     offender: value.to_string(),
+                    column: None,
+                    severity: None,
                 });
                 None
             }
@@ -287,6 +311,8 @@ pub fn parse_optional_field_value(
                     line,
                     code: ParseMessageCode::OptionalFieldValueTypeMismatch,
                     offender: value.to_string(),
+                    column: None,
+                    severity: None,
                 });
                 None
             }
@@ -298,6 +324,8 @@ pub fn parse_optional_field_value(
                     line,
                     code: ParseMessageCode::OptionalFieldValueTypeMismatch,
                     offender: value.to_string(),
+                    column: None,
+                    severity: None,
                 });
                 None
             }
@@ -318,6 +346,8 @@ pub fn parse_optional_field_value(
                         line,
                         code: ParseMessageCode::OptionalFieldValueTypeMismatch,
                         offender: chunk.to_string(),
+                        column: None,
+                        severity: None,
                     });
                 }
             }
@@ -329,29 +359,55 @@ pub fn parse_optional_field_value(
     (result, errors)
 }
 
+/// Parses the optional fields of a line, reporting duplicate tags.
 pub fn collect_optional_fields(
     line: usize,
     record_type: &str,
     fields: &[&str],
+) -> (Vec<OptionalField>, Vec<ParseMessage>) {
+    collect_fields(line, record_type, fields, false)
+}
+
+/// Like [`collect_optional_fields`], but a field starting with `#` ends the line: it and
+/// every field after it are treated as a comment and skipped.
+pub fn collect_optional_fields_until_comment(
+    line: usize,
+    record_type: &str,
+    fields: &[&str],
+) -> (Vec<OptionalField>, Vec<ParseMessage>) {
+    collect_fields(line, record_type, fields, true)
+}
+
+fn collect_fields(
+    line: usize,
+    record_type: &str,
+    fields: &[&str],
+    allow_inline_comments: bool,
 ) -> (Vec<OptionalField>, Vec<ParseMessage>) {
     let mut optional_fields = Vec::new();
     let mut errors = Vec::new();
-    let mut used_tags = Vec::new();
+    let mut used_tags = HashSet::with_capacity(fields.len());
 
     let record_type_char = record_type.chars().next().unwrap_or(' ');
 
     // check for duplicate optional fields
     for field in fields {
+        if allow_inline_comments && field.starts_with('#') {
+            break;
+        }
+
         let (parsed_field, field_errors) = parse_optional_field(line, &record_type_char, field);
         if let Some(f) = parsed_field {
-            if used_tags.contains(&f.tag) { // TODO: benchmark against a hashset, should be faster
+            if used_tags.contains(&f.tag) {
                 errors.push(ParseMessage {
                     line,
                     code: ParseMessageCode::DuplicateOptionalField,
                     offender: f.tag.clone(),
+                    column: None,
+                    severity: None,
                 });
             } else {
-                used_tags.push(f.tag.clone());
+                used_tags.insert(f.tag.clone());
                 optional_fields.push(f);
             }
         }
@@ -379,6 +435,8 @@ fn parse_optional_field(
             line,
             code: ParseMessageCode::InvalidOptionalField,
             offender: field.to_string(),
+            column: None,
+            severity: None,
         });
         return (None, errors);
     }
@@ -396,6 +454,8 @@ fn parse_optional_field(
                 line,
                 code: ParseMessageCode::InvalidOptionalFieldTag,
                 offender: tag.to_string(),
+                column: None,
+                severity: None,
             });
         }
     } else {
@@ -403,6 +463,8 @@ fn parse_optional_field(
             line,
             code: ParseMessageCode::InvalidOptionalFieldTag,
             offender: tag.to_string(),
+            column: None,
+            severity: None,
         });
         return (None, errors);
     }
@@ -410,37 +472,40 @@ fn parse_optional_field(
     // type should be a single character and match [AifZJHB]
     let type_char = type_str.chars().next().unwrap_or('Z');
 
-    let ftype;
-    if type_str.len() != 1 {
+    let ftype = if type_str.len() != 1 {
         errors.push(ParseMessage {
             line,
             code: ParseMessageCode::InvalidOptionalFieldType,
             offender: type_str.to_string(),
+            column: None,
+            severity: None,
         });
 
         // fallback to string, don't try to use the first character as the type
-        ftype = FieldType::String;
+        FieldType::String
     } else {
-        ftype = match FieldType::try_from(type_char) {
+        match FieldType::try_from(type_char) {
             Ok(t) => t,
             Err(code) => {
                 errors.push(ParseMessage {
                     line,
                     code,
                     offender: type_str.to_string(),
+                    column: None,
+                    severity: None,
                 });
 
                 // first char is unknown, fallback to string
                 FieldType::String
             }
-        };
-    }
+        }
+    };
 
     // if the tag is reserved, check if it's being used for the right record type
-    if tag.chars().next().unwrap().is_uppercase() {
-        if let Err(e) = check_optional_field_tag_context(line, record_type, ftype, tag) {
-            errors.push(e);
-        }
+    if tag.chars().next().unwrap().is_uppercase()
+        && let Err(e) = check_optional_field_tag_context(line, record_type, ftype, tag)
+    {
+        errors.push(e);
     }
 
     // parse the value based on the type
@@ -459,12 +524,26 @@ fn parse_optional_field(
     }
 }
 
+/// Tag the parser's own flags are written in, see [`crate::gfa::WriteOptions::write_internal_flags`].
+pub(crate) const FLAG_TAG: &str = "PF";
+
+/// The optional fields of a record, along with the flags the parser sets on it (e.g. `ghost`,
+/// `edge`, `gap`). The flags are kept apart from the fields, so they never collide with a
+/// tag from the file and aren't written out unless asked for.
 #[derive(Debug, Clone, Default)]
-pub struct TagMap(pub HashMap<String, OptionalFieldValue>);
+pub struct TagMap {
+    pub tags: HashMap<String, OptionalFieldValue>,
+    flags: HashSet<String>,
+}
 
 impl TagMap {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self::with_flags(HashMap::new(), HashSet::new())
+    }
+
+    /// Creates a tag map from optional fields and the parser's own flags.
+    pub fn with_flags(tags: HashMap<String, OptionalFieldValue>, flags: HashSet<String>) -> Self {
+        Self { tags, flags }
     }
 
     #[inline]
@@ -473,12 +552,12 @@ impl TagMap {
         for t in tags {
             map.insert(t.tag, t.value);
         }
-        Self(map)
+        Self::with_flags(map, HashSet::new())
     }
 
     // creates a Vec<OptionalField> from the TagMap
     pub fn to_vec(&self) -> Vec<OptionalField> {
-        self.0
+        self.tags
             .iter()
             .map(|(k, v)| OptionalField {
                 tag: k.clone(),
@@ -492,43 +571,107 @@ impl TagMap {
     where
         T: for<'a> TryFrom<&'a OptionalFieldValue>,
     {
-        self.0.get(key).and_then(|v| T::try_from(v).ok())
+        self.tags.get(key).and_then(|v| T::try_from(v).ok())
     }
 
     #[inline]
     pub fn contains(&self, key: &str) -> bool {
-        self.0.contains_key(key)
+        self.tags.contains_key(key)
     }
 
-    // uses the PF tag to store data as space delimited strings
-    // TODO: could the tag name be an option?
+    /// Sets one of the parser's own flags, which live next to the tags rather than in one.
+    /// Use [`TagMap::add_flag_with`] to keep flags in a tag of the file instead.
     pub fn add_flag(&mut self, flag: &str) {
-        if let Some(value) = self.0.get_mut("PF") {
-            if let OptionalFieldValue::String(flags) = value {
-                if !flags.split_whitespace().any(|f| f == flag) {
-                    flags.push_str(&format!(" {flag}"));
-                }
+        self.flags.insert(flag.to_string());
+    }
+
+    pub fn has_flag(&self, flag: &str) -> bool {
+        self.flags.contains(flag)
+    }
+
+    pub fn remove_flag(&mut self, flag: &str) {
+        self.flags.remove(flag);
+    }
+
+    /// The parser's own flags, in no particular order.
+    pub fn flags(&self) -> impl Iterator<Item = &str> {
+        self.flags.iter().map(String::as_str)
+    }
+
+    /// Adds a flag to `tag`, which holds flags as a space delimited string.
+    /// Does nothing if the flag is already set, or if `tag` holds something other than a string.
+    pub fn add_flag_with(&mut self, tag: &str, flag: &str) {
+        if let Some(value) = self.tags.get_mut(tag) {
+            if let OptionalFieldValue::String(flags) = value
+                && !flags.split_whitespace().any(|f| f == flag)
+            {
+                flags.push_str(&format!(" {flag}"));
             }
         } else {
-            self.0.insert("PF".to_string(), OptionalFieldValue::String(flag.to_string()));
+            self.tags.insert(
+                tag.to_string(),
+                OptionalFieldValue::String(flag.to_string()),
+            );
         }
     }
 
-    pub fn has_flag(&self, flag: &str) -> bool {
-        if let Some(value) = self.0.get("PF") {
-            if let OptionalFieldValue::String(flags) = value {
-                return flags.split_whitespace().any(|f| f == flag);
-            }
+    pub fn has_flag_with(&self, tag: &str, flag: &str) -> bool {
+        if let Some(OptionalFieldValue::String(flags)) = self.tags.get(tag) {
+            return flags.split_whitespace().any(|f| f == flag);
         }
         false
     }
 
+    /// Removes a flag from `tag`, and the tag itself once it holds no flags.
+    pub fn remove_flag_with(&mut self, tag: &str, flag: &str) {
+        if let Some(OptionalFieldValue::String(flags)) = self.tags.get(tag) {
+            let remaining = flags
+                .split_whitespace()
+                .filter(|f| *f != flag)
+                .collect::<Vec<&str>>()
+                .join(" ");
+
+            if remaining.is_empty() {
+                self.tags.remove(tag);
+            } else {
+                self.tags
+                    .insert(tag.to_string(), OptionalFieldValue::String(remaining));
+            }
+        }
+    }
+
     pub fn add_tag(&mut self, tag: &str, value: OptionalFieldValue) {
-        self.0.insert(tag.to_string(), value);
+        self.tags.insert(tag.to_string(), value);
+    }
+
+    /// Like [`TagMap::add_tag`], but rejects reserved tags with the wrong type or on a record
+    /// type that doesn't allow them (e.g. `VN` on a segment), the same way parsing would.
+    /// The tag map is left unchanged on error.
+    ///
+    /// Example:
+    /// ```
+    /// use parfait_gfa::optional_field::{OptionalFieldValue, TagMap};
+    ///
+    /// let mut tags = TagMap::default();
+    ///
+    /// assert!(tags.set_checked('S', "LN", OptionalFieldValue::Int(4)).is_ok());
+    /// assert!(tags.set_checked('S', "VN", OptionalFieldValue::String("1.0".into())).is_err());
+    /// assert!(!tags.contains("VN"));
+    /// ```
+    pub fn set_checked(
+        &mut self,
+        record_type: char,
+        tag: &str,
+        value: OptionalFieldValue,
+    ) -> Result<(), ParseMessage> {
+        check_optional_field_tag_context(0, &record_type, value.get_field_type(), tag)?;
+        self.add_tag(tag, value);
+
+        Ok(())
     }
 
     pub fn remove_tag(&mut self, tag: &str) {
-        self.0.remove(tag);
+        self.tags.remove(tag);
     }
 }
 
@@ -564,7 +707,10 @@ mod tests {
 
         // wrong type
         let e = check_optional_field_tag_context(1, &'H', FieldType::Int, "VN").unwrap_err();
-        assert_eq!(e.code, ParseMessageCode::InvalidOptionalFieldReservedTagType);
+        assert_eq!(
+            e.code,
+            ParseMessageCode::InvalidOptionalFieldReservedTagType
+        );
 
         // wrong record
         let e2 = check_optional_field_tag_context(1, &'S', FieldType::String, "VN").unwrap_err();
@@ -573,10 +719,61 @@ mod tests {
 
     // tests for parse_optional_field_value()
 
+    #[test]
+    fn test_flags_with_custom_tag() {
+        let mut tags = TagMap::default();
+        tags.add_tag("PF", OptionalFieldValue::String("user".into()));
+
+        tags.add_flag_with("zz", "ghost");
+        tags.add_flag_with("zz", "edge");
+        tags.add_flag_with("zz", "ghost");
+
+        assert!(tags.has_flag_with("zz", "edge"));
+        assert!(!tags.has_flag("ghost"));
+        assert_eq!(tags.get::<String>("zz").unwrap(), "ghost edge");
+
+        tags.remove_flag_with("zz", "ghost");
+        tags.remove_flag_with("zz", "edge");
+        assert!(!tags.contains("zz"));
+
+        // a PF tag from the file isn't mistaken for the parser's flags
+        assert!(!tags.has_flag("user"));
+        tags.add_flag("ghost");
+        assert_eq!(tags.get::<String>("PF").unwrap(), "user");
+    }
+
+    #[test]
+    fn test_set_checked() {
+        let mut tags = TagMap::default();
+
+        let err = tags
+            .set_checked('S', "LN", OptionalFieldValue::String("4".into()))
+            .unwrap_err();
+        assert_eq!(
+            err.code,
+            ParseMessageCode::InvalidOptionalFieldReservedTagType
+        );
+
+        let err = tags
+            .set_checked('S', "VN", OptionalFieldValue::String("1.0".into()))
+            .unwrap_err();
+        assert_eq!(err.code, ParseMessageCode::UnexpectedReservedTagType);
+
+        assert!(
+            tags.set_checked('H', "VN", OptionalFieldValue::String("1.0".into()))
+                .is_ok()
+        );
+        assert!(
+            tags.set_checked('S', "ab", OptionalFieldValue::Int(1))
+                .is_ok()
+        );
+        assert_eq!(tags.tags.len(), 2);
+    }
+
     #[test]
     fn test_parse_optional_field_value_empty() {
         let (opt, errs) = parse_optional_field_value(123, FieldType::String, "");
-        
+
         assert!(opt.is_none()); // this is not a valid optional field, so we should get None
         assert_eq!(errs.len(), 1);
         assert_eq!(errs[0].code, ParseMessageCode::OptionalFieldValueEmpty);
@@ -597,7 +794,7 @@ mod tests {
         // this isn't valid, chars must be a single character
         let (opt2, errs2) = parse_optional_field_value(1, FieldType::Char, "XY");
         assert!(opt2.is_none()); // invalid OF, so None
-        assert_eq!(errs2.len(), 1); 
+        assert_eq!(errs2.len(), 1);
         assert_eq!(
             errs2[0].code,
             ParseMessageCode::OptionalFieldValueTypeMismatch // check if correct error code
@@ -609,7 +806,7 @@ mod tests {
         // valid integer
         let (opt, errs) = parse_optional_field_value(1, FieldType::Int, "123");
         assert!(errs.is_empty());
-        
+
         match opt.unwrap() {
             OptionalFieldValue::Int(i) => assert_eq!(i, 123),
             _ => panic!("expected Int"),
@@ -629,7 +826,7 @@ mod tests {
         // valid float
         let (opt, errs) = parse_optional_field_value(1, FieldType::Float, "1.23");
         assert!(errs.is_empty());
-        
+
         match opt.unwrap() {
             OptionalFieldValue::Float(f) => assert!((f - 1.23).abs() < 1e-6),
             _ => panic!("expected Float"),
@@ -670,7 +867,7 @@ mod tests {
         let data = "hi".as_bytes().to_vec();
         let (opt, errs) = parse_optional_field_value(1, FieldType::ByteArray, "hi");
         assert!(errs.is_empty());
-        
+
         match opt.unwrap() {
             OptionalFieldValue::ByteArray(v) => assert_eq!(v, data),
             _ => panic!("expected ByteArray"),
@@ -683,7 +880,7 @@ mod tests {
         let (opt, errs) = parse_optional_field_value(1, FieldType::NumberArray, "1,2.5,foo");
         assert_eq!(errs.len(), 1);
         assert_eq!(errs[0].offender, "foo"); // should report the invalid part
-        
+
         // the valid parts should still be parsed
         if let OptionalFieldValue::NumberArray(arr) = opt.unwrap() {
             assert_eq!(arr.len(), 2);
@@ -732,8 +929,10 @@ mod tests {
     fn test_parse_optional_field_invalid_type_two_chars() {
         let (opt, errs) = parse_optional_field(1, &'X', "aa:ii:hello");
         // invalid type length
-        assert!(errs.iter()
-                .any(|e| e.code == ParseMessageCode::InvalidOptionalFieldType));
+        assert!(
+            errs.iter()
+                .any(|e| e.code == ParseMessageCode::InvalidOptionalFieldType)
+        );
         // fallback to String, so succeeds with String("hello")
         assert!(opt.is_some());
         let field = opt.unwrap();
@@ -782,4 +981,20 @@ mod tests {
                 .any(|e| e.code == ParseMessageCode::UnexpectedReservedTagType)
         );
     }
+
+    // tests for collect_optional_fields()
+
+    #[test]
+    fn test_collect_optional_fields_inline_comment() {
+        let fields = ["aa:i:1", "# coverage", "bb:i:2"];
+
+        let (tags, errs) = collect_optional_fields_until_comment(1, "S", &fields);
+        assert!(errs.is_empty());
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].tag, "aa");
+
+        let (tags, errs) = collect_optional_fields(1, "S", &fields);
+        assert_eq!(tags.len(), 2);
+        assert_eq!(errs[0].code, ParseMessageCode::InvalidOptionalField);
+    }
 }